@@ -0,0 +1,92 @@
+## Autosave
+
+autosave-resume =
+    The last battle didn't end properly.
+    Resume it? [Y]es / [N]o
+
+## Scenario
+
+scenario-winner = { $faction } wins!
+scenario-no-winner = No winner
+share-code = Share code: { $code }
+
+## Lobby
+
+lobby-sessions = Sessions on the LAN, press their number to join:
+lobby-session = [{ $number }] { $name } ({ $players } players)
+lobby-spectating = Spectating
+lobby-choice = Your faction: { $faction } (Left/Right), budget: { $budget } (Up/Down)
+lobby-host = Host
+lobby-player = Player { $id }
+lobby-roster-entry = { $name }: { $faction }, budget { $budget }
+lobby-launch = Press Enter to launch
+lobby-waiting = Waiting for the host to launch
+
+## Game menu
+
+menu-paused = Paused
+menu-save-entry = [1] Save Game
+menu-load-entry = [2] Load Game
+menu-save-title = Save Game
+menu-slot-name = Slot name: { $name }_
+menu-save-confirm = Press Enter to save
+menu-load-title = Load Game
+menu-no-save = No saved game
+menu-load-prompt = Load Game, press the number of a slot:
+menu-slot = [{ $number }] { $name } - { $scenario }, { $time }, { $fleets }
+menu-sandbox = sandbox
+menu-fleet = { $count } ships of faction { $faction }
+menu-code-entry = [3] Play Share Code
+menu-code-title = Play Share Code
+menu-code = Code: { $code }_
+menu-code-invalid = Invalid code: { $error }
+menu-code-confirm = Press Enter to play it from the start
+menu-close = Press { $key } to close
+
+## Leaderboard
+
+leaderboard-title = Leaderboard: { $scenario }
+leaderboard-all-scenarios = all scenarios
+leaderboard-help = Sort by [1] score [2] wave [3] time [4] date, [F] filter the scenario
+leaderboard-survival = Survival
+leaderboard-race = Race
+leaderboard-entry = { $mode } { $scenario } - score { $score }, wave { $wave }, { $time }{ $won ->
+        [yes] {""}
+       *[no] {" "}(lost)
+    }, seed { $seed }, { $date }
+leaderboard-empty = No result yet
+
+## Mixer
+
+mixer-title = Mixer
+mixer-master = Master
+mixer-music = Music
+mixer-effects = Effects
+mixer-bus = { $selected ->
+        [yes] >
+       *[no] {" "}
+    } [{ $number }] { $bus }: { $volume }%{ $muted ->
+        [yes] {" "}(muted)
+       *[no] {""}
+    }
+mixer-help = [-] / [+] volume, [M] mute
+
+## Achievements
+
+achievement-toast = Achievement unlocked:
+achievement-hundred-kills = Centurion
+achievement-hundred-kills-description = Destroy 100 ships
+achievement-ten-waves = Holdout
+achievement-ten-waves-description = Survive 10 waves
+achievement-flawless-victory = Flawless
+achievement-flawless-victory-description = Win without losing a ship
+achievement-slingshot = Slingshot
+achievement-slingshot-description = Destroy a ship with a shot bent around a black hole
+trophies-title = Trophies ({ $unlocked }/{ $total })
+trophies-entry = { $unlocked ->
+        [yes] [x]
+       *[no] [ ]
+    } { $name }: { $description } ({ $date })
+trophies-kills = Ships destroyed: { $kills }/{ $goal }
+world-event-solar-flare-warning = Solar flare incoming!
+world-event-solar-flare = Solar flare! Targeting systems blinded
@@ -0,0 +1,92 @@
+## Autosave
+
+autosave-resume =
+    La dernière bataille ne s'est pas terminée correctement.
+    La reprendre ? [Y] Oui / [N] Non
+
+## Scenario
+
+scenario-winner = { $faction } gagne !
+scenario-no-winner = Aucun vainqueur
+share-code = Code de partage : { $code }
+
+## Lobby
+
+lobby-sessions = Parties sur le réseau local, appuyez sur leur numéro pour rejoindre :
+lobby-session = [{ $number }] { $name } ({ $players } joueurs)
+lobby-spectating = Spectateur
+lobby-choice = Votre faction : { $faction } (Gauche/Droite), budget : { $budget } (Haut/Bas)
+lobby-host = Hôte
+lobby-player = Joueur { $id }
+lobby-roster-entry = { $name } : { $faction }, budget { $budget }
+lobby-launch = Appuyez sur Entrée pour lancer
+lobby-waiting = En attente du lancement par l'hôte
+
+## Game menu
+
+menu-paused = Pause
+menu-save-entry = [1] Sauvegarder
+menu-load-entry = [2] Charger
+menu-save-title = Sauvegarder
+menu-slot-name = Nom de l'emplacement : { $name }_
+menu-save-confirm = Appuyez sur Entrée pour sauvegarder
+menu-load-title = Charger
+menu-no-save = Aucune partie sauvegardée
+menu-load-prompt = Charger, appuyez sur le numéro d'un emplacement :
+menu-slot = [{ $number }] { $name } - { $scenario }, { $time }, { $fleets }
+menu-sandbox = bac à sable
+menu-fleet = { $count } vaisseaux de la faction { $faction }
+menu-code-entry = [3] Jouer un code de partage
+menu-code-title = Jouer un code de partage
+menu-code = Code : { $code }_
+menu-code-invalid = Code invalide : { $error }
+menu-code-confirm = Appuyez sur Entrée pour le jouer depuis le début
+menu-close = Appuyez sur { $key } pour fermer
+
+## Leaderboard
+
+leaderboard-title = Classement : { $scenario }
+leaderboard-all-scenarios = tous les scénarios
+leaderboard-help = Trier par [1] score [2] vague [3] temps [4] date, [F] filtrer le scénario
+leaderboard-survival = Survie
+leaderboard-race = Course
+leaderboard-entry = { $mode } { $scenario } - score { $score }, vague { $wave }, { $time }{ $won ->
+        [yes] {""}
+       *[no] {" "}(perdu)
+    }, graine { $seed }, { $date }
+leaderboard-empty = Aucun résultat pour l'instant
+
+## Mixer
+
+mixer-title = Mixage
+mixer-master = Général
+mixer-music = Musique
+mixer-effects = Effets
+mixer-bus = { $selected ->
+        [yes] >
+       *[no] {" "}
+    } [{ $number }] { $bus } : { $volume } %{ $muted ->
+        [yes] {" "}(muet)
+       *[no] {""}
+    }
+mixer-help = [-] / [+] volume, [M] couper le son
+
+## Achievements
+
+achievement-toast = Succès débloqué :
+achievement-hundred-kills = Centurion
+achievement-hundred-kills-description = Détruire 100 vaisseaux
+achievement-ten-waves = Résistant
+achievement-ten-waves-description = Survivre à 10 vagues
+achievement-flawless-victory = Sans faute
+achievement-flawless-victory-description = Gagner sans perdre de vaisseau
+achievement-slingshot = Fronde
+achievement-slingshot-description = Détruire un vaisseau d'un tir courbé autour d'un trou noir
+trophies-title = Trophées ({ $unlocked }/{ $total })
+trophies-entry = { $unlocked ->
+        [yes] [x]
+       *[no] [ ]
+    } { $name } : { $description } ({ $date })
+trophies-kills = Vaisseaux détruits : { $kills }/{ $goal }
+world-event-solar-flare-warning = Éruption solaire imminente !
+world-event-solar-flare = Éruption solaire ! Systèmes de visée aveuglés
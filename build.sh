@@ -1,3 +1,3 @@
-cargo build --release --target wasm32-unknown-unknown
+cargo build --release --target wasm32-unknown-unknown --features wasm
 wasm-bindgen --out-dir ./dist/target --target web ./target/wasm32-unknown-unknown/release/gravity-war.wasm
 cp -r assets dist
@@ -0,0 +1,403 @@
+use bevy::{color::palettes::css, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    asteroids::Asteroid,
+    factions::Factions,
+    game_assets::{loaded_texture, GameAssets},
+    scenario::Planet,
+    settings::UserSettings,
+    Faction, Spaceship,
+};
+
+/// Okabe-Ito colors, told apart with protanopia and deuteranopia.
+const RED_GREEN_SAFE: [Srgba; 7] = [
+    Srgba::rgb(0.8, 0.475, 0.655),
+    Srgba::rgb(0.902, 0.624, 0.),
+    Srgba::rgb(0.337, 0.706, 0.914),
+    Srgba::rgb(0.941, 0.894, 0.259),
+    Srgba::rgb(0., 0.447, 0.698),
+    Srgba::rgb(0.835, 0.369, 0.),
+    Srgba::rgb(0., 0.62, 0.451),
+];
+/// Colors told apart with tritanopia.
+const BLUE_YELLOW_SAFE: [Srgba; 5] = [
+    css::GAINSBORO,
+    Srgba::rgb(0.894, 0.102, 0.11),
+    Srgba::rgb(0., 0.808, 0.82),
+    Srgba::rgb(1., 0.561, 0.694),
+    css::GRAY,
+];
+/// Distance of the badges above the ships.
+const BADGE_OFFSET: f32 = 50.;
+const BADGE_RADIUS: f32 = 8.;
+const PATTERN_ALPHA: f32 = 0.4;
+const BACKGROUND: Color = Color::srgb(0.02, 0.02, 0.05);
+const HUD_FONT_SIZE: f32 = 20.;
+const HIGH_CONTRAST_FONT_SIZE: f32 = 28.;
+const HIGH_CONTRAST_LINE_WIDTH: f32 = 3.;
+
+/// Colorblind palettes replacing the faction colors, and cues telling the
+/// factions apart without color: a hull pattern on the default ship sprite, a
+/// badge shape above each ship, the heading line style and a badge symbol
+/// before the faction names.
+///
+/// Both are kept in the [`UserSettings`], the palette is cycled with `F11`
+/// and the cues are toggled with `F12` by default.
+///
+/// The settings also enable a high-contrast mode (bright outlines around the
+/// ships and bodies, black background and larger HUD text) and a
+/// reduced-motion mode, checked by the motion effects: the shaking of the
+/// jinking ships, the tumbling of the debris and the spreading of the EMP
+/// pulses.
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_gizmo_group::<AccessibilityGizmos>()
+            .insert_resource(ClearColor(BACKGROUND))
+            .add_systems(
+                Update,
+                (
+                    (change_appearance, apply_appearance).chain(),
+                    apply_contrast.run_if(resource_changed::<UserSettings>),
+                    scale_hud_text,
+                    update_hull_patterns,
+                    draw_badges.run_if(|factions: Res<Factions>| factions.cues()),
+                    draw_outlines.run_if(high_contrast),
+                )
+                    .chain(),
+            );
+    }
+}
+
+fn high_contrast(settings: Res<UserSettings>) -> bool {
+    settings.accessibility.high_contrast
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Palette {
+    /// Colors of the faction definitions.
+    #[default]
+    Factions,
+    RedGreenSafe,
+    BlueYellowSafe,
+}
+
+impl Palette {
+    /// Colors indexed by faction, `None` for the definition ones.
+    pub fn colors(self) -> Option<&'static [Srgba]> {
+        match self {
+            Palette::Factions => None,
+            Palette::RedGreenSafe => Some(&RED_GREEN_SAFE),
+            Palette::BlueYellowSafe => Some(&BLUE_YELLOW_SAFE),
+        }
+    }
+
+    fn next(self) -> Self {
+        match self {
+            Palette::Factions => Palette::RedGreenSafe,
+            Palette::RedGreenSafe => Palette::BlueYellowSafe,
+            Palette::BlueYellowSafe => Palette::Factions,
+        }
+    }
+}
+
+/// Shape drawn above the ships of a faction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Badge {
+    Circle,
+    Triangle,
+    Square,
+    Diamond,
+    Cross,
+}
+
+impl Badge {
+    pub fn of(faction: Faction) -> Self {
+        const BADGES: [Badge; 5] = [
+            Badge::Circle,
+            Badge::Triangle,
+            Badge::Square,
+            Badge::Diamond,
+            Badge::Cross,
+        ];
+        BADGES[faction.0 as usize % BADGES.len()]
+    }
+
+    /// Text version of the shape, the default font only has ASCII glyphs.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Badge::Circle => "(o)",
+            Badge::Triangle => "/\\",
+            Badge::Square => "[]",
+            Badge::Diamond => "<>",
+            Badge::Cross => "(x)",
+        }
+    }
+}
+
+/// Style of the lines drawn for a faction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    /// Style of the faction when the cues are shown, else solid.
+    pub fn of(faction: Faction, factions: &Factions) -> Self {
+        const STYLES: [LineStyle; 3] = [LineStyle::Solid, LineStyle::Dashed, LineStyle::Dotted];
+        if factions.cues() {
+            STYLES[faction.0 as usize % STYLES.len()]
+        } else {
+            LineStyle::Solid
+        }
+    }
+}
+
+/// Mask over the default ship sprite of a faction.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HullPattern {
+    Plain,
+    Stripes,
+    Dots,
+    Chevrons,
+}
+
+impl HullPattern {
+    pub fn of(faction: Faction) -> Self {
+        const PATTERNS: [HullPattern; 4] = [
+            HullPattern::Plain,
+            HullPattern::Stripes,
+            HullPattern::Dots,
+            HullPattern::Chevrons,
+        ];
+        PATTERNS[faction.0 as usize % PATTERNS.len()]
+    }
+
+    /// Index in the [`GameAssets::hull_patterns`].
+    fn texture_index(self) -> Option<usize> {
+        match self {
+            HullPattern::Plain => None,
+            HullPattern::Stripes => Some(0),
+            HullPattern::Dots => Some(1),
+            HullPattern::Chevrons => Some(2),
+        }
+    }
+}
+
+/// Draws a line from `start` to `end` in the given style.
+pub fn styled_line_2d<T: GizmoConfigGroup>(
+    gizmos: &mut Gizmos<T>,
+    start: Vec2,
+    end: Vec2,
+    color: Color,
+    style: LineStyle,
+) {
+    let (dash, gap) = match style {
+        LineStyle::Solid => {
+            gizmos.line_2d(start, end, color);
+            return;
+        }
+        LineStyle::Dashed => (12., 8.),
+        LineStyle::Dotted => (3., 6.),
+    };
+    let length = start.distance(end);
+    let direction = (end - start).normalize_or_zero();
+    let mut distance = 0.;
+    while distance < length {
+        let dash_end = (distance + dash).min(length);
+        gizmos.line_2d(
+            start + direction * distance,
+            start + direction * dash_end,
+            color,
+        );
+        distance += dash + gap;
+    }
+}
+
+/// Badges and outlines, always drawn unlike the debug channels.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct AccessibilityGizmos;
+
+#[derive(Component)]
+struct HullPatternOverlay;
+
+fn change_appearance(keyboard: Res<ButtonInput<KeyCode>>, mut settings: ResMut<UserSettings>) {
+    if keyboard.just_pressed(settings.input.cycle_palette) {
+        let palette = settings.accessibility.palette.next();
+        info!("Palette: {palette:?}");
+        settings.accessibility.palette = palette;
+    }
+    if keyboard.just_pressed(settings.input.toggle_faction_cues) {
+        let cues = !settings.accessibility.faction_cues;
+        info!("Faction cues: {cues}");
+        settings.accessibility.faction_cues = cues;
+    }
+}
+
+/// Only changes the [`Factions`] when the appearance does, as the ships are
+/// drawn again then.
+fn apply_appearance(settings: Res<UserSettings>, mut factions: ResMut<Factions>) {
+    let accessibility = &settings.accessibility;
+    if factions.palette() != accessibility.palette || factions.cues() != accessibility.faction_cues
+    {
+        factions.set_appearance(accessibility.palette, accessibility.faction_cues);
+    }
+}
+
+fn apply_contrast(
+    settings: Res<UserSettings>,
+    mut clear_color: ResMut<ClearColor>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    let high_contrast = settings.accessibility.high_contrast;
+    clear_color.0 = if high_contrast {
+        Color::BLACK
+    } else {
+        BACKGROUND
+    };
+    let (config, _) = config_store.config_mut::<AccessibilityGizmos>();
+    config.line_width = if high_contrast {
+        HIGH_CONTRAST_LINE_WIDTH
+    } else {
+        GizmoConfig::default().line_width
+    };
+}
+
+fn scale_hud_text(settings: Res<UserSettings>, mut fonts: Query<&mut TextFont>) {
+    let font_size = if settings.accessibility.high_contrast {
+        HIGH_CONTRAST_FONT_SIZE
+    } else {
+        HUD_FONT_SIZE
+    };
+    for mut font in fonts.iter_mut() {
+        if (settings.is_changed() || font.is_added()) && font.font_size != font_size {
+            font.font_size = font_size;
+        }
+    }
+}
+
+/// Covers the default ship sprites with the pattern of their faction.
+fn update_hull_patterns(
+    mut commands: Commands,
+    factions: Res<Factions>,
+    game_assets: Option<Res<GameAssets>>,
+    asset_server: Res<AssetServer>,
+    ships: Query<(Entity, &Faction, Ref<Sprite>, Option<&Children>), With<Spaceship>>,
+    overlays: Query<(), With<HullPatternOverlay>>,
+) {
+    let Some(game_assets) = game_assets else {
+        return;
+    };
+    for (ship, faction, sprite, children) in ships.iter() {
+        if !factions.is_changed() && !sprite.is_changed() {
+            continue;
+        }
+        for child in children.into_iter().flatten() {
+            if overlays.contains(*child) {
+                commands.entity(*child).despawn_recursive();
+            }
+        }
+        if !factions.cues() || sprite.image != game_assets.ship {
+            continue;
+        }
+        let Some(image) = HullPattern::of(*faction)
+            .texture_index()
+            .and_then(|index| game_assets.hull_patterns.get(index))
+            .and_then(|handle| loaded_texture(&asset_server, handle))
+        else {
+            continue;
+        };
+        commands.entity(ship).with_children(|ship| {
+            ship.spawn((
+                HullPatternOverlay,
+                Sprite {
+                    image,
+                    color: Color::BLACK.with_alpha(PATTERN_ALPHA),
+                    custom_size: sprite.custom_size,
+                    ..default()
+                },
+                Transform::from_xyz(0., 0., 0.05),
+            ));
+        });
+    }
+}
+
+fn draw_badges(
+    ships: Query<(&Faction, &Transform), With<Spaceship>>,
+    factions: Res<Factions>,
+    mut gizmos: Gizmos<AccessibilityGizmos>,
+) {
+    for (faction, transform) in ships.iter() {
+        let center = transform.translation.truncate() + Vec2::Y * BADGE_OFFSET;
+        let color = factions.color(*faction);
+        match Badge::of(*faction) {
+            Badge::Circle => {
+                gizmos.circle_2d(Isometry2d::from_translation(center), BADGE_RADIUS, color);
+            }
+            Badge::Triangle => {
+                let vertices = [90., 210., 330., 90.].map(|degrees: f32| {
+                    center + Vec2::from_angle(degrees.to_radians()) * BADGE_RADIUS
+                });
+                gizmos.linestrip_2d(vertices, color);
+            }
+            Badge::Square => {
+                gizmos.rect_2d(
+                    Isometry2d::from_translation(center),
+                    Vec2::splat(BADGE_RADIUS * 1.6),
+                    color,
+                );
+            }
+            Badge::Diamond => {
+                gizmos.rect_2d(
+                    Isometry2d::new(center, Rot2::degrees(45.)),
+                    Vec2::splat(BADGE_RADIUS * 1.4),
+                    color,
+                );
+            }
+            Badge::Cross => {
+                let offset = Vec2::splat(BADGE_RADIUS * 0.7);
+                gizmos.line_2d(center - offset, center + offset, color);
+                let offset = Vec2::new(offset.x, -offset.y);
+                gizmos.line_2d(center - offset, center + offset, color);
+            }
+        }
+    }
+}
+
+/// Outlines the ships in a lighter faction color and the bodies in white.
+fn draw_outlines(
+    ships: Query<(&Faction, &Transform, &Collider), With<Spaceship>>,
+    bodies: Query<(&Transform, &Collider), Or<(With<Planet>, With<Asteroid>)>>,
+    factions: Res<Factions>,
+    mut gizmos: Gizmos<AccessibilityGizmos>,
+) {
+    for (faction, transform, collider) in ships.iter() {
+        let Some(cuboid) = collider.as_cuboid() else {
+            continue;
+        };
+        let isometry = Isometry2d::new(
+            transform.translation.truncate(),
+            Rot2::radians(transform.rotation.to_euler(EulerRot::XYZ).2),
+        );
+        let color = factions.color(*faction).lighter(0.2);
+        gizmos.rect_2d(isometry, cuboid.half_extents() * 2., color);
+    }
+    for (transform, collider) in bodies.iter() {
+        if let Some(ball) = collider.as_ball() {
+            let isometry = Isometry2d::from_translation(transform.translation.truncate());
+            gizmos.circle_2d(isometry, ball.radius(), Color::WHITE);
+        } else if let Some(polygon) = collider.as_convex_polygon() {
+            let points: Vec<Vec2> = polygon
+                .points()
+                .map(|point| transform.transform_point(point.extend(0.)).truncate())
+                .collect();
+            // Closed outline
+            gizmos.linestrip_2d(points.iter().chain(points.first()).copied(), Color::WHITE);
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    leaderboard::format_date,
+    localization::{Localization, LocalizedText},
+    scenario::{Scenario, ScenarioEnded, ScenarioHandle, Waves, WinCondition},
+    settings::UserSettings,
+    Faction, GameState, ShipDestroyed, Spaceship,
+};
+
+const KILLS_GOAL: u32 = 100;
+const WAVES_GOAL: u32 = 10;
+const TOAST_SECS: f32 = 4.;
+/// Faction spawned by left click, when the scenario doesn't tell the player.
+const DEFAULT_PLAYER_FACTION: Faction = Faction(1);
+
+/// Achievements unlocked by the player faction, evaluated from the gameplay
+/// events and kept in `~/.local/share/gravity-war/achievements.ron` (or the
+/// platform equivalent), with the kills counted across the games.
+///
+/// Unlocks are announced with a toast, and the trophy screen is toggled with
+/// `T` by default.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::load())
+            .init_resource::<GameLosses>()
+            .add_event::<AchievementUnlocked>()
+            .add_systems(OnEnter(GameState::InGame), reset_losses)
+            // Also saves the kills
+            .add_systems(OnExit(GameState::InGame), save_achievements)
+            .add_systems(Last, save_achievements.run_if(on_event::<AppExit>))
+            .add_systems(
+                Update,
+                (
+                    (
+                        count_kills.run_if(on_event::<ShipDestroyed>),
+                        check_waves.run_if(resource_changed::<Waves>),
+                        check_victory.run_if(on_event::<ScenarioEnded>),
+                    )
+                        .run_if(in_state(GameState::InGame)),
+                    (spawn_toasts, save_achievements).run_if(on_event::<AchievementUnlocked>),
+                    expire_toasts,
+                    toggle_trophies,
+                    update_trophies.run_if(any_with_component::<TrophyText>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum Achievement {
+    /// Destroyed 100 ships, over all the games.
+    HundredKills,
+    /// Still had ships once 10 waves were spawned.
+    TenWaves,
+    /// Won a scenario without losing a ship.
+    FlawlessVictory,
+    /// Destroyed a ship with a shot bent around a black hole.
+    Slingshot,
+}
+
+const ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement::HundredKills,
+    Achievement::TenWaves,
+    Achievement::FlawlessVictory,
+    Achievement::Slingshot,
+];
+
+impl Achievement {
+    fn name_id(self) -> &'static str {
+        match self {
+            Achievement::HundredKills => "achievement-hundred-kills",
+            Achievement::TenWaves => "achievement-ten-waves",
+            Achievement::FlawlessVictory => "achievement-flawless-victory",
+            Achievement::Slingshot => "achievement-slingshot",
+        }
+    }
+
+    fn description_id(self) -> &'static str {
+        match self {
+            Achievement::HundredKills => "achievement-hundred-kills-description",
+            Achievement::TenWaves => "achievement-ten-waves-description",
+            Achievement::FlawlessVictory => "achievement-flawless-victory-description",
+            Achievement::Slingshot => "achievement-slingshot-description",
+        }
+    }
+}
+
+#[derive(Event)]
+struct AchievementUnlocked(Achievement);
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Unlock {
+    achievement: Achievement,
+    /// Seconds since the Unix epoch.
+    date: u64,
+}
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+struct Achievements {
+    unlocks: Vec<Unlock>,
+    /// Ships destroyed by the player faction.
+    kills: u32,
+}
+
+/// Ships of the player faction destroyed during the current game.
+#[derive(Resource, Default)]
+struct GameLosses(u32);
+
+#[derive(Component)]
+struct Toast(Timer);
+
+#[derive(Component)]
+struct TrophyText;
+
+impl Achievements {
+    /// `None` on platforms without a data directory.
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("gravity-war").join("achievements.ron"))
+    }
+
+    /// Nothing unlocked when the file is missing or invalid.
+    fn load() -> Self {
+        let Some(text) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|err| {
+            warn!("Ignoring invalid achievements: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    fn unlock(&self, achievement: Achievement) -> Option<&Unlock> {
+        self.unlocks
+            .iter()
+            .find(|unlock| unlock.achievement == achievement)
+    }
+
+    /// Sends the event the first time only.
+    fn try_unlock(
+        &mut self,
+        achievement: Achievement,
+        events: &mut EventWriter<AchievementUnlocked>,
+    ) {
+        if self.unlock(achievement).is_some() {
+            return;
+        }
+        info!("Achievement unlocked: {achievement:?}");
+        self.unlocks.push(Unlock {
+            achievement,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        });
+        events.send(AchievementUnlocked(achievement));
+    }
+}
+
+/// The faction surviving the scenario, else the one spawned by left click.
+fn player_faction(handle: &Option<Res<ScenarioHandle>>, scenarios: &Assets<Scenario>) -> Faction {
+    let scenario = handle.as_ref().and_then(|handle| scenarios.get(&handle.0));
+    match scenario.map(|scenario| &scenario.win_condition) {
+        Some(WinCondition::Survive { faction, .. }) => *faction,
+        _ => DEFAULT_PLAYER_FACTION,
+    }
+}
+
+fn reset_losses(mut losses: ResMut<GameLosses>) {
+    losses.0 = 0;
+}
+
+fn count_kills(
+    mut events: EventReader<ShipDestroyed>,
+    mut achievements: ResMut<Achievements>,
+    mut losses: ResMut<GameLosses>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+) {
+    let player = player_faction(&handle, &scenarios);
+    for event in events.read() {
+        if event.victim == player {
+            losses.0 += 1;
+        }
+        if event.killer != Some(player) || event.victim == player {
+            continue;
+        }
+        achievements.kills += 1;
+        if achievements.kills >= KILLS_GOAL {
+            achievements.try_unlock(Achievement::HundredKills, &mut unlocked);
+        }
+        if event.slingshot {
+            achievements.try_unlock(Achievement::Slingshot, &mut unlocked);
+        }
+    }
+}
+
+fn check_waves(
+    waves: Res<Waves>,
+    ships: Query<&Faction, With<Spaceship>>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+) {
+    let player = player_faction(&handle, &scenarios);
+    if waves.0 >= WAVES_GOAL && ships.iter().any(|faction| *faction == player) {
+        achievements.try_unlock(Achievement::TenWaves, &mut unlocked);
+    }
+}
+
+fn check_victory(
+    mut events: EventReader<ScenarioEnded>,
+    losses: Res<GameLosses>,
+    mut achievements: ResMut<Achievements>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+) {
+    let player = player_faction(&handle, &scenarios);
+    for event in events.read() {
+        if event.winner == Some(player) && losses.0 == 0 {
+            achievements.try_unlock(Achievement::FlawlessVictory, &mut unlocked);
+        }
+    }
+}
+
+fn save_achievements(achievements: Res<Achievements>) {
+    if let Err(err) = achievements.save() {
+        error!("Failed to save the achievements: {err}");
+    }
+}
+
+fn spawn_toasts(mut commands: Commands, mut events: EventReader<AchievementUnlocked>) {
+    for AchievementUnlocked(achievement) in events.read() {
+        commands
+            .spawn((
+                Toast(Timer::from_seconds(TOAST_SECS, TimerMode::Once)),
+                Node {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(48.),
+                    right: Val::Px(12.),
+                    column_gap: Val::Px(8.),
+                    ..default()
+                },
+            ))
+            .with_children(|toast| {
+                toast.spawn(LocalizedText::new("achievement-toast"));
+                toast.spawn(LocalizedText::new(achievement.name_id()));
+            });
+    }
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut Toast)>,
+    time: Res<Time<Real>>,
+) {
+    for (entity, mut toast) in toasts.iter_mut() {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn toggle_trophies(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    texts: Query<Entity, With<TrophyText>>,
+) {
+    if !keyboard.just_pressed(settings.input.trophies) {
+        return;
+    }
+
+    if texts.is_empty() {
+        commands.spawn((
+            TrophyText,
+            Text::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+        ));
+    } else {
+        for text in texts.iter() {
+            commands.entity(text).despawn_recursive();
+        }
+    }
+}
+
+fn update_trophies(
+    mut texts: Query<&mut Text, With<TrophyText>>,
+    achievements: Res<Achievements>,
+    localization: Res<Localization>,
+) {
+    let Ok(mut text) = texts.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = vec![localization.format(
+        "trophies-title",
+        &[
+            ("unlocked", achievements.unlocks.len().into()),
+            ("total", ACHIEVEMENTS.len().into()),
+        ],
+    )];
+    lines.extend(ACHIEVEMENTS.iter().map(|achievement| {
+        let date = achievements
+            .unlock(*achievement)
+            .map_or_else(|| "-".to_string(), |unlock| format_date(unlock.date));
+        localization.format(
+            "trophies-entry",
+            &[
+                ("name", localization.text(achievement.name_id()).into()),
+                (
+                    "description",
+                    localization.text(achievement.description_id()).into(),
+                ),
+                (
+                    "unlocked",
+                    if achievements.unlock(*achievement).is_some() {
+                        "yes"
+                    } else {
+                        "no"
+                    }
+                    .into(),
+                ),
+                ("date", date.into()),
+            ],
+        )
+    }));
+    lines.push(localization.format(
+        "trophies-kills",
+        &[
+            ("kills", achievements.kills.into()),
+            ("goal", KILLS_GOAL.into()),
+        ],
+    ));
+    text.0 = lines.join("\n");
+}
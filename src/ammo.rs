@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::{config::ShipStats, factions::Factions, launch, Faction, SimulationSet, Spaceship};
+
+/// Distance of the home of a faction within which its ships resupply.
+const RESUPPLY_RADIUS: f32 = 150.;
+
+/// Finite rounds of the ship weapons, tuned by the `ammo_capacity` of the
+/// [`ShipStats`]. Each bolt, missile, pulse, torpedo or shell spends a round,
+/// beams only build heat.
+///
+/// Ships running out of ammo head back to the home of their faction, see
+/// [`crate::factions::FactionDefinition`], and reload `resupply_rate` rounds
+/// per second there until full.
+pub struct AmmoPlugin;
+
+impl Plugin for AmmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            resupply
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, apply_ammo_stats);
+    }
+}
+
+/// Rounds left in the weapon of a ship.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Ammo {
+    pub current: f32,
+    /// Unlimited at 0.
+    pub max: f32,
+    /// Out of ammo, heading home until full.
+    pub resupplying: bool,
+}
+
+impl Ammo {
+    pub fn is_empty(&self) -> bool {
+        self.max > 0. && self.current < 1.
+    }
+
+    pub fn spend(&mut self) {
+        if self.max > 0. {
+            self.current -= 1.;
+        }
+    }
+}
+
+fn resupply(
+    mut ships: Query<(&Faction, &Transform, &ShipStats, &mut Ammo), With<Spaceship>>,
+    factions: Res<Factions>,
+    time: Res<Time>,
+) {
+    for (faction, transform, stats, mut ammo) in ships.iter_mut() {
+        if ammo.is_empty() {
+            ammo.resupplying = true;
+        }
+        if ammo.current >= ammo.max {
+            ammo.resupplying = false;
+            continue;
+        }
+        let Some(home) = factions.home(*faction) else {
+            continue;
+        };
+        if transform.translation.truncate().distance(home) <= RESUPPLY_RADIUS {
+            ammo.current = (ammo.current + stats.resupply_rate * time.delta_secs()).min(ammo.max);
+        }
+    }
+}
+
+/// Spawned ships start full, edited stats keep the fraction of ammo of the
+/// living ships.
+fn apply_ammo_stats(mut ammos: Query<(&ShipStats, &mut Ammo), Changed<ShipStats>>) {
+    for (stats, mut ammo) in ammos.iter_mut() {
+        ammo.current = if ammo.max > 0. {
+            ammo.current * stats.ammo_capacity / ammo.max
+        } else {
+            stats.ammo_capacity
+        };
+        ammo.max = stats.ammo_capacity;
+    }
+}
@@ -0,0 +1,226 @@
+use std::{
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+};
+
+use bevy::{prelude::*, reflect::GetPath};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tungstenite::Message;
+
+use crate::{
+    config::Configuration,
+    scenario::{ScenarioAction, ScenarioHandle, TriggerAction},
+    Faction, ShipClass, Spaceship, SpawnRequest,
+};
+
+/// Control API enabled with `--api <port>`: a WebSocket server on localhost
+/// accepting one JSON command per message, answered by one JSON message.
+///
+/// ```json
+/// {"command": "spawn_ship", "faction": 1, "class": "fighter", "x": 0, "y": 0}
+/// {"command": "set_config", "path": "defaults.propulsion_force", "value": 80}
+/// {"command": "query_state"}
+/// {"command": "trigger_event", "action": {"Message": "Hello"}}
+/// ```
+///
+/// Answers are `{"ok": true, ...}` or `{"ok": false, "error": "..."}`.
+pub struct ApiPlugin {
+    pub port: u16,
+}
+
+impl Plugin for ApiPlugin {
+    fn build(&self, app: &mut App) {
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, self.port)) {
+            Ok(listener) => listener,
+            Err(err) => {
+                error!("Failed to listen to the API port {}: {err}", self.port);
+                return;
+            }
+        };
+        info!("Control API listening on ws://localhost:{}", self.port);
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || serve_connection(stream, sender));
+            }
+        });
+
+        app.insert_resource(ApiRequests(Mutex::new(receiver)))
+            .add_systems(Update, handle_api_requests);
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum ApiCommand {
+    SpawnShip {
+        faction: Faction,
+        #[serde(default)]
+        class: ShipClass,
+        x: f32,
+        y: f32,
+    },
+    /// Sets a field of the [`Configuration`] by its reflection path.
+    SetConfig {
+        path: String,
+        value: Value,
+    },
+    QueryState,
+    TriggerEvent {
+        action: ScenarioAction,
+    },
+}
+
+struct ApiRequest {
+    command: ApiCommand,
+    reply: Sender<Value>,
+}
+
+/// Commands of all the connections, handled by the game loop.
+#[derive(Resource)]
+struct ApiRequests(Mutex<Receiver<ApiRequest>>);
+
+/// Runs on its own thread until the connection is closed.
+fn serve_connection(stream: TcpStream, requests: Sender<ApiRequest>) {
+    let mut socket = match tungstenite::accept(stream) {
+        Ok(socket) => socket,
+        Err(err) => {
+            warn!("Failed WebSocket handshake: {err}");
+            return;
+        }
+    };
+
+    while let Ok(message) = socket.read() {
+        let Message::Text(text) = message else {
+            continue;
+        };
+        let answer = match serde_json::from_str(&text) {
+            Ok(command) => {
+                let (reply, answer) = mpsc::channel();
+                if requests.send(ApiRequest { command, reply }).is_err() {
+                    // The game exited
+                    return;
+                }
+                answer.recv().unwrap_or_else(|_| error_answer("no answer"))
+            }
+            Err(err) => error_answer(&err.to_string()),
+        };
+        if socket.send(Message::Text(answer.to_string())).is_err() {
+            return;
+        }
+    }
+}
+
+fn error_answer(error: &str) -> Value {
+    json!({ "ok": false, "error": error })
+}
+
+fn handle_api_requests(
+    requests: Res<ApiRequests>,
+    mut spawn_requests: EventWriter<SpawnRequest>,
+    mut triggers: EventWriter<TriggerAction>,
+    mut configuration: ResMut<Configuration>,
+    scenario: Option<Res<ScenarioHandle>>,
+    ships: Query<(Entity, &Faction, &ShipClass, &Transform, &Velocity), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    let Ok(receiver) = requests.0.lock() else {
+        return;
+    };
+    for ApiRequest { command, reply } in receiver.try_iter() {
+        let answer = match command {
+            ApiCommand::SpawnShip {
+                faction,
+                class,
+                x,
+                y,
+            } => {
+                spawn_requests.send(SpawnRequest {
+                    faction,
+                    class,
+                    position: Vec2::new(x, y),
+                });
+                json!({ "ok": true })
+            }
+            ApiCommand::SetConfig { path, value } => {
+                match set_config_value(&mut configuration, &path, &value) {
+                    Ok(()) => json!({ "ok": true }),
+                    Err(err) => error_answer(&err),
+                }
+            }
+            ApiCommand::QueryState => {
+                let ships: Vec<Value> = ships
+                    .iter()
+                    .map(|(entity, faction, class, transform, velocity)| {
+                        json!({
+                            "id": entity.to_bits(),
+                            "faction": faction.0,
+                            "class": class.0,
+                            "x": transform.translation.x,
+                            "y": transform.translation.y,
+                            "vx": velocity.linvel.x,
+                            "vy": velocity.linvel.y,
+                        })
+                    })
+                    .collect();
+                json!({ "ok": true, "elapsed_secs": time.elapsed_secs(), "ships": ships })
+            }
+            ApiCommand::TriggerEvent { action } => {
+                if scenario.is_some() {
+                    triggers.send(TriggerAction(action));
+                    json!({ "ok": true })
+                } else {
+                    error_answer("no scenario is played")
+                }
+            }
+        };
+        // The connection may be closed already
+        let _ = reply.send(answer);
+    }
+}
+
+/// Only numbers and booleans can be set.
+fn set_config_value(
+    configuration: &mut Configuration,
+    path: &str,
+    value: &Value,
+) -> Result<(), String> {
+    let field = configuration
+        .reflect_path_mut(path)
+        .map_err(|err| err.to_string())?;
+
+    let number = || {
+        value
+            .as_f64()
+            .ok_or_else(|| format!("expected a number for {path}"))
+    };
+    if let Some(field) = field.try_downcast_mut::<f32>() {
+        *field = number()? as f32;
+    } else if let Some(field) = field.try_downcast_mut::<Option<f32>>() {
+        *field = if value.is_null() {
+            None
+        } else {
+            Some(number()? as f32)
+        };
+    } else if let Some(field) = field.try_downcast_mut::<u32>() {
+        *field = value
+            .as_u64()
+            .and_then(|value| value.try_into().ok())
+            .ok_or_else(|| format!("expected a positive integer for {path}"))?;
+    } else if let Some(field) = field.try_downcast_mut::<bool>() {
+        *field = value
+            .as_bool()
+            .ok_or_else(|| format!("expected a boolean for {path}"))?;
+    } else {
+        return Err(format!("{path} can't be set"));
+    }
+    Ok(())
+}
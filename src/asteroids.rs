@@ -0,0 +1,217 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    asset::RenderAssetUsages,
+    color::palettes::css,
+    ecs::system::SystemParam,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    damage::{self, DamageEvent, Health},
+    game_assets::{loaded_texture, GameAssets},
+    launch,
+    rng::GameRng,
+    scenario::{AsteroidBelt, ScenarioEntity, ScenarioHandle},
+    sound::Impact,
+    star_system::{self, StarSystem},
+    GameState, SimulationSet,
+};
+
+/// Vertices of the collider hull, at a random distance from the center.
+const VERTICES: usize = 7;
+const HEALTH_PER_SIZE: f32 = 3.;
+/// Fragments of a destroyed asteroid, half its size, unless smaller than
+/// the minimum size.
+const FRAGMENTS: usize = 3;
+const MIN_FRAGMENT_SIZE: f32 = 4.;
+/// Speed of the fragments away from the center of the asteroid.
+const FRAGMENT_SPEED: f32 = 40.;
+
+/// Asteroids are dynamic bodies with a random convex collider, blocking the
+/// shots and colliding with the solid ships. They break into smaller
+/// fragments once their [`Health`] runs out, and the smallest ones vanish.
+///
+/// They are spread in the `asteroid_belts` of the scenario, or the ones of the
+/// generated [`StarSystem`] without scenario.
+pub struct AsteroidsPlugin;
+
+impl Plugin for AsteroidsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, create_asteroid_material)
+            .add_systems(
+                OnEnter(GameState::InGame),
+                spawn_asteroids
+                    .after(star_system::generate_star_system)
+                    .run_if(not(resource_exists::<ScenarioHandle>)),
+            )
+            .add_systems(
+                FixedUpdate,
+                fragment_asteroids
+                    .after(damage::resolve_damage)
+                    .in_set(SimulationSet)
+                    .run_if(any_with_component::<Asteroid>.and(launch::is_authoritative)),
+            );
+    }
+}
+
+#[derive(Component)]
+pub struct Asteroid {
+    /// Radius of the circle around the collider.
+    pub size: f32,
+}
+
+#[derive(Resource)]
+struct AsteroidMaterial(Handle<ColorMaterial>);
+
+/// Resources to draw the asteroids.
+#[derive(SystemParam)]
+pub struct AsteroidArt<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    material: Res<'w, AsteroidMaterial>,
+    game_assets: Res<'w, GameAssets>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+fn create_asteroid_material(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands.insert_resource(AsteroidMaterial(materials.add(Color::from(css::GRAY))));
+}
+
+/// Spawns an asteroid with a random hull fitting in a circle of `size`.
+pub fn spawn_asteroid(
+    commands: &mut Commands,
+    art: &mut AsteroidArt,
+    rng: &mut GameRng,
+    position: Vec2,
+    size: f32,
+    velocity: Vec2,
+) {
+    let mut angles: Vec<f32> = (0..VERTICES).map(|_| rng.gen_range(0.0..TAU)).collect();
+    angles.sort_by(f32::total_cmp);
+    let vertices: Vec<Vec2> = angles
+        .iter()
+        .map(|angle| Vec2::from_angle(*angle) * size * rng.gen_range(0.6..=1.))
+        .collect();
+    let Some(collider) = Collider::convex_hull(&vertices) else {
+        return;
+    };
+    let hull: Vec<Vec2> = collider
+        .as_convex_polygon()
+        .map(|polygon| polygon.points().collect())
+        .unwrap_or(vertices);
+
+    let mut entity_commands = commands.spawn((
+        ScenarioEntity,
+        Asteroid { size },
+        Health::new(size * HEALTH_PER_SIZE),
+        Transform::from_translation(position.extend(-1.)),
+        RigidBody::Dynamic,
+        collider,
+        Velocity::linear(velocity),
+        GravityScale(0.),
+    ));
+    match loaded_texture(&art.asset_server, &art.game_assets.asteroid) {
+        Some(image) => entity_commands.insert(Sprite {
+            image,
+            custom_size: Some(Vec2::splat(size * 2.)),
+            ..default()
+        }),
+        None => entity_commands.insert((
+            Mesh2d(art.meshes.add(polygon_mesh(&hull))),
+            MeshMaterial2d(art.material.0.clone()),
+        )),
+    };
+}
+
+/// Fan of triangles from the center to the vertices of a convex polygon.
+fn polygon_mesh(vertices: &[Vec2]) -> Mesh {
+    let positions: Vec<[f32; 3]> = std::iter::once(Vec2::ZERO)
+        .chain(vertices.iter().copied())
+        .map(|vertex| [vertex.x, vertex.y, 0.])
+        .collect();
+    let count = vertices.len() as u32;
+    let indices = (0..count)
+        .flat_map(|i| [0, i + 1, (i + 1) % count + 1])
+        .collect();
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Spawns `count` asteroids in the ring of the belt.
+pub fn spawn_belt(
+    commands: &mut Commands,
+    art: &mut AsteroidArt,
+    rng: &mut GameRng,
+    belt: &AsteroidBelt,
+) {
+    for _ in 0..belt.count {
+        let angle = rng.gen_range(0.0..TAU);
+        let distance = rng.gen_range(belt.inner_radius..=belt.outer_radius);
+        let position = belt.center + Vec2::from_angle(angle) * distance;
+        let size = rng.gen_range(belt.min_size..=belt.max_size);
+        spawn_asteroid(commands, art, rng, position, size, Vec2::ZERO);
+    }
+}
+
+fn spawn_asteroids(
+    mut commands: Commands,
+    mut art: AsteroidArt,
+    mut rng: ResMut<GameRng>,
+    star_system: Res<StarSystem>,
+) {
+    for belt in &star_system.asteroid_belts {
+        spawn_belt(&mut commands, &mut art, &mut rng, belt);
+    }
+}
+
+/// Damages the asteroids, breaking the destroyed ones into fragments.
+fn fragment_asteroids(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut asteroids: Query<(&Asteroid, &Transform, &Velocity, &mut Health)>,
+    mut art: AsteroidArt,
+    mut rng: ResMut<GameRng>,
+    mut impacts: EventWriter<Impact>,
+) {
+    for event in events.read() {
+        let Ok((asteroid, transform, velocity, mut health)) = asteroids.get_mut(event.target)
+        else {
+            continue;
+        };
+        // Already destroyed by a previous event
+        if health.current <= 0. {
+            continue;
+        }
+        health.current -= event.amount;
+        if health.current > 0. {
+            continue;
+        }
+
+        commands.entity(event.target).despawn_recursive();
+        let position = transform.translation.truncate();
+        impacts.send(Impact { position });
+        let size = asteroid.size / 2.;
+        if size < MIN_FRAGMENT_SIZE {
+            continue;
+        }
+        let first_angle = rng.gen_range(0.0..TAU);
+        for i in 0..FRAGMENTS {
+            let direction = Vec2::from_angle(first_angle + TAU * i as f32 / FRAGMENTS as f32);
+            spawn_asteroid(
+                &mut commands,
+                &mut art,
+                &mut rng,
+                position + direction * size,
+                size,
+                velocity.linvel + direction * FRAGMENT_SPEED,
+            );
+        }
+    }
+}
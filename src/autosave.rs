@@ -0,0 +1,134 @@
+use std::{fs, sync::Mutex, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+
+use crate::{
+    localization::LocalizedText,
+    snapshot::{SnapshotWorld, WorldSnapshot},
+    GameState,
+};
+
+const AUTOSAVE_PATH: &str = "autosave.ron";
+const CAPTURE_PERIOD: Duration = Duration::from_secs(5);
+const WRITE_PERIOD: Duration = Duration::from_secs(60);
+
+/// Last captured snapshot, written to disk by the panic hook.
+static LATEST_SNAPSHOT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keeps an autosave of the battle on disk, updated periodically and on panic,
+/// and removed on a clean exit. When one is found at launch, the player is
+/// offered to resume it.
+pub struct AutosavePlugin;
+
+impl Plugin for AutosavePlugin {
+    fn build(&self, app: &mut App) {
+        install_panic_hook();
+
+        app.add_systems(Startup, offer_resume)
+            .add_systems(
+                Update,
+                (
+                    capture_snapshot.run_if(on_real_timer(CAPTURE_PERIOD)),
+                    write_autosave.run_if(on_real_timer(WRITE_PERIOD)),
+                    answer_resume.run_if(any_with_component::<ResumePrompt>),
+                )
+                    .chain()
+                    // Captured from and restored into the battle, not the lobby
+                    .run_if(in_state(GameState::InGame)),
+            )
+            .add_systems(Last, remove_autosave.run_if(on_event::<AppExit>));
+    }
+}
+
+#[derive(Component)]
+struct ResumePrompt;
+
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        write_latest_snapshot();
+        default_hook(info);
+    }));
+}
+
+fn write_latest_snapshot() {
+    // The panic may have happened while holding the lock
+    let Ok(latest) = LATEST_SNAPSHOT.try_lock() else {
+        return;
+    };
+    if let Some(text) = latest.as_ref() {
+        match fs::write(AUTOSAVE_PATH, text) {
+            Ok(()) => info!("Autosaved to {AUTOSAVE_PATH}"),
+            Err(err) => error!("Failed to autosave to {AUTOSAVE_PATH}: {err}"),
+        }
+    }
+}
+
+fn capture_snapshot(world: SnapshotWorld) {
+    match WorldSnapshot::capture(&world).to_ron() {
+        Ok(text) => {
+            if let Ok(mut latest) = LATEST_SNAPSHOT.lock() {
+                *latest = Some(text);
+            }
+        }
+        Err(err) => error!("Failed to capture autosave: {err}"),
+    }
+}
+
+fn write_autosave() {
+    write_latest_snapshot();
+}
+
+fn remove_autosave() {
+    if fs::metadata(AUTOSAVE_PATH).is_ok() {
+        if let Err(err) = fs::remove_file(AUTOSAVE_PATH) {
+            error!("Failed to remove {AUTOSAVE_PATH}: {err}");
+        }
+    }
+}
+
+fn offer_resume(mut commands: Commands) {
+    if fs::metadata(AUTOSAVE_PATH).is_err() {
+        return;
+    }
+
+    commands.spawn((
+        ResumePrompt,
+        LocalizedText::new("autosave-resume"),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            left: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn answer_resume(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    prompts: Query<Entity, With<ResumePrompt>>,
+    mut world: SnapshotWorld,
+) {
+    let resume = keyboard.just_pressed(KeyCode::KeyY);
+    if !resume && !keyboard.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+
+    if resume {
+        let result = fs::read_to_string(AUTOSAVE_PATH)
+            .map_err(|err| err.to_string())
+            .and_then(|text| WorldSnapshot::from_ron(&text).map_err(|err| err.to_string()));
+        match result {
+            Ok(snapshot) => {
+                snapshot.restore(&mut commands, &mut world);
+                info!("Resumed from {AUTOSAVE_PATH}");
+            }
+            Err(err) => error!("Failed to resume from {AUTOSAVE_PATH}: {err}"),
+        }
+    }
+
+    for prompt in prompts.iter() {
+        commands.entity(prompt).despawn_recursive();
+    }
+}
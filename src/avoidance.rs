@@ -0,0 +1,49 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{asteroids::Asteroid, config::Configuration, scenario::Planet};
+
+/// Planets and asteroids the ships look for ahead of them, casting a ray
+/// along their heading over the `avoidance_distance` of the
+/// [`Configuration`].
+///
+/// When the ray hits one, [`crate::apply_forces`] pushes the ship sideways,
+/// away from the center of the obstacle, the harder the closer it is.
+#[derive(SystemParam)]
+pub struct Obstacles<'w, 's> {
+    rapier_context: ReadDefaultRapierContext<'w, 's>,
+    bodies: Query<'w, 's, &'static Transform, Or<(With<Planet>, With<Asteroid>)>>,
+}
+
+impl Obstacles<'_, '_> {
+    /// Lateral force as a fraction of the thrust, weighted by the
+    /// `avoidance_weight`.
+    pub fn steering(&self, entity: Entity, transform: &Transform, configs: &Configuration) -> Vec2 {
+        if configs.avoidance_distance <= 0. {
+            return Vec2::ZERO;
+        }
+        let origin = transform.translation.truncate();
+        let heading = transform.up().truncate();
+        let is_obstacle = |hit: Entity| self.bodies.contains(hit);
+        let filter = QueryFilter::new()
+            .exclude_collider(entity)
+            .predicate(&is_obstacle);
+        let Some((hit, toi)) =
+            self.rapier_context
+                .cast_ray(origin, heading, configs.avoidance_distance, true, filter)
+        else {
+            return Vec2::ZERO;
+        };
+        let Ok(obstacle) = self.bodies.get(hit) else {
+            return Vec2::ZERO;
+        };
+        let side = heading.perp();
+        // Straight at the center, around by the left
+        let away = if side.dot(origin - obstacle.translation.truncate()) < 0. {
+            -side
+        } else {
+            side
+        };
+        away * (1. - toi / configs.avoidance_distance) * configs.avoidance_weight
+    }
+}
@@ -0,0 +1,203 @@
+use bevy::prelude::*;
+
+use crate::{
+    brain::{Brain, BrainScheduler},
+    config::{AiBackend, Configuration},
+    damage::{Health, Shield},
+    docking::Docking,
+    factions::Factions,
+    gravity::SpatialGrid,
+    launch,
+    personality::Personality,
+    Faction, SimulationSet, Spaceship, Target,
+};
+
+/// Distance within which the ships weigh the allied and enemy forces.
+const BALANCE_RADIUS: f32 = 400.;
+/// Distance of a waypoint at which the ships head for the next one.
+const WAYPOINT_RADIUS: f32 = 60.;
+
+/// Switches the [`AiState`] of the ships from their health, the distance to
+/// their target and the local balance of forces, the health of the allies
+/// against the health of the enemies around.
+///
+/// The state tunes how [`crate::apply_forces`] steers the ship: slower on
+/// patrol, full thrust to engage, away from the enemies around when fleeing,
+/// and toward the allies around when regrouping.
+///
+/// Once fleeing, the ships without a dock to repair at keep away from the
+/// fight until their health or shield recovers, loitering once no enemy is
+/// around.
+///
+/// Like the targeting, the states only switch when the [`BrainScheduler`]
+/// lets the ship think. The factions with the [`AiBackend::Utility`] decide
+/// through [`crate::utility`] instead.
+///
+/// The patrolling ships with a [`PatrolRoute`] cycle through its waypoints.
+pub struct BehaviorPlugin;
+
+impl Plugin for BehaviorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (decide_behavior, advance_patrol_routes)
+                .after(crate::update_targets)
+                .before(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+#[derive(Component, Clone, Copy, Default, PartialEq, Debug)]
+pub enum AiState {
+    /// No enemy close enough, cruising toward the target, or along the
+    /// [`PatrolRoute`].
+    #[default]
+    Patrol,
+    Engage,
+    /// Too damaged, moving away from the center of the enemies around, or
+    /// loitering without any.
+    Flee {
+        from: Option<Vec2>,
+    },
+    /// Outnumbered, joining the allies around.
+    Regroup,
+    /// Circling the target at range, see [`crate::utility`].
+    Orbit,
+    /// Heading for a friendly dock, see [`crate::docking`].
+    Dock,
+}
+
+impl AiState {
+    /// Fraction of the thrust used by the ship.
+    pub fn thrust(self) -> f32 {
+        match self {
+            Self::Patrol => 0.6,
+            Self::Regroup | Self::Orbit => 0.8,
+            Self::Engage | Self::Flee { from: Some(_) } | Self::Dock => 1.,
+            Self::Flee { from: None } => 0.,
+        }
+    }
+}
+
+/// Waypoints cycled through while patrolling, e.g. from the `patrol` of a
+/// scenario fleet.
+#[derive(Component, Clone, Debug)]
+pub struct PatrolRoute {
+    waypoints: Vec<Vec2>,
+    next: usize,
+}
+
+impl PatrolRoute {
+    pub fn new(waypoints: Vec<Vec2>) -> Self {
+        Self { waypoints, next: 0 }
+    }
+
+    /// Waypoint the ship is heading for.
+    pub fn waypoint(&self) -> Option<Vec2> {
+        self.waypoints.get(self.next).copied()
+    }
+}
+
+fn advance_patrol_routes(mut routes: Query<(&Transform, &AiState, &mut PatrolRoute)>) {
+    for (transform, state, mut route) in routes.iter_mut() {
+        let Some(waypoint) = route.waypoint() else {
+            continue;
+        };
+        if *state == AiState::Patrol
+            && transform.translation.truncate().distance(waypoint) <= WAYPOINT_RADIUS
+        {
+            route.next = (route.next + 1) % route.waypoints.len();
+        }
+    }
+}
+
+fn decide_behavior(
+    mut ships: Query<
+        (
+            Entity,
+            &Faction,
+            &Transform,
+            &Health,
+            &Shield,
+            &Target,
+            &Docking,
+            &Personality,
+            &Brain,
+            &mut AiState,
+        ),
+        With<Spaceship>,
+    >,
+    factions: Res<Factions>,
+    configs: Res<Configuration>,
+    scheduler: Res<BrainScheduler>,
+) {
+    let grid = SpatialGrid::new(
+        BALANCE_RADIUS,
+        ships.iter().map(|(_, faction, transform, health, ..)| {
+            (transform.translation.truncate(), (*faction, health.current))
+        }),
+    );
+    for (
+        entity,
+        faction,
+        transform,
+        health,
+        shield,
+        target,
+        docking,
+        personality,
+        brain,
+        mut state,
+    ) in ships.iter_mut()
+    {
+        let backend = factions.ai_backend(*faction).unwrap_or(configs.ai_backend);
+        if backend != AiBackend::StateMachine || !scheduler.is_due(brain) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let (allies, enemies, enemy_positions, enemy_count) = grid
+            .neighbors(position)
+            .filter(|(other_position, _)| other_position.distance(position) <= BALANCE_RADIUS)
+            .fold(
+                (0., 0., Vec2::ZERO, 0),
+                |(allies, enemies, positions, count), (other_position, (other_faction, health))| {
+                    if other_faction == faction {
+                        (allies + health, enemies, positions, count)
+                    } else {
+                        (
+                            allies,
+                            enemies + health,
+                            positions + *other_position,
+                            count + 1,
+                        )
+                    }
+                },
+            );
+        let engaged =
+            target.hostile && target.distance <= configs.engage_distance * personality.aggression;
+        let damaged =
+            health.current < health.max * configs.flee_health_threshold * personality.caution;
+        // Until repaired, or shielded again
+        let recovering = matches!(*state, AiState::Flee { .. })
+            && damaged
+            && (shield.max <= 0. || shield.current < shield.max);
+        // Rather repaired at the dock
+        let new_state = if docking.dock().is_none() && (damaged && engaged || recovering) {
+            AiState::Flee {
+                from: (enemy_count > 0).then(|| enemy_positions / enemy_count as f32),
+            }
+        } else if !engaged {
+            AiState::Patrol
+        } else if allies < enemies * configs.regroup_ratio {
+            AiState::Regroup
+        } else {
+            AiState::Engage
+        };
+        if *state != new_state {
+            debug!("Ship {entity} switches from {:?} to {new_state:?}", *state);
+            *state = new_state;
+        }
+    }
+}
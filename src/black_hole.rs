@@ -0,0 +1,111 @@
+use bevy::{color::palettes::css, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    damage::{self, DamageEvent, LastAttacker},
+    gravity::GravityWell,
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    GameState, SimulationSet, Spaceship,
+};
+
+/// Mass of the black holes per square pixel of their event horizon.
+const DENSITY: f32 = 25.;
+/// Accretion ring around the event horizon, in multiples of its radius.
+const RING_INNER: f32 = 1.3;
+const RING_OUTER: f32 = 2.;
+
+/// Black holes of the `black_holes` of the scenario: extreme gravity wells
+/// destroying the ships crossing their event horizon.
+///
+/// The swallowed ships are credited to the last faction which damaged them,
+/// so pushing an enemy in is a kill.
+pub struct BlackHolePlugin;
+
+impl Plugin for BlackHolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_black_holes)
+            .add_systems(
+                FixedUpdate,
+                swallow_ships
+                    .before(damage::resolve_damage)
+                    .in_set(SimulationSet)
+                    .run_if(any_with_component::<BlackHole>.and(launch::is_authoritative)),
+            );
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct BlackHoleDefinition {
+    pub position: Vec2,
+    pub horizon_radius: f32,
+    /// Mass of the [`GravityWell`], proportional to the area of the horizon
+    /// by default.
+    #[serde(default)]
+    pub mass: Option<f32>,
+}
+
+#[derive(Component)]
+pub struct BlackHole {
+    pub horizon_radius: f32,
+}
+
+fn spawn_black_holes(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let Some(scenario) = handle.and_then(|handle| scenarios.get(&handle.0)) else {
+        return;
+    };
+    let horizon_material = materials.add(Color::BLACK);
+    let ring_material = materials.add(Color::from(css::ORANGE).with_alpha(0.6));
+    for black_hole in &scenario.black_holes {
+        let radius = black_hole.horizon_radius;
+        commands
+            .spawn((
+                ScenarioEntity,
+                BlackHole {
+                    horizon_radius: radius,
+                },
+                GravityWell {
+                    mass: black_hole.mass.unwrap_or(DENSITY * radius.powi(2)),
+                    radius,
+                },
+                Mesh2d(meshes.add(Circle::new(radius))),
+                MeshMaterial2d(horizon_material.clone()),
+                Transform::from_translation(black_hole.position.extend(-1.)),
+            ))
+            .with_child((
+                Mesh2d(meshes.add(Annulus::new(radius * RING_INNER, radius * RING_OUTER))),
+                MeshMaterial2d(ring_material.clone()),
+                // Behind the horizon
+                Transform::from_xyz(0., 0., -0.1),
+            ));
+    }
+}
+
+fn swallow_ships(
+    black_holes: Query<(&BlackHole, &Transform)>,
+    ships: Query<(Entity, &Transform, &LastAttacker), With<Spaceship>>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (black_hole, black_hole_transform) in black_holes.iter() {
+        let center = black_hole_transform.translation.truncate();
+        for (ship, transform, last_attacker) in ships.iter() {
+            if transform.translation.truncate().distance(center) > black_hole.horizon_radius {
+                continue;
+            }
+            damages.send(DamageEvent {
+                target: ship,
+                amount: f32::INFINITY,
+                source: last_attacker.0,
+                slingshot: false,
+                direction: None,
+                attacker: None,
+            });
+        }
+    }
+}
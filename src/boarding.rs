@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::{Configuration, ShipStats},
+    damage::{self, Health},
+    factions::Factions,
+    launch,
+    ships::ShipDefinitions,
+    Faction, ShipClass, SimulationSet, Spaceship, Target,
+};
+
+/// Distance of the enemy ships able to board a ship.
+const BOARDING_RANGE: f32 = 80.;
+
+/// Ships whose health falls below their `boarding_threshold` can be boarded
+/// by an adjacent enemy ship. Once the boarder stayed within range for the
+/// `boarding_secs` of the ship, the ship is captured and joins the faction of
+/// the boarder with its current health.
+///
+/// Boarding stops when the boarder is destroyed, leaves the range or gets
+/// boarded itself, another enemy can then start over.
+pub struct BoardingPlugin;
+
+impl Plugin for BoardingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (start_boarding, capture_ships)
+                .chain()
+                // After the damage of the step
+                .after(damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, draw_boarding);
+    }
+}
+
+/// The ship is being boarded by the ship `boarder`.
+#[derive(Component)]
+pub struct Boarding {
+    pub boarder: Entity,
+    pub timer: Timer,
+}
+
+fn within_range(transform: &Transform, boarder_transform: &Transform) -> bool {
+    transform
+        .translation
+        .distance(boarder_transform.translation)
+        <= BOARDING_RANGE
+}
+
+fn start_boarding(
+    mut commands: Commands,
+    victims: Query<
+        (Entity, &Faction, &Transform, &Health, &ShipStats),
+        (With<Spaceship>, Without<Boarding>),
+    >,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+) {
+    for (entity, faction, transform, health, stats) in victims.iter() {
+        if health.current > health.max * stats.boarding_threshold {
+            continue;
+        }
+        let boarder = ships
+            .iter()
+            .find(|(_, boarder_faction, boarder_transform)| {
+                *boarder_faction != faction && within_range(transform, boarder_transform)
+            });
+        if let Some((boarder, ..)) = boarder {
+            commands.entity(entity).insert(Boarding {
+                boarder,
+                timer: Timer::from_seconds(stats.boarding_secs, TimerMode::Once),
+            });
+        }
+    }
+}
+
+fn capture_ships(
+    mut commands: Commands,
+    mut victims: Query<
+        (
+            Entity,
+            &mut Faction,
+            &ShipClass,
+            &Transform,
+            &mut Boarding,
+            &mut ShipStats,
+            &mut Target,
+            Option<&mut Sprite>,
+        ),
+        With<Spaceship>,
+    >,
+    boarders: Query<(&Faction, &Transform), (With<Spaceship>, Without<Boarding>)>,
+    configs: Res<Configuration>,
+    definitions: ShipDefinitions,
+    factions: Res<Factions>,
+    time: Res<Time>,
+) {
+    for (entity, mut faction, class, transform, mut boarding, mut stats, mut target, sprite) in
+        victims.iter_mut()
+    {
+        let boarder = boarders.get(boarding.boarder).ok();
+        let Some((boarder_faction, _)) =
+            boarder.filter(|(_, boarder_transform)| within_range(transform, boarder_transform))
+        else {
+            commands.entity(entity).remove::<Boarding>();
+            continue;
+        };
+        if !boarding.timer.tick(time.delta()).finished() {
+            continue;
+        }
+
+        // The default texture is tinted with the faction color
+        if let Some(mut sprite) = sprite.filter(|sprite| sprite.color == factions.color(*faction)) {
+            sprite.color = factions.color(*boarder_faction);
+        }
+        *faction = *boarder_faction;
+        *stats = configs.resolve(*faction, class, definitions.get(class));
+        *target = Target::default();
+        commands.entity(entity).remove::<Boarding>();
+    }
+}
+
+/// Links the boarded ships to their boarder, brighter as the capture nears.
+fn draw_boarding(
+    boarded: Query<(&Boarding, &Transform)>,
+    boarders: Query<(&Faction, &Transform), With<Spaceship>>,
+    factions: Res<Factions>,
+    mut gizmos: Gizmos,
+) {
+    for (boarding, transform) in boarded.iter() {
+        let Ok((faction, boarder_transform)) = boarders.get(boarding.boarder) else {
+            continue;
+        };
+        gizmos.line_2d(
+            boarder_transform.translation.truncate(),
+            transform.translation.truncate(),
+            factions
+                .color(*faction)
+                .with_alpha(0.2 + 0.8 * boarding.timer.fraction()),
+        );
+    }
+}
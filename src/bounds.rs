@@ -0,0 +1,101 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::{BoundsMode, Configuration},
+    launch, SimulationSet,
+};
+
+const BOUNDS_COLOR: Color = Color::srgba(1., 1., 1., 0.15);
+
+/// Keeps the moving bodies around the arena with the `bounds_mode` of the
+/// [`Configuration`]: wrapped torus-style to the opposite edge, or pulled
+/// back with an acceleration increasing with their distance to the edge.
+///
+/// Bodies moved along a path, like the satellites and comets, are left alone.
+pub struct BoundsPlugin;
+
+impl Plugin for BoundsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ArenaBounds>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    wrap_bodies.run_if(bounds_mode_is(BoundsMode::Wrap)),
+                    contain_bodies.run_if(bounds_mode_is(BoundsMode::Contain)),
+                )
+                    .after(crate::apply_forces)
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            )
+            .add_systems(
+                Update,
+                draw_bounds.run_if(not(bounds_mode_is(BoundsMode::Unbounded))),
+            );
+    }
+}
+
+/// Edges of the arena.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct ArenaBounds(pub Rect);
+
+impl Default for ArenaBounds {
+    fn default() -> Self {
+        Self(Rect::from_center_half_size(Vec2::ZERO, Vec2::splat(1500.)))
+    }
+}
+
+fn bounds_mode_is(mode: BoundsMode) -> impl Fn(Res<Configuration>) -> bool {
+    move |configs: Res<Configuration>| configs.bounds_mode == mode
+}
+
+/// Bodies moved by their velocity.
+fn is_free(body: &RigidBody) -> bool {
+    matches!(body, RigidBody::Dynamic | RigidBody::KinematicVelocityBased)
+}
+
+fn wrap_bodies(
+    mut bodies: Query<(&RigidBody, &mut Transform), With<Velocity>>,
+    bounds: Res<ArenaBounds>,
+) {
+    let bounds = bounds.0;
+    let size = bounds.size();
+    for (body, mut transform) in bodies.iter_mut() {
+        if !is_free(body) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let wrapped = (position - bounds.min).rem_euclid(size) + bounds.min;
+        if wrapped != position {
+            transform.translation = wrapped.extend(transform.translation.z);
+        }
+    }
+}
+
+fn contain_bodies(
+    mut bodies: Query<(&RigidBody, &Transform, &mut Velocity)>,
+    bounds: Res<ArenaBounds>,
+    configs: Res<Configuration>,
+    time: Res<Time>,
+) {
+    for (body, transform, mut velocity) in bodies.iter_mut() {
+        if !is_free(body) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        // Offset back to the closest point of the arena
+        let inward = position.clamp(bounds.0.min, bounds.0.max) - position;
+        if inward == Vec2::ZERO {
+            continue;
+        }
+        velocity.linvel += inward * configs.containment_force * time.delta_secs();
+    }
+}
+
+fn draw_bounds(bounds: Res<ArenaBounds>, mut gizmos: Gizmos) {
+    gizmos.rect_2d(
+        Isometry2d::from_translation(bounds.0.center()),
+        bounds.0.size(),
+        BOUNDS_COLOR,
+    );
+}
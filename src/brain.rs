@@ -0,0 +1,107 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{prelude::*, utils::Instant};
+
+use crate::{launch, SimulationSet};
+
+/// Spreads expensive AI decisions of [`Brain`] entities across fixed ticks:
+/// targeting and state transitions run every `period_ticks` for each brain,
+/// staggered by its slot, while the steering still runs every tick.
+pub struct BrainPlugin;
+
+impl Plugin for BrainPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BrainScheduler>()
+            .add_observer(register_brain)
+            .add_systems(
+                FixedUpdate,
+                advance_brain_tick
+                    .before(crate::update_targets)
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            );
+    }
+}
+
+/// Entity taking AI decisions through the [`BrainScheduler`].
+#[derive(Component, Default)]
+pub struct Brain {
+    /// Turn within the period, counted as the brains are added by the
+    /// simulation, so that the lockstep peers agree on it unlike on the
+    /// entity indices.
+    slot: u32,
+}
+
+/// Round-robin queue of brains: each tick only the ones due think, one in
+/// `period_ticks` (every 100 ms at 60 Hz), and less if the `time_budget`
+/// runs out, so frame times stay flat whatever the fleet size.
+#[derive(Resource)]
+pub struct BrainScheduler {
+    pub period_ticks: u32,
+    pub time_budget: Duration,
+    tick: u32,
+    next_slot: u32,
+    queue: VecDeque<(Entity, u32)>,
+}
+
+impl Default for BrainScheduler {
+    fn default() -> Self {
+        Self {
+            period_ticks: 6,
+            time_budget: Duration::from_millis(2),
+            tick: 0,
+            next_slot: 0,
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl BrainScheduler {
+    /// Whether the `brain` thinks this tick.
+    pub fn is_due(&self, brain: &Brain) -> bool {
+        self.is_slot_due(brain.slot)
+    }
+
+    fn is_slot_due(&self, slot: u32) -> bool {
+        slot.wrapping_add(self.tick) % self.period_ticks.max(1) == 0
+    }
+
+    /// Calls `think` for the brains due this tick, going once through the
+    /// queue. It returns `false` for entities that can't think anymore (e.g.
+    /// despawned), which are forgotten.
+    ///
+    /// The brains left once the `time_budget` runs out stay at the front of
+    /// the queue, so that they are the first to think at their next turn.
+    pub fn run_batch(&mut self, mut think: impl FnMut(Entity) -> bool) {
+        let start = Instant::now();
+
+        for _ in 0..self.queue.len() {
+            if start.elapsed() > self.time_budget {
+                break;
+            }
+            let Some((entity, slot)) = self.queue.pop_front() else {
+                break;
+            };
+            if !self.is_slot_due(slot) || think(entity) {
+                self.queue.push_back((entity, slot));
+            }
+        }
+    }
+}
+
+fn advance_brain_tick(mut scheduler: ResMut<BrainScheduler>) {
+    scheduler.tick = scheduler.tick.wrapping_add(1);
+}
+
+fn register_brain(
+    trigger: Trigger<OnAdd, Brain>,
+    mut brains: Query<&mut Brain>,
+    mut scheduler: ResMut<BrainScheduler>,
+) {
+    let slot = scheduler.next_slot;
+    scheduler.next_slot = slot.wrapping_add(1);
+    if let Ok(mut brain) = brains.get_mut(trigger.entity()) {
+        brain.slot = slot;
+    }
+    scheduler.queue.push_back((trigger.entity(), slot));
+}
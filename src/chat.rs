@@ -0,0 +1,215 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    f32::consts::TAU,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{
+    factions::Factions, rng::GameRng, ships::ShipDefinitions, Faction, ShipClass, Spaceship,
+    SpawnRequest,
+};
+
+/// Delay before reconnecting to the chat server.
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+/// Time between two commands of the same viewer.
+const VIEWER_COOLDOWN_SECS: f32 = 30.;
+/// Time between two spawns of the queue.
+const SPAWN_INTERVAL_SECS: f32 = 1.;
+/// Commands beyond are dropped.
+const MAX_QUEUED_SPAWNS: usize = 20;
+/// Ships a faction can have over the smallest fleet before its spawns are refused.
+const MAX_FLEET_LEAD: usize = 10;
+/// Distance of the spawns from the home of their faction.
+const SPAWN_SPREAD: f32 = 200.;
+
+/// Chat integration enabled with `--chat <channel>`: joins the IRC channel,
+/// Twitch by default, and spawns the ships asked by the viewers with
+/// `!spawn <faction> [class]`, e.g. `!spawn red fighter`.
+///
+/// The connection is anonymous and read-only. Each viewer can spawn a ship
+/// every 30 seconds, spawns are queued and applied one per second, and
+/// factions far larger than the smallest fleet can't get more ships.
+pub struct ChatPlugin {
+    /// Address of the IRC server, e.g. `irc.chat.twitch.tv:6667`.
+    pub server: String,
+    pub channel: String,
+}
+
+impl Plugin for ChatPlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        let server = self.server.clone();
+        let channel = self.channel.trim_start_matches('#').to_lowercase();
+        thread::spawn(move || loop {
+            match read_chat(&server, &channel, &sender) {
+                // The game exited
+                Ok(()) => return,
+                Err(err) => warn!("Chat connection to {server} lost: {err}"),
+            }
+            thread::sleep(RECONNECT_DELAY);
+        });
+
+        app.insert_resource(ChatCommands(Mutex::new(receiver)))
+            .init_resource::<ChatSpawnQueue>()
+            .add_systems(Update, (queue_chat_spawns, spawn_queued_ships).chain());
+    }
+}
+
+/// `!spawn` command of a viewer.
+struct ChatCommand {
+    viewer: String,
+    /// Name or number of the faction.
+    faction: String,
+    class: Option<String>,
+}
+
+/// Commands read by the chat thread.
+#[derive(Resource)]
+struct ChatCommands(Mutex<Receiver<ChatCommand>>);
+
+#[derive(Resource, Default)]
+struct ChatSpawnQueue {
+    spawns: VecDeque<(Faction, ShipClass)>,
+    /// Real time of the last command of each viewer.
+    last_commands: HashMap<String, f32>,
+    /// Real time of the last spawn.
+    last_spawn: f32,
+}
+
+/// Reads the chat messages until the connection is closed.
+fn read_chat(server: &str, channel: &str, commands: &Sender<ChatCommand>) -> Result<(), String> {
+    let mut stream = TcpStream::connect(server).map_err(|err| err.to_string())?;
+    // Twitch accepts any `justinfan` nickname without password
+    let nickname = format!("justinfan{}", rand::thread_rng().gen_range(10000..100000));
+    write!(
+        stream,
+        "NICK {nickname}\r\nUSER {nickname} 0 * :gravity-war\r\nJOIN #{channel}\r\n"
+    )
+    .map_err(|err| err.to_string())?;
+    info!("Joined the chat #{channel} on {server}");
+
+    let reader = BufReader::new(stream.try_clone().map_err(|err| err.to_string())?);
+    for line in reader.lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        if let Some(token) = line.strip_prefix("PING ") {
+            write!(stream, "PONG {token}\r\n").map_err(|err| err.to_string())?;
+            continue;
+        }
+        let Some(command) = parse_message(&line) else {
+            continue;
+        };
+        if commands.send(command).is_err() {
+            return Ok(());
+        }
+    }
+    Err("closed by the server".to_string())
+}
+
+/// `!spawn` command of a `:<viewer>!<user>@<host> PRIVMSG #<channel> :<text>` line.
+fn parse_message(line: &str) -> Option<ChatCommand> {
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let viewer = prefix.split('!').next()?.to_string();
+    let (_, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let mut words = text.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("!spawn") {
+        return None;
+    }
+    Some(ChatCommand {
+        viewer,
+        faction: words.next()?.to_string(),
+        class: words.next().map(str::to_lowercase),
+    })
+}
+
+fn queue_chat_spawns(
+    commands: Res<ChatCommands>,
+    mut queue: ResMut<ChatSpawnQueue>,
+    factions: Res<Factions>,
+    definitions: ShipDefinitions,
+    time: Res<Time<Real>>,
+) {
+    let Ok(receiver) = commands.0.lock() else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    for ChatCommand {
+        viewer,
+        faction,
+        class,
+    } in receiver.try_iter()
+    {
+        if queue
+            .last_commands
+            .get(&viewer)
+            .is_some_and(|last| now - last < VIEWER_COOLDOWN_SECS)
+        {
+            continue;
+        }
+        let Some(faction) = factions.find(&faction) else {
+            debug!("{viewer} asked for the unknown faction {faction:?}");
+            continue;
+        };
+        let class = class.map_or_else(ShipClass::default, ShipClass);
+        if definitions.get(&class).is_none() {
+            debug!("{viewer} asked for the unknown class {:?}", class.0);
+            continue;
+        }
+        if queue.spawns.len() >= MAX_QUEUED_SPAWNS {
+            debug!("Dropping the spawn of {viewer}, the queue is full");
+            continue;
+        }
+        queue.last_commands.insert(viewer, now);
+        queue.spawns.push_back((faction, class));
+    }
+}
+
+/// Spawns the next queued ship, unless its faction leads the smallest fleet by
+/// too much.
+fn spawn_queued_ships(
+    mut queue: ResMut<ChatSpawnQueue>,
+    mut requests: EventWriter<SpawnRequest>,
+    ships: Query<&Faction, With<Spaceship>>,
+    factions: Res<Factions>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time<Real>>,
+) {
+    let now = time.elapsed_secs();
+    if now - queue.last_spawn < SPAWN_INTERVAL_SECS {
+        return;
+    }
+    let Some((faction, class)) = queue.spawns.pop_front() else {
+        return;
+    };
+    queue.last_spawn = now;
+
+    let mut fleets: HashMap<Faction, usize> = HashMap::from([(faction, 0)]);
+    for ship_faction in ships.iter() {
+        *fleets.entry(*ship_faction).or_default() += 1;
+    }
+    let smallest = fleets.values().copied().min().unwrap_or_default();
+    if fleets[&faction] >= smallest + MAX_FLEET_LEAD {
+        info!(
+            "Refusing a chat spawn for {}, too far ahead",
+            factions.name(faction)
+        );
+        return;
+    }
+
+    let home = factions.home(faction).unwrap_or_default();
+    let offset = Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(0.0..SPAWN_SPREAD);
+    requests.send(SpawnRequest {
+        faction,
+        class,
+        position: home + offset,
+    });
+}
@@ -0,0 +1,215 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    damage::{DamageEvent, Health},
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    star_system::{self, StarSystem},
+    GameState, SimulationSet,
+};
+
+const COMET_COLOR: Color = Color::srgb(0.7, 0.9, 1.);
+/// Seconds between two particles of the tails.
+const TAIL_PERIOD_SECS: f32 = 0.05;
+const TAIL_LIFETIME_SECS: f32 = 1.5;
+/// Newton iterations solving Kepler's equation.
+const KEPLER_ITERATIONS: usize = 5;
+
+/// Comets of the `comets` of the scenario or of the generated
+/// [`StarSystem`], also spawned by the `SpawnComet` scenario action.
+///
+/// Comets travel on elliptical orbits around a focus, faster when closer to
+/// it, pushing the bodies on their way and damaging the ships and asteroids
+/// they touch with their `dps`. Like the satellites, their position only
+/// depends on the time. They leave a tail of fading particles.
+pub struct CometsPlugin;
+
+impl Plugin for CometsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            spawn_comets.after(star_system::generate_star_system),
+        )
+        .add_systems(
+            FixedUpdate,
+            (move_comets, damage_bodies.run_if(launch::is_authoritative))
+                .chain()
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Comet>),
+        )
+        .add_systems(Update, (emit_tails, fade_tails));
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CometDefinition {
+    /// Focus of the ellipse, e.g. the central star.
+    pub focus: Vec2,
+    /// Closest and farthest distances from the focus.
+    pub periapsis: f32,
+    pub apoapsis: f32,
+    /// Direction of the periapsis from the focus, in degrees.
+    #[serde(default)]
+    pub angle: f32,
+    /// Seconds to travel the whole orbit.
+    pub period_secs: f32,
+    pub radius: f32,
+    /// Damage per second to the bodies touched.
+    #[serde(default = "default_dps")]
+    pub dps: f32,
+    /// Fraction of the orbit traveled when spawned, 0 at the periapsis.
+    #[serde(default)]
+    pub phase: f32,
+}
+
+fn default_dps() -> f32 {
+    60.
+}
+
+#[derive(Component)]
+pub struct Comet {
+    definition: CometDefinition,
+    elapsed_secs: f32,
+}
+
+impl Comet {
+    /// Position on the orbit, from Kepler's equation.
+    fn position(&self) -> Vec2 {
+        let orbit = &self.definition;
+        let semi_major_axis = (orbit.periapsis + orbit.apoapsis) / 2.;
+        let eccentricity = (orbit.apoapsis - orbit.periapsis) / (orbit.apoapsis + orbit.periapsis);
+        let semi_minor_axis = semi_major_axis * (1. - eccentricity.powi(2)).sqrt();
+
+        let mean_anomaly = TAU * (orbit.phase + self.elapsed_secs / orbit.period_secs);
+        let mut eccentric_anomaly = mean_anomaly;
+        for _ in 0..KEPLER_ITERATIONS {
+            eccentric_anomaly -=
+                (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+                    / (1. - eccentricity * eccentric_anomaly.cos());
+        }
+
+        let offset = Vec2::new(
+            semi_major_axis * (eccentric_anomaly.cos() - eccentricity),
+            semi_minor_axis * eccentric_anomaly.sin(),
+        );
+        orbit.focus + Vec2::from_angle(orbit.angle.to_radians()).rotate(offset)
+    }
+}
+
+/// Particle of the tail of a comet.
+#[derive(Component)]
+struct TailParticle {
+    secs_left: f32,
+}
+
+/// Spawns a comet at the start of its orbit.
+pub fn spawn_comet(commands: &mut Commands, definition: &CometDefinition) {
+    let comet = Comet {
+        definition: definition.clone(),
+        elapsed_secs: 0.,
+    };
+    commands.spawn((
+        ScenarioEntity,
+        Transform::from_translation(comet.position().extend(-0.5)),
+        comet,
+        RigidBody::KinematicPositionBased,
+        Collider::ball(definition.radius),
+        Sprite::from_color(COMET_COLOR, Vec2::splat(definition.radius * 2.)),
+    ));
+}
+
+fn spawn_comets(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    star_system: Option<Res<StarSystem>>,
+) {
+    let comets = match (handle, &star_system) {
+        (Some(handle), _) => match scenarios.get(&handle.0) {
+            Some(scenario) => scenario.comets.as_slice(),
+            None => return,
+        },
+        (None, Some(star_system)) => star_system.comets.as_slice(),
+        (None, None) => return,
+    };
+    for comet in comets {
+        spawn_comet(&mut commands, comet);
+    }
+}
+
+fn move_comets(mut comets: Query<(&mut Comet, &mut Transform)>, time: Res<Time>) {
+    for (mut comet, mut transform) in comets.iter_mut() {
+        comet.elapsed_secs += time.delta_secs();
+        transform.translation = comet.position().extend(transform.translation.z);
+    }
+}
+
+fn damage_bodies(
+    comets: Query<(&Comet, &Transform)>,
+    bodies: Query<(Entity, &Transform), (With<Health>, Without<Comet>)>,
+    time: Res<Time>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (comet, comet_transform) in comets.iter() {
+        let center = comet_transform.translation.truncate();
+        for (entity, transform) in bodies.iter() {
+            let offset = transform.translation.truncate() - center;
+            if offset.length() > comet.definition.radius {
+                continue;
+            }
+            damages.send(DamageEvent {
+                target: entity,
+                amount: comet.definition.dps * time.delta_secs(),
+                source: None,
+                slingshot: false,
+                direction: Some(offset),
+                attacker: None,
+            });
+        }
+    }
+}
+
+fn emit_tails(
+    mut commands: Commands,
+    comets: Query<(&Comet, &Transform)>,
+    time: Res<Time>,
+    mut since_last: Local<f32>,
+) {
+    *since_last += time.delta_secs();
+    if *since_last < TAIL_PERIOD_SECS {
+        return;
+    }
+    *since_last = 0.;
+    for (comet, transform) in comets.iter() {
+        commands.spawn((
+            ScenarioEntity,
+            TailParticle {
+                secs_left: TAIL_LIFETIME_SECS,
+            },
+            Sprite::from_color(COMET_COLOR, Vec2::splat(comet.definition.radius)),
+            Transform::from_translation(transform.translation.truncate().extend(-0.6)),
+        ));
+    }
+}
+
+/// Shrinks and fades the particles out.
+fn fade_tails(
+    mut commands: Commands,
+    mut particles: Query<(Entity, &mut TailParticle, &mut Sprite, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut particle, mut sprite, mut transform) in particles.iter_mut() {
+        particle.secs_left -= time.delta_secs();
+        if particle.secs_left <= 0. {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let life = particle.secs_left / TAIL_LIFETIME_SECS;
+        sprite.color = COMET_COLOR.with_alpha(0.6 * life);
+        transform.scale = Vec3::splat(life);
+    }
+}
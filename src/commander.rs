@@ -0,0 +1,158 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem::discriminant,
+    time::Duration,
+};
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+
+use crate::{
+    config::Configuration, damage::Health, factions::Factions, influence::InfluenceMap, launch,
+    stations::Station, Faction, GameState, SimulationSet, Spaceship,
+};
+
+const COMMAND_PERIOD: Duration = Duration::from_secs(1);
+/// Distance of the enemy fleets from a station at which its faction defends it.
+const DEFEND_RADIUS: f32 = 600.;
+
+/// Strategic layer between the spawns and the steering of the ships: every
+/// second, the commander of each faction weighs the fleets, their centers
+/// and the stations, and issues an [`Order`] to its ships.
+///
+/// A faction retreats home when its fleet has less than `retreat_ratio` of
+/// the strength of the enemies, defends its stations threatened by an enemy
+/// fleet, and otherwise attacks the closest enemy station, or the weakest
+/// flank of the enemy fleets on the [`InfluenceMap`]. The patrolling ships
+/// head for the objective of the order, and the retreating ones break off
+/// the fight, see [`crate::update_targets`].
+pub struct CommanderPlugin;
+
+impl Plugin for CommanderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Orders>()
+            .add_systems(OnEnter(GameState::InGame), reset_orders)
+            .add_systems(
+                FixedUpdate,
+                issue_orders
+                    .before(crate::update_targets)
+                    .in_set(SimulationSet)
+                    .run_if(on_timer(COMMAND_PERIOD).and(launch::is_authoritative)),
+            );
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Order {
+    /// Toward an enemy station or fleet.
+    Attack(Vec2),
+    /// Back to a threatened station.
+    Defend(Vec2),
+    /// Back home, out of the fight.
+    Retreat(Vec2),
+}
+
+impl Order {
+    pub fn objective(self) -> Vec2 {
+        match self {
+            Self::Attack(objective) | Self::Defend(objective) | Self::Retreat(objective) => {
+                objective
+            }
+        }
+    }
+}
+
+/// Current [`Order`] of each faction.
+#[derive(Resource, Default, Debug)]
+pub struct Orders(HashMap<Faction, Order>);
+
+impl Orders {
+    pub fn get(&self, faction: Faction) -> Option<Order> {
+        self.0.get(&faction).copied()
+    }
+}
+
+/// Ships of a faction as seen by its commander.
+#[derive(Default)]
+struct Fleet {
+    strength: f32,
+    positions: Vec2,
+    count: usize,
+}
+
+impl Fleet {
+    fn center(&self) -> Vec2 {
+        self.positions / self.count.max(1) as f32
+    }
+}
+
+fn reset_orders(mut orders: ResMut<Orders>) {
+    orders.0.clear();
+}
+
+fn issue_orders(
+    mut orders: ResMut<Orders>,
+    ships: Query<(&Faction, &Transform, &Health), With<Spaceship>>,
+    stations: Query<(&Faction, &Transform), With<Station>>,
+    factions: Res<Factions>,
+    influence: Res<InfluenceMap>,
+    configs: Res<Configuration>,
+) {
+    // Sorted, so that the lockstep peers sum the strengths in the same order
+    let mut fleets: BTreeMap<Faction, Fleet> = BTreeMap::new();
+    for (faction, transform, health) in ships.iter() {
+        let fleet = fleets.entry(*faction).or_default();
+        fleet.strength += health.current;
+        fleet.positions += transform.translation.truncate();
+        fleet.count += 1;
+    }
+    let stations: Vec<(Faction, Vec2)> = stations
+        .iter()
+        .map(|(faction, transform)| (*faction, transform.translation.truncate()))
+        .collect();
+
+    let total_strength: f32 = fleets.values().map(|fleet| fleet.strength).sum();
+    let mut new_orders = HashMap::new();
+    for (&faction, fleet) in &fleets {
+        let center = fleet.center();
+        let enemy_fleets = || {
+            fleets
+                .iter()
+                .filter(move |(other, _)| **other != faction)
+                .map(|(_, enemy)| enemy.center())
+        };
+        let enemy_strength = total_strength - fleet.strength;
+        let home = factions.home(faction);
+        let threatened_station = stations
+            .iter()
+            .filter(|(station_faction, _)| *station_faction == faction)
+            .map(|(_, station)| *station)
+            .find(|station| enemy_fleets().any(|enemy| enemy.distance(*station) <= DEFEND_RADIUS));
+        let closest = |positions: &mut dyn Iterator<Item = Vec2>| {
+            positions.min_by(|a, b| a.distance(center).total_cmp(&b.distance(center)))
+        };
+        let order = match home {
+            Some(home) if fleet.strength < enemy_strength * configs.retreat_ratio => {
+                Some(Order::Retreat(home))
+            }
+            _ => threatened_station.map(Order::Defend).or_else(|| {
+                closest(
+                    &mut stations
+                        .iter()
+                        .filter(|(station_faction, _)| *station_faction != faction)
+                        .map(|(_, station)| *station),
+                )
+                // Through the weakest flank of the enemy fleets
+                .or_else(|| influence.weakest_flank(faction, center))
+                .map(Order::Attack)
+            }),
+        };
+        if let Some(order) = order {
+            let previous = orders.get(faction).map(|order| discriminant(&order));
+            if previous != Some(discriminant(&order)) {
+                debug!("Commander of {faction:?} orders {order:?}");
+            }
+            new_orders.insert(faction, order);
+        }
+    }
+    orders.0 = new_orders;
+}
@@ -0,0 +1,485 @@
+use bevy::prelude::*;
+#[cfg(feature = "dev-tools")]
+use bevy_inspector_egui::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    settings::UserSettings,
+    ships::{ShipDefinition, ShipDefinitions},
+    utility::UtilityWeights,
+    weapons::WeaponKind,
+    Faction, ShipClass, Spaceship,
+};
+
+/// Layered ship tuning: global defaults, then the [`ShipDefinition`] of the class,
+/// then per-faction overrides, then per-class overrides.
+/// Resolved into a [`ShipStats`] per ship.
+///
+/// Also loaded as an asset from [`CONFIGURATION_PATH`], see [`apply_configuration_file`].
+#[derive(Reflect, Resource, Asset, Serialize, Deserialize, Clone)]
+#[reflect(Resource)]
+#[serde(default)]
+#[cfg_attr(
+    feature = "dev-tools",
+    derive(InspectorOptions),
+    reflect(InspectorOptions)
+)]
+pub struct Configuration {
+    pub defaults: ShipStats,
+    pub factions: Vec<FactionOverride>,
+    pub classes: Vec<ClassOverride>,
+    pub collider_mode: ColliderMode,
+    /// Damage per unit of relative speed when [`ColliderMode::Solid`] ships collide.
+    pub ramming_damage: f32,
+    /// Distance of the bodies pushed and damaged by the explosion of a destroyed ship.
+    pub explosion_radius: f32,
+    /// Damage at the center of the explosion, decreasing linearly to the radius.
+    pub explosion_damage: f32,
+    /// Impulse at the center of the explosion, decreasing linearly to the radius.
+    pub explosion_impulse: f32,
+    /// Torpedoes are aimed to reach their target after being bent by the planets.
+    pub torpedo_aim_lead: bool,
+    /// Ships attract each other like small gravity wells.
+    pub ship_gravity: bool,
+    /// Mass of the ships when `ship_gravity` is enabled, see [`crate::gravity::GravityWell`].
+    pub ship_mass: f32,
+    /// Ships head for a wormhole when it's a shortcut to their target.
+    pub wormhole_targeting: bool,
+    /// Force pushing the ships away from the stars, see [`crate::solar_wind`].
+    pub solar_wind: f32,
+    /// What happens to the bodies leaving the [`crate::bounds::ArenaBounds`].
+    pub bounds_mode: BoundsMode,
+    /// Acceleration toward the arena per pixel beyond its bounds, with [`BoundsMode::Contain`].
+    pub containment_force: f32,
+    /// Fraction of their health below which the ships head for a dock, see [`crate::docking`].
+    pub dock_health_threshold: f32,
+    /// Health regained per second by the docked ships.
+    pub dock_repair_rate: f32,
+    /// Mean seconds between two [`crate::world_events::WorldEvent`]s, none at 0.
+    pub world_event_secs: f32,
+    /// Distance within which the allies steer together, see [`crate::flocking::Flock`].
+    pub flocking_radius: f32,
+    /// Distance below which the allies push each other away.
+    pub separation_distance: f32,
+    pub separation_weight: f32,
+    pub alignment_weight: f32,
+    pub cohesion_weight: f32,
+    /// Distance ahead within which the ships steer around the planets and asteroids, see [`crate::avoidance`].
+    pub avoidance_distance: f32,
+    pub avoidance_weight: f32,
+    /// Lateral force of the ships jinking under fire, see [`crate::evasion`], none at 0.
+    pub evasion_weight: f32,
+    /// Distance between the ships flying in formation, see [`crate::formation`].
+    pub formation_spacing: f32,
+    pub formation_weight: f32,
+    /// Maximum number of ships in a squad, with its leader, see [`crate::squads`].
+    pub squad_size: usize,
+    /// Deviation of the random [`crate::personality::Personality`] of the ships, all the same at 0.
+    pub personality_spread: f32,
+    /// Distance of the hostile target within which the ships engage, see [`crate::behavior::AiState`].
+    pub engage_distance: f32,
+    /// Fraction of their health below which the engaged ships flee, until their shield recovers.
+    pub flee_health_threshold: f32,
+    /// Ratio of allied to enemy health around below which the ships regroup.
+    pub regroup_ratio: f32,
+    /// Ratio of the fleet to the enemy fleets strength below which a faction retreats, see [`crate::commander`].
+    pub retreat_ratio: f32,
+    /// Ships slingshot around the wells and lure their pursuers into the hazards, see [`crate::tactics`].
+    pub gravity_tactics: bool,
+    /// How the ships decide, unless their faction has its own `ai_backend`.
+    pub ai_backend: AiBackend,
+    /// Weights of the considerations of the [`AiBackend::Utility`], see [`crate::utility`].
+    pub utility_weights: UtilityWeights,
+    /// Ships aim where their bolts meet the moving enemies, see [`crate::targeting`].
+    pub aim_lead: bool,
+}
+
+impl Default for Configuration {
+    fn default() -> Self {
+        Self {
+            defaults: ShipStats::default(),
+            factions: vec![],
+            classes: vec![],
+            collider_mode: ColliderMode::default(),
+            ramming_damage: 0.2,
+            explosion_radius: 120.,
+            explosion_damage: 40.,
+            explosion_impulse: 150.,
+            torpedo_aim_lead: true,
+            ship_gravity: false,
+            ship_mass: 300.,
+            wormhole_targeting: true,
+            solar_wind: 0.,
+            bounds_mode: BoundsMode::default(),
+            containment_force: 2.,
+            dock_health_threshold: 0.3,
+            dock_repair_rate: 10.,
+            world_event_secs: 90.,
+            flocking_radius: 150.,
+            separation_distance: 60.,
+            separation_weight: 0.8,
+            alignment_weight: 0.2,
+            cohesion_weight: 0.1,
+            avoidance_distance: 200.,
+            avoidance_weight: 1.,
+            evasion_weight: 0.6,
+            formation_spacing: 80.,
+            formation_weight: 0.5,
+            squad_size: 4,
+            personality_spread: 0.2,
+            engage_distance: 600.,
+            flee_health_threshold: 0.25,
+            regroup_ratio: 0.5,
+            retreat_ratio: 0.25,
+            gravity_tactics: true,
+            ai_backend: AiBackend::default(),
+            utility_weights: UtilityWeights::default(),
+            aim_lead: true,
+        }
+    }
+}
+
+/// How the ships collide with each other.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ColliderMode {
+    /// Ships pass through each other.
+    #[default]
+    Sensor,
+    /// Ships bounce off each other and take ramming damage.
+    Solid,
+}
+
+/// How the bodies are kept in the arena.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum BoundsMode {
+    /// Bodies fly off forever.
+    Unbounded,
+    /// Bodies leaving the arena enter it again from the opposite edge.
+    Wrap,
+    /// Bodies beyond the edges are pulled back, harder the farther they are.
+    #[default]
+    Contain,
+}
+
+/// How the ships choose their [`crate::behavior::AiState`].
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum AiBackend {
+    /// Transitions on health, distance and balance of forces thresholds, see [`crate::behavior`].
+    #[default]
+    StateMachine,
+    /// Best scored action from weighted considerations, see [`crate::utility`].
+    Utility,
+}
+
+impl Configuration {
+    pub fn resolve(
+        &self,
+        faction: Faction,
+        class: &ShipClass,
+        definition: Option<&ShipDefinition>,
+    ) -> ShipStats {
+        let definition_stats = definition.map(|definition| StatsOverride {
+            rotation_force: Some(definition.turn_rate),
+            propulsion_force: Some(definition.thrust),
+            rotation_max: Some(definition.max_turn),
+            weapon: definition
+                .weapons
+                .iter()
+                .find_map(|name| WeaponKind::from_name(name)),
+            ..default()
+        });
+        let faction_overrides = self
+            .factions
+            .iter()
+            .filter(|o| o.faction == faction.0)
+            .map(|o| &o.stats);
+        let class_overrides = self
+            .classes
+            .iter()
+            .filter(|o| o.class == *class)
+            .map(|o| &o.stats);
+
+        definition_stats
+            .iter()
+            .chain(faction_overrides)
+            .chain(class_overrides)
+            .fold(self.defaults, |stats, stats_override| {
+                stats_override.apply(stats)
+            })
+    }
+}
+
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, Copy, Debug)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct ShipStats {
+    pub rotation_force: f32,
+    pub propulsion_force: f32,
+    /// Distance up to which the weapons deal their full damage.
+    pub aim_distance: f32,
+    pub rotation_max: f32,
+    /// Seconds between two shots.
+    pub fire_cooldown: f32,
+    pub projectile_speed: f32,
+    /// Health taken from the ships hit by a projectile.
+    pub projectile_damage: f32,
+    pub weapon: WeaponKind,
+    /// Distance of the targets the ship fires at, the damage of its weapons
+    /// decreasing linearly from the `aim_distance`.
+    pub weapon_range: f32,
+    /// Damage per second of a [`WeaponKind::Beam`].
+    pub beam_dps: f32,
+    /// Heat of a shot, the weapon overheats at 1, see [`crate::weapons::Heat`].
+    pub heat_per_shot: f32,
+    /// Heat vented per second.
+    pub heat_dissipation: f32,
+    /// Factor of the venting while the ship isn't thrusting.
+    pub idle_venting_factor: f32,
+    /// Seconds the weapon can't fire once overheated.
+    pub overheat_secs: f32,
+    /// Damage absorbed before the health, see [`crate::damage::Shield`].
+    pub shield_max: f32,
+    /// Shield regained per second.
+    pub shield_recharge_rate: f32,
+    /// Seconds without damage before the shield recharges.
+    pub shield_recharge_delay: f32,
+    /// Distance of the enemy munitions shot down by the point defense, none at 0.
+    pub point_defense_range: f32,
+    /// Seconds between two interceptions.
+    pub point_defense_cooldown: f32,
+    /// Distance of the enemy ships disabled by a [`WeaponKind::Emp`] pulse.
+    pub emp_radius: f32,
+    /// Seconds the ships hit by an EMP pulse drift without propulsion nor targeting.
+    pub emp_secs: f32,
+    /// Distance of the enemy ships locked by the tractor beam, none at 0.
+    pub tractor_range: f32,
+    /// Impulse per second pulling the locked ship toward the ship.
+    pub tractor_force: f32,
+    /// Seconds of pull of a full tractor beam energy.
+    pub tractor_duration_secs: f32,
+    /// Damage of a [`WeaponKind::Torpedoes`] hit.
+    pub torpedo_damage: f32,
+    /// Launch speed of the torpedoes, relative to the ship.
+    pub torpedo_speed: f32,
+    /// Rounds of the weapon, unlimited at 0, see [`crate::ammo::Ammo`].
+    pub ammo_capacity: f32,
+    /// Rounds reloaded per second at the home of the faction.
+    pub resupply_rate: f32,
+    /// Fraction of the damage absorbed by the armor on hits to the front.
+    pub front_armor: f32,
+    /// Damage multiplier of the critical hits to the rear.
+    pub critical_multiplier: f32,
+    /// Radius of the bursts of a [`WeaponKind::Flak`] shell.
+    pub flak_radius: f32,
+    /// Damage per second to the enemy ships within a flak burst.
+    pub flak_dps: f32,
+    /// Fraction of health below which the enemies can board the ship, see [`crate::boarding`].
+    pub boarding_threshold: f32,
+    /// Seconds for the enemies to capture the ship once boarding.
+    pub boarding_secs: f32,
+}
+
+impl Default for ShipStats {
+    fn default() -> Self {
+        Self {
+            rotation_force: 0.02,
+            propulsion_force: 50.,
+            aim_distance: 250.,
+            rotation_max: 0.05,
+            fire_cooldown: 0.5,
+            projectile_speed: 600.,
+            projectile_damage: 10.,
+            weapon: WeaponKind::Bolts,
+            weapon_range: 500.,
+            beam_dps: 30.,
+            heat_per_shot: 0.1,
+            heat_dissipation: 0.15,
+            idle_venting_factor: 2.,
+            overheat_secs: 3.,
+            shield_max: 50.,
+            shield_recharge_rate: 10.,
+            shield_recharge_delay: 3.,
+            point_defense_range: 0.,
+            point_defense_cooldown: 0.5,
+            emp_radius: 150.,
+            emp_secs: 3.,
+            tractor_range: 0.,
+            tractor_force: 200.,
+            tractor_duration_secs: 4.,
+            torpedo_damage: 60.,
+            torpedo_speed: 300.,
+            ammo_capacity: 40.,
+            resupply_rate: 5.,
+            front_armor: 0.3,
+            critical_multiplier: 1.5,
+            flak_radius: 80.,
+            flak_dps: 60.,
+            boarding_threshold: 0.25,
+            boarding_secs: 5.,
+        }
+    }
+}
+
+/// Fields left to `None` keep the value of the previous layer.
+#[derive(Reflect, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct StatsOverride {
+    pub rotation_force: Option<f32>,
+    pub propulsion_force: Option<f32>,
+    pub aim_distance: Option<f32>,
+    pub rotation_max: Option<f32>,
+    pub fire_cooldown: Option<f32>,
+    pub projectile_speed: Option<f32>,
+    pub projectile_damage: Option<f32>,
+    pub weapon: Option<WeaponKind>,
+    pub weapon_range: Option<f32>,
+    pub beam_dps: Option<f32>,
+    pub heat_per_shot: Option<f32>,
+    pub heat_dissipation: Option<f32>,
+    pub idle_venting_factor: Option<f32>,
+    pub overheat_secs: Option<f32>,
+    pub shield_max: Option<f32>,
+    pub shield_recharge_rate: Option<f32>,
+    pub shield_recharge_delay: Option<f32>,
+    pub point_defense_range: Option<f32>,
+    pub point_defense_cooldown: Option<f32>,
+    pub emp_radius: Option<f32>,
+    pub emp_secs: Option<f32>,
+    pub tractor_range: Option<f32>,
+    pub tractor_force: Option<f32>,
+    pub tractor_duration_secs: Option<f32>,
+    pub torpedo_damage: Option<f32>,
+    pub torpedo_speed: Option<f32>,
+    pub ammo_capacity: Option<f32>,
+    pub resupply_rate: Option<f32>,
+    pub front_armor: Option<f32>,
+    pub critical_multiplier: Option<f32>,
+    pub flak_radius: Option<f32>,
+    pub flak_dps: Option<f32>,
+    pub boarding_threshold: Option<f32>,
+    pub boarding_secs: Option<f32>,
+}
+
+impl StatsOverride {
+    fn apply(&self, stats: ShipStats) -> ShipStats {
+        ShipStats {
+            rotation_force: self.rotation_force.unwrap_or(stats.rotation_force),
+            propulsion_force: self.propulsion_force.unwrap_or(stats.propulsion_force),
+            aim_distance: self.aim_distance.unwrap_or(stats.aim_distance),
+            rotation_max: self.rotation_max.unwrap_or(stats.rotation_max),
+            fire_cooldown: self.fire_cooldown.unwrap_or(stats.fire_cooldown),
+            projectile_speed: self.projectile_speed.unwrap_or(stats.projectile_speed),
+            projectile_damage: self.projectile_damage.unwrap_or(stats.projectile_damage),
+            weapon: self.weapon.unwrap_or(stats.weapon),
+            weapon_range: self.weapon_range.unwrap_or(stats.weapon_range),
+            beam_dps: self.beam_dps.unwrap_or(stats.beam_dps),
+            heat_per_shot: self.heat_per_shot.unwrap_or(stats.heat_per_shot),
+            heat_dissipation: self.heat_dissipation.unwrap_or(stats.heat_dissipation),
+            idle_venting_factor: self
+                .idle_venting_factor
+                .unwrap_or(stats.idle_venting_factor),
+            overheat_secs: self.overheat_secs.unwrap_or(stats.overheat_secs),
+            shield_max: self.shield_max.unwrap_or(stats.shield_max),
+            shield_recharge_rate: self
+                .shield_recharge_rate
+                .unwrap_or(stats.shield_recharge_rate),
+            shield_recharge_delay: self
+                .shield_recharge_delay
+                .unwrap_or(stats.shield_recharge_delay),
+            point_defense_range: self
+                .point_defense_range
+                .unwrap_or(stats.point_defense_range),
+            point_defense_cooldown: self
+                .point_defense_cooldown
+                .unwrap_or(stats.point_defense_cooldown),
+            emp_radius: self.emp_radius.unwrap_or(stats.emp_radius),
+            emp_secs: self.emp_secs.unwrap_or(stats.emp_secs),
+            tractor_range: self.tractor_range.unwrap_or(stats.tractor_range),
+            tractor_force: self.tractor_force.unwrap_or(stats.tractor_force),
+            tractor_duration_secs: self
+                .tractor_duration_secs
+                .unwrap_or(stats.tractor_duration_secs),
+            torpedo_damage: self.torpedo_damage.unwrap_or(stats.torpedo_damage),
+            torpedo_speed: self.torpedo_speed.unwrap_or(stats.torpedo_speed),
+            ammo_capacity: self.ammo_capacity.unwrap_or(stats.ammo_capacity),
+            resupply_rate: self.resupply_rate.unwrap_or(stats.resupply_rate),
+            front_armor: self.front_armor.unwrap_or(stats.front_armor),
+            critical_multiplier: self
+                .critical_multiplier
+                .unwrap_or(stats.critical_multiplier),
+            flak_radius: self.flak_radius.unwrap_or(stats.flak_radius),
+            flak_dps: self.flak_dps.unwrap_or(stats.flak_dps),
+            boarding_threshold: self.boarding_threshold.unwrap_or(stats.boarding_threshold),
+            boarding_secs: self.boarding_secs.unwrap_or(stats.boarding_secs),
+        }
+    }
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FactionOverride {
+    pub faction: u32,
+    pub stats: StatsOverride,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct ClassOverride {
+    pub class: ShipClass,
+    pub stats: StatsOverride,
+}
+
+pub fn resolve_ship_stats(
+    trigger: Trigger<OnAdd, Spaceship>,
+    spaceships: Query<(&Faction, &ShipClass)>,
+    configs: Res<Configuration>,
+    definitions: ShipDefinitions,
+    mut commands: Commands,
+) {
+    if let Ok((faction, class)) = spaceships.get(trigger.entity()) {
+        commands.entity(trigger.entity()).insert(configs.resolve(
+            *faction,
+            class,
+            definitions.get(class),
+        ));
+    }
+}
+
+/// Re-resolve stats of living ships when the configuration or ship definitions
+/// are edited (e.g. from the inspector).
+pub fn refresh_ship_stats(
+    configs: Res<Configuration>,
+    definitions: ShipDefinitions,
+    mut spaceships: Query<(&Faction, &ShipClass, &mut ShipStats)>,
+) {
+    for (faction, class, mut stats) in spaceships.iter_mut() {
+        *stats = configs.resolve(*faction, class, definitions.get(class));
+    }
+}
+
+pub const CONFIGURATION_PATH: &str = "gameplay.config.ron";
+
+#[derive(Resource)]
+pub struct ConfigurationHandle(Handle<Configuration>);
+
+pub fn load_configuration_file(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ConfigurationHandle(asset_server.load(CONFIGURATION_PATH)));
+}
+
+/// Copy the configuration file into the resource each time it is (re)loaded.
+/// The configuration persisted in the user settings wins over the first load.
+pub fn apply_configuration_file(
+    mut events: EventReader<AssetEvent<Configuration>>,
+    handle: Res<ConfigurationHandle>,
+    files: Res<Assets<Configuration>>,
+    settings: Res<UserSettings>,
+    mut configs: ResMut<Configuration>,
+) {
+    for event in events.read() {
+        let first_load = event.is_loaded_with_dependencies(&handle.0);
+        if (first_load && settings.gameplay.is_none()) || event.is_modified(&handle.0) {
+            if let Some(config) = files.get(&handle.0) {
+                info!("Applying {CONFIGURATION_PATH}");
+                *configs = config.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{Configuration, ShipStats},
+    launch,
+    scenario::ScenarioEntity,
+    sound::Explosion,
+    weapons, Faction, ShipDestroyed, SimulationSet, Spaceship,
+};
+
+/// Health of the ships before the definition of their class is loaded.
+const DEFAULT_HEALTH: f32 = 100.;
+/// Cosine of the half angle of the front and rear arcs, 45°.
+const ARC_COS: f32 = FRAC_1_SQRT_2;
+
+/// Applies the [`DamageEvent`]s to the [`Shield`] then the [`Health`] of the
+/// ships, destroying the ships left without health.
+///
+/// Hits to the front of the ships are mitigated by their `front_armor`, hits
+/// to the rear are critical and multiplied by their `critical_multiplier`.
+///
+/// Destroyed ships leave a [`Blast`] pushing the bodies around and damaging
+/// the ships around on the next step, which can destroy them in turn.
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    detonate_blasts,
+                    resolve_damage.after(weapons::handle_projectile_hits),
+                    recharge_shields,
+                )
+                    .chain()
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            )
+            // Also on the clients, to show the shields
+            .add_systems(Update, apply_shield_stats);
+    }
+}
+
+/// Health of a ship, its maximum comes from the [`crate::ships::ShipDefinition`].
+#[derive(Component, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self::new(DEFAULT_HEALTH)
+    }
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Keeps the same fraction of health.
+    pub fn set_max(&mut self, max: f32) {
+        if max == self.max {
+            return;
+        }
+        if self.max > 0. {
+            self.current *= max / self.max;
+        }
+        self.max = max;
+    }
+}
+
+/// Shield of a ship absorbing the damage before its [`Health`], recharging
+/// `recharge_rate` per second once not hit for `recharge_delay` seconds.
+/// Tuned by the `shield_*` fields of the [`ShipStats`].
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Shield {
+    pub current: f32,
+    pub max: f32,
+    pub recharge_rate: f32,
+    pub recharge_delay: f32,
+    /// Seconds until the recharge resumes.
+    pub cooldown_secs: f32,
+}
+
+impl Shield {
+    /// Damage left once absorbed by the shield.
+    fn absorb(&mut self, amount: f32) -> f32 {
+        let absorbed = amount.min(self.current);
+        self.current -= absorbed;
+        self.cooldown_secs = self.recharge_delay;
+        amount - absorbed
+    }
+}
+
+/// Last faction which damaged the ship, credited when the ship is destroyed
+/// without a shot, e.g. by a [`crate::black_hole::BlackHole`].
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct LastAttacker(pub Option<Faction>);
+
+/// Explosion of a destroyed ship, applied and despawned on the next step.
+#[derive(Component)]
+#[require(Transform)]
+pub struct Blast {
+    /// Killer of the destroyed ship, credited for the damage.
+    pub source: Option<Faction>,
+}
+
+/// Damage dealt to the ship `target`, by the ships of `source` if any.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub source: Option<Faction>,
+    /// Dealt by a shot bent by gravity, see [`ShipDestroyed::slingshot`].
+    pub slingshot: bool,
+    /// Travel direction of the hit, for the directional armor. Undirected
+    /// damage ignores the armor.
+    pub direction: Option<Vec2>,
+    /// Ship which dealt it, see [`crate::targeting::ThreatTable`].
+    pub attacker: Option<Entity>,
+}
+
+/// Damages the ships of the other factions within `radius` of `position`.
+pub fn damage_enemies_around<'a>(
+    ships: impl Iterator<Item = (Entity, &'a Faction, &'a Transform)>,
+    faction: Faction,
+    position: Vec2,
+    radius: f32,
+    amount: f32,
+    damages: &mut EventWriter<DamageEvent>,
+) {
+    for (ship, ship_faction, transform) in ships {
+        let offset = transform.translation.truncate() - position;
+        if *ship_faction != faction && offset.length() <= radius {
+            damages.send(DamageEvent {
+                target: ship,
+                amount,
+                source: Some(faction),
+                slingshot: false,
+                direction: Some(offset),
+                attacker: None,
+            });
+        }
+    }
+}
+
+pub(crate) fn resolve_damage(
+    mut commands: Commands,
+    mut events: EventReader<DamageEvent>,
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &ShipStats,
+            &Velocity,
+            &mut Health,
+            &mut Shield,
+            &mut LastAttacker,
+        ),
+        With<Spaceship>,
+    >,
+    mut destroyed: EventWriter<ShipDestroyed>,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for event in events.read() {
+        let Ok((faction, transform, stats, velocity, mut health, mut shield, mut last_attacker)) =
+            ships.get_mut(event.target)
+        else {
+            continue;
+        };
+        // Already destroyed by a previous event
+        if health.current <= 0. {
+            continue;
+        }
+        let amount = match event.direction {
+            Some(direction) => event.amount * facing_multiplier(transform, direction, stats),
+            None => event.amount,
+        };
+        health.current -= shield.absorb(amount);
+        if event.source.is_some() {
+            last_attacker.0 = event.source;
+        }
+        if health.current > 0. {
+            continue;
+        }
+
+        commands.entity(event.target).despawn_recursive();
+        commands.spawn((
+            ScenarioEntity,
+            Blast {
+                source: event.source,
+            },
+            Transform::from_translation(transform.translation),
+        ));
+        destroyed.send(ShipDestroyed {
+            victim: *faction,
+            killer: event.source,
+            slingshot: event.slingshot,
+            position: transform.translation.truncate(),
+            velocity: velocity.linvel,
+        });
+        explosions.send(Explosion {
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
+/// Damage multiplier of a hit traveling in `direction`, depending on the side
+/// of the ship it hits.
+fn facing_multiplier(transform: &Transform, direction: Vec2, stats: &ShipStats) -> f32 {
+    // Hits to the front travel against the heading
+    let facing = -transform.up().truncate().dot(direction.normalize_or_zero());
+    if facing >= ARC_COS {
+        1. - stats.front_armor
+    } else if facing <= -ARC_COS {
+        stats.critical_multiplier
+    } else {
+        1.
+    }
+}
+
+/// Pushes the dynamic bodies away from the blasts and damages the ships, both
+/// decreasing linearly with the distance.
+fn detonate_blasts(
+    mut commands: Commands,
+    blasts: Query<(Entity, &Blast, &Transform)>,
+    mut bodies: Query<(
+        Entity,
+        &RigidBody,
+        &Transform,
+        Option<&mut ExternalImpulse>,
+        Has<Health>,
+    )>,
+    configs: Res<Configuration>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (blast_entity, blast, blast_transform) in blasts.iter() {
+        let center = blast_transform.translation.truncate();
+        for (entity, body, transform, impulse, has_health) in bodies.iter_mut() {
+            let offset = transform.translation.truncate() - center;
+            let falloff = 1. - offset.length() / configs.explosion_radius.max(f32::EPSILON);
+            if falloff <= 0. {
+                continue;
+            }
+
+            if *body == RigidBody::Dynamic {
+                let push = offset.normalize_or_zero() * configs.explosion_impulse * falloff;
+                match impulse {
+                    Some(mut impulse) => impulse.impulse += push,
+                    None => {
+                        commands.entity(entity).insert(ExternalImpulse {
+                            impulse: push,
+                            torque_impulse: 0.,
+                        });
+                    }
+                }
+            }
+            if has_health {
+                damages.send(DamageEvent {
+                    target: entity,
+                    amount: configs.explosion_damage * falloff,
+                    source: blast.source,
+                    slingshot: false,
+                    direction: Some(offset),
+                    attacker: None,
+                });
+            }
+        }
+        commands.entity(blast_entity).despawn();
+    }
+}
+
+fn recharge_shields(mut shields: Query<&mut Shield>, time: Res<Time>) {
+    for mut shield in shields.iter_mut() {
+        if shield.current >= shield.max {
+            continue;
+        }
+        shield.cooldown_secs -= time.delta_secs();
+        if shield.cooldown_secs <= 0. {
+            shield.current =
+                (shield.current + shield.recharge_rate * time.delta_secs()).min(shield.max);
+        }
+    }
+}
+
+/// Spawned ships start with a full shield, edited stats keep the fraction of
+/// shield of the living ships.
+fn apply_shield_stats(mut shields: Query<(&ShipStats, &mut Shield), Changed<ShipStats>>) {
+    for (stats, mut shield) in shields.iter_mut() {
+        shield.current = if shield.max > 0. {
+            shield.current * stats.shield_max / shield.max
+        } else {
+            stats.shield_max
+        };
+        shield.max = stats.shield_max;
+        shield.recharge_rate = stats.shield_recharge_rate;
+        shield.recharge_delay = stats.shield_recharge_delay;
+    }
+}
@@ -0,0 +1,155 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+
+use crate::{
+    factions::Factions,
+    pool::{prefill_pool, EntityPool, Pooled},
+    rng::GameRng,
+    settings::UserSettings,
+    GameState, ShipDestroyed, SimulationSet,
+};
+
+const PIECES: usize = 6;
+/// Pieces spawned ahead of time, more are spawned when they run out.
+const PREFILLED_PIECES: usize = 16 * PIECES;
+const PIECE_SIZE: Vec2 = Vec2::new(6., 3.);
+/// Speed of the pieces away from the wreck, on top of its velocity.
+const SPREAD_SPEED: f32 = 60.;
+/// Radians per second of tumbling, in either direction.
+const MAX_SPIN: f32 = 4.;
+const LIFETIME_SECS: f32 = 20.;
+/// Distance from the camera beyond which the pieces are released early.
+const CULL_DISTANCE: f32 = 2500.;
+
+/// Pieces of the destroyed ships, drifting with the velocity of the wreck and
+/// tumbling while they fade out, so the aftermath of a battle stays readable.
+///
+/// The pieces are pooled dynamic bodies attracted by the planets, colliding
+/// with nothing, and released once faded or far from the camera.
+pub struct DebrisPlugin;
+
+impl Plugin for DebrisPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntityPool::<Debris>::new(
+            spawn_piece,
+            activate_piece,
+            park_piece,
+        ))
+        .add_systems(Startup, prefill_pool::<Debris, PREFILLED_PIECES>)
+        .add_systems(
+            FixedUpdate,
+            spawn_debris
+                .in_set(SimulationSet)
+                .run_if(on_event::<ShipDestroyed>),
+        )
+        .add_systems(Update, fade_debris)
+        .add_systems(OnExit(GameState::InGame), release_debris);
+    }
+}
+
+#[derive(Component, Default)]
+struct Debris {
+    secs_left: f32,
+}
+
+fn spawn_piece(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((
+            Debris::default(),
+            Pooled::default(),
+            Sprite::from_color(Color::WHITE, PIECE_SIZE),
+            Transform::default(),
+            RigidBody::Fixed,
+            Collider::cuboid(PIECE_SIZE.x / 2., PIECE_SIZE.y / 2.),
+            CollisionGroups::new(Group::NONE, Group::NONE),
+            Velocity::zero(),
+            GravityScale(0.),
+            Visibility::Hidden,
+        ))
+        .id()
+}
+
+fn activate_piece(entity_commands: &mut EntityCommands) {
+    entity_commands.insert(RigidBody::Dynamic);
+}
+
+/// Parked pieces stay still, out of reach of the planets.
+fn park_piece(entity_commands: &mut EntityCommands) {
+    entity_commands.insert((RigidBody::Fixed, Velocity::zero()));
+}
+
+fn spawn_debris(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Debris>>,
+    mut destroyed: EventReader<ShipDestroyed>,
+    mut rng: ResMut<GameRng>,
+    factions: Res<Factions>,
+    settings: Res<UserSettings>,
+) {
+    // Still drawn without tumbling, so that the setting doesn't change the
+    // random sequence
+    let tumbling = if settings.accessibility.reduced_motion {
+        0.
+    } else {
+        1.
+    };
+    for event in destroyed.read() {
+        let color = factions.color(event.victim).darker(0.2);
+        for _ in 0..PIECES {
+            let direction = Vec2::from_angle(rng.gen_range(0.0..TAU));
+            let velocity = event.velocity + direction * rng.gen_range(0.0..SPREAD_SPEED);
+            pool.acquire(&mut commands).insert((
+                Debris {
+                    secs_left: LIFETIME_SECS,
+                },
+                Sprite::from_color(color, PIECE_SIZE),
+                Transform::from_translation(event.position.extend(-0.2))
+                    .with_rotation(Quat::from_rotation_z(rng.gen_range(0.0..TAU))),
+                Velocity {
+                    linvel: velocity,
+                    angvel: rng.gen_range(-MAX_SPIN..MAX_SPIN) * tumbling,
+                },
+            ));
+        }
+    }
+}
+
+fn fade_debris(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Debris>>,
+    mut debris: Query<(Entity, &mut Debris, &mut Pooled, &mut Sprite, &Transform)>,
+    camera: Query<&Transform, (With<Camera>, Without<Debris>)>,
+    time: Res<Time>,
+) {
+    let camera_position = camera
+        .get_single()
+        .ok()
+        .map(|transform| transform.translation.truncate());
+    for (entity, mut piece, mut pooled, mut sprite, transform) in debris.iter_mut() {
+        if !pooled.active {
+            continue;
+        }
+        piece.secs_left -= time.delta_secs();
+        let far = camera_position.is_some_and(|camera_position| {
+            camera_position.distance(transform.translation.truncate()) > CULL_DISTANCE
+        });
+        if piece.secs_left <= 0. || far {
+            pool.release(&mut commands, entity, &mut pooled);
+            continue;
+        }
+        sprite.color.set_alpha(piece.secs_left / LIFETIME_SECS);
+    }
+}
+
+fn release_debris(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Debris>>,
+    mut debris: Query<(Entity, &mut Pooled), With<Debris>>,
+) {
+    for (entity, mut pooled) in debris.iter_mut() {
+        pool.release(&mut commands, entity, &mut pooled);
+    }
+}
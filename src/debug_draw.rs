@@ -0,0 +1,191 @@
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+
+use crate::{
+    accessibility::{styled_line_2d, LineStyle},
+    config::ShipStats,
+    factions::Factions,
+    Faction, Spaceship, Target,
+};
+
+/// Debug drawing channels on top of [`Gizmos`], each toggled with its own key.
+///
+/// Channels are enabled by default only in `dev-tools` builds. Besides the
+/// immediate mode gizmos, each channel has [`PersistentLines`] kept on screen
+/// for a given duration.
+pub struct DebugDrawPlugin;
+
+impl Plugin for DebugDrawPlugin {
+    fn build(&self, app: &mut App) {
+        add_channel::<HeadingGizmos>(app, KeyCode::F1);
+        add_channel::<TargetGizmos>(app, KeyCode::F2);
+        add_channel::<EventGizmos>(app, KeyCode::F3);
+        // The function keys are all bound
+        add_channel::<RangeGizmos>(app, KeyCode::KeyG);
+
+        app.add_systems(
+            Update,
+            (draw_headings, draw_targets, draw_ranges).run_if(any_with_component::<Spaceship>),
+        )
+        .add_observer(mark_spawns);
+    }
+}
+
+/// Direction of each spaceship.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct HeadingGizmos;
+
+/// Line from each spaceship to its target.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct TargetGizmos;
+
+/// Optimal and maximum weapon ranges around each spaceship.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct RangeGizmos;
+
+/// Short-lived markers of gameplay events (spawns, deaths...).
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct EventGizmos;
+
+#[derive(Resource)]
+pub struct PersistentLines<T: GizmoConfigGroup> {
+    lines: Vec<PersistentLine>,
+    _marker: PhantomData<fn() -> T>,
+}
+
+struct PersistentLine {
+    start: Vec2,
+    end: Vec2,
+    color: Color,
+    remaining_secs: f32,
+}
+
+impl<T: GizmoConfigGroup> Default for PersistentLines<T> {
+    fn default() -> Self {
+        Self {
+            lines: vec![],
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: GizmoConfigGroup> PersistentLines<T> {
+    pub fn line(&mut self, start: Vec2, end: Vec2, duration_secs: f32, color: impl Into<Color>) {
+        self.lines.push(PersistentLine {
+            start,
+            end,
+            color: color.into(),
+            remaining_secs: duration_secs,
+        });
+    }
+
+    pub fn cross(&mut self, center: Vec2, size: f32, duration_secs: f32, color: impl Into<Color>) {
+        let color = color.into();
+        let half = size / 2.;
+        self.line(
+            center - Vec2::splat(half),
+            center + Vec2::splat(half),
+            duration_secs,
+            color,
+        );
+        self.line(
+            center + Vec2::new(-half, half),
+            center + Vec2::new(half, -half),
+            duration_secs,
+            color,
+        );
+    }
+}
+
+fn add_channel<T: GizmoConfigGroup>(app: &mut App, toggle_key: KeyCode) {
+    app.insert_gizmo_config(
+        T::default(),
+        GizmoConfig {
+            enabled: cfg!(feature = "dev-tools"),
+            ..default()
+        },
+    )
+    .init_resource::<PersistentLines<T>>()
+    .add_systems(
+        Update,
+        (
+            draw_persistent_lines::<T>,
+            move |keyboard: Res<ButtonInput<KeyCode>>, mut store: ResMut<GizmoConfigStore>| {
+                if keyboard.just_pressed(toggle_key) {
+                    let (config, _) = store.config_mut::<T>();
+                    config.enabled = !config.enabled;
+                }
+            },
+        ),
+    );
+}
+
+fn draw_persistent_lines<T: GizmoConfigGroup>(
+    mut persistent_lines: ResMut<PersistentLines<T>>,
+    mut gizmos: Gizmos<T>,
+    time: Res<Time>,
+) {
+    let delta_secs = time.delta_secs();
+    persistent_lines.lines.retain_mut(|line| {
+        gizmos.line_2d(line.start, line.end, line.color);
+        line.remaining_secs -= delta_secs;
+        line.remaining_secs > 0.
+    });
+}
+
+fn draw_headings(
+    spaceships: Query<(&Faction, &Transform), With<Spaceship>>,
+    mut gizmos: Gizmos<HeadingGizmos>,
+    factions: Res<Factions>,
+) {
+    for (faction, transform) in spaceships.iter() {
+        let pos = transform.translation.truncate();
+        let direction = transform.up().truncate();
+        styled_line_2d(
+            &mut gizmos,
+            pos,
+            pos + direction * 100.,
+            factions.color(*faction),
+            LineStyle::of(*faction, &factions),
+        );
+    }
+}
+
+fn draw_targets(
+    spaceships: Query<(&Transform, &Target), With<Spaceship>>,
+    mut gizmos: Gizmos<TargetGizmos>,
+) {
+    for (transform, target) in spaceships.iter() {
+        gizmos.line_2d(
+            transform.translation.truncate(),
+            target.translation.truncate(),
+            Color::srgba(1., 1., 0., 0.2),
+        );
+    }
+}
+
+fn draw_ranges(
+    spaceships: Query<(&Faction, &Transform, &ShipStats), With<Spaceship>>,
+    mut gizmos: Gizmos<RangeGizmos>,
+    factions: Res<Factions>,
+) {
+    for (faction, transform, stats) in spaceships.iter() {
+        let position = transform.translation.truncate();
+        let color = factions.color(*faction);
+        gizmos.circle_2d(position, stats.aim_distance, color.with_alpha(0.3));
+        gizmos.circle_2d(position, stats.weapon_range, color.with_alpha(0.1));
+    }
+}
+
+fn mark_spawns(
+    trigger: Trigger<OnAdd, Spaceship>,
+    spaceships: Query<(&Faction, &Transform)>,
+    mut lines: ResMut<PersistentLines<EventGizmos>>,
+    factions: Res<Factions>,
+) {
+    if let Ok((faction, transform)) = spaceships.get(trigger.entity()) {
+        let color = factions.color(*faction);
+        lines.cross(transform.translation.truncate(), 40., 1., color);
+    }
+}
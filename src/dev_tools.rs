@@ -0,0 +1,64 @@
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_inspector_egui::{
+    bevy_egui::{egui, EguiContexts},
+    quick::ResourceInspectorPlugin,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::Configuration, difficulty::AiDifficulty, factions::Factions, telemetry::Telemetry,
+    Faction,
+};
+
+/// Inspector, collider wireframes, diagnostics and telemetry window.
+/// Only built with the `dev-tools` feature.
+pub struct DevToolsPlugin;
+
+impl Plugin for DevToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            RapierDebugRenderPlugin::default(),
+            FrameTimeDiagnosticsPlugin,
+            LogDiagnosticsPlugin::default(),
+            ResourceInspectorPlugin::<Configuration>::default(),
+            ResourceInspectorPlugin::<AiDifficulty>::default(),
+        ))
+        .add_systems(Update, telemetry_window);
+    }
+}
+
+fn telemetry_window(
+    mut contexts: EguiContexts,
+    telemetry: Res<Telemetry>,
+    factions: Res<Factions>,
+) {
+    let Some(sample) = telemetry.latest() else {
+        return;
+    };
+
+    egui::Window::new("Telemetry").show(contexts.ctx_mut(), |ui| {
+        ui.label(format!("Entities: {}", sample.entities));
+        ui.label(format!("Spaceships: {}", sample.spaceships));
+        ui.label(format!("Frame time: {:.2} ms", sample.frame_time_ms));
+        ui.label(format!("Fixed update: {:.2} ms/s", sample.fixed_update_ms));
+        ui.label(format!(
+            "Spawns: {}/s, deaths: {}/s",
+            sample.spawns, sample.deaths
+        ));
+        ui.separator();
+        for (faction, strength) in &sample.faction_strength {
+            ui.colored_label(
+                faction_color(&factions, *faction),
+                format!("{}: {strength}", factions.label(*faction)),
+            );
+        }
+    });
+}
+
+fn faction_color(factions: &Factions, faction: Faction) -> egui::Color32 {
+    let [r, g, b, _] = factions.color(faction).to_srgba().to_u8_array();
+    egui::Color32::from_rgb(r, g, b)
+}
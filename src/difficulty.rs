@@ -0,0 +1,123 @@
+use bevy::prelude::*;
+#[cfg(feature = "dev-tools")]
+use bevy_inspector_egui::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{config::ShipStats, Faction};
+
+/// How well the AI of each faction flies and fights, from the [`AiDifficulty`]
+/// edited in the inspector: the `level` of all the factions, unless
+/// overridden for some of them, e.g. easier for the enemies of the player.
+///
+/// The level delays the choice of new enemies in [`crate::update_targets`],
+/// spreads the aim around them, and scales the thrust and rotation of the
+/// ships in [`crate::apply_forces`].
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AiDifficulty>()
+            .register_type::<AiDifficulty>();
+    }
+}
+
+#[derive(Reflect, Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[reflect(Resource)]
+#[cfg_attr(
+    feature = "dev-tools",
+    derive(InspectorOptions),
+    reflect(InspectorOptions)
+)]
+pub struct AiDifficulty {
+    pub level: DifficultyLevel,
+    pub factions: Vec<FactionDifficulty>,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct FactionDifficulty {
+    pub faction: u32,
+    pub level: DifficultyLevel,
+}
+
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum DifficultyLevel {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Brutal,
+}
+
+impl DifficultyLevel {
+    /// Seconds before the ships pick a new enemy.
+    fn reaction_secs(self) -> f32 {
+        match self {
+            Self::Easy => 0.6,
+            Self::Normal | Self::Hard | Self::Brutal => 0.,
+        }
+    }
+
+    /// Maximum angle in radians between the aim and the enemy.
+    fn aim_error(self) -> f32 {
+        match self {
+            Self::Easy => 0.2,
+            Self::Normal | Self::Hard | Self::Brutal => 0.,
+        }
+    }
+
+    /// Factor of the thrust and rotation of the ships.
+    fn agility(self) -> f32 {
+        match self {
+            Self::Easy => 0.75,
+            Self::Normal => 1.,
+            Self::Hard => 1.15,
+            Self::Brutal => 1.3,
+        }
+    }
+}
+
+impl AiDifficulty {
+    pub fn level(&self, faction: Faction) -> DifficultyLevel {
+        self.factions
+            .iter()
+            .rev()
+            .find(|difficulty| difficulty.faction == faction.0)
+            .map_or(self.level, |difficulty| difficulty.level)
+    }
+
+    pub fn reaction_secs(&self, faction: Faction) -> f32 {
+        self.level(faction).reaction_secs()
+    }
+
+    pub fn aim_error(&self, faction: Faction) -> f32 {
+        self.level(faction).aim_error()
+    }
+
+    /// Stats with the force and rotation caps of the level.
+    pub fn scale(&self, faction: Faction, stats: &ShipStats) -> ShipStats {
+        let agility = self.level(faction).agility();
+        ShipStats {
+            rotation_force: stats.rotation_force * agility,
+            propulsion_force: stats.propulsion_force * agility,
+            rotation_max: stats.rotation_max * agility,
+            ..*stats
+        }
+    }
+}
+
+/// When the ship can pick a new enemy again.
+#[derive(Component, Default, Debug)]
+pub struct Reaction {
+    ready_at: f32,
+}
+
+impl Reaction {
+    /// Whether the ship reacts at `now`, then waits `delay` seconds.
+    pub fn react(&mut self, now: f32, delay: f32) -> bool {
+        if now < self.ready_at {
+            return false;
+        }
+        self.ready_at = now + delay;
+        true
+    }
+}
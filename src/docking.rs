@@ -0,0 +1,218 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_rapier2d::prelude::*;
+use itertools::Itertools;
+
+use crate::{
+    ammo::Ammo,
+    behavior::AiState,
+    config::{Configuration, ShipStats},
+    damage::Health,
+    launch, Faction, SimulationSet, Spaceship,
+};
+
+/// Distance beyond the surface of a body within which the ships dock.
+const DOCK_RANGE: f32 = 50.;
+const PLANET_SLOTS: usize = 4;
+const STATION_SLOTS: usize = 2;
+
+/// Ships whose health falls below the `dock_health_threshold` of the
+/// [`Configuration`] head for the closest friendly [`Dock`] with a free slot:
+/// any planet, or a station of their faction. So do the ships deciding to
+/// [`AiState::Dock`] until they are full.
+///
+/// Once in the docking zone, ships are held still while they regain
+/// `dock_repair_rate` health and `resupply_rate` rounds per second, then
+/// leave when full. A dock holds a limited number of ships at once, the
+/// others wait their turn around it.
+pub struct DockingPlugin;
+
+impl Plugin for DockingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (seek_docks, dock_ships, repair_docked_ships)
+                .chain()
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Dock>.and(launch::is_authoritative)),
+        );
+    }
+}
+
+/// Docking zone around a planet or a station, friendly to the ships of its
+/// [`Faction`] if any, or to everyone.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Dock {
+    pub radius: f32,
+    /// Ships docked at once.
+    pub slots: usize,
+}
+
+impl Dock {
+    pub fn planet(radius: f32) -> Self {
+        Self {
+            radius: radius + DOCK_RANGE,
+            slots: PLANET_SLOTS,
+        }
+    }
+
+    pub fn station(size: f32) -> Self {
+        Self {
+            radius: size / 2. + DOCK_RANGE,
+            slots: STATION_SLOTS,
+        }
+    }
+}
+
+/// Docking state of a ship.
+#[derive(Component, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Docking {
+    #[default]
+    Undocked,
+    /// Heading for the dock, or waiting for a free slot.
+    Approaching(Entity),
+    Docked(Entity),
+}
+
+impl Docking {
+    /// Dock the ship is heading for or docked at.
+    pub fn dock(&self) -> Option<Entity> {
+        match self {
+            Self::Undocked => None,
+            Self::Approaching(dock) | Self::Docked(dock) => Some(*dock),
+        }
+    }
+}
+
+type Docks<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static Dock,
+        &'static Transform,
+        Option<&'static Faction>,
+    ),
+    Without<Spaceship>,
+>;
+
+pub(crate) fn is_friendly(dock_faction: Option<&Faction>, faction: Faction) -> bool {
+    dock_faction.is_none_or(|dock_faction| *dock_faction == faction)
+}
+
+/// Ships docked at each dock.
+pub(crate) fn occupied_slots<'a>(
+    dockings: impl Iterator<Item = &'a Docking>,
+) -> HashMap<Entity, usize> {
+    dockings
+        .filter_map(|docking| match docking {
+            Docking::Docked(dock) => Some(*dock),
+            _ => None,
+        })
+        .counts()
+        .into_iter()
+        .collect()
+}
+
+fn seek_docks(
+    mut ships: Query<
+        (&Faction, &Transform, &Health, &Ammo, &AiState, &mut Docking),
+        With<Spaceship>,
+    >,
+    docks: Docks,
+    configs: Res<Configuration>,
+) {
+    let occupied = occupied_slots(ships.iter().map(|(.., docking)| docking));
+    for (faction, transform, health, ammo, state, mut docking) in ships.iter_mut() {
+        // Or decided to, while not full
+        let wanted = health.current < health.max * configs.dock_health_threshold
+            || *state == AiState::Dock && (health.current < health.max || ammo.current < ammo.max);
+        if *docking != Docking::Undocked || !wanted {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let closest = docks
+            .iter()
+            .filter(|(entity, dock, _, dock_faction)| {
+                is_friendly(*dock_faction, *faction)
+                    && occupied.get(entity).copied().unwrap_or(0) < dock.slots
+            })
+            .map(|(entity, _, dock_transform, _)| {
+                (
+                    entity,
+                    dock_transform.translation.truncate().distance(position),
+                )
+            })
+            .min_by(|(_, a_distance), (_, b_distance)| a_distance.total_cmp(b_distance));
+        if let Some((dock, _)) = closest {
+            *docking = Docking::Approaching(dock);
+        }
+    }
+}
+
+/// Docks the ships reaching the zone of their dock while it has a free slot,
+/// and releases the ships whose dock is gone or was captured.
+fn dock_ships(
+    mut ships: Query<(&Faction, &Transform, &mut Docking), With<Spaceship>>,
+    docks: Docks,
+) {
+    let mut occupied = occupied_slots(ships.iter().map(|(.., docking)| docking));
+    for (faction, transform, mut docking) in ships.iter_mut() {
+        let Some(dock_entity) = docking.dock() else {
+            continue;
+        };
+        let Some((_, dock, dock_transform, _)) = docks
+            .get(dock_entity)
+            .ok()
+            .filter(|(.., dock_faction)| is_friendly(*dock_faction, *faction))
+        else {
+            *docking = Docking::Undocked;
+            continue;
+        };
+        if !matches!(*docking, Docking::Approaching(_)) {
+            continue;
+        }
+        let distance = transform
+            .translation
+            .truncate()
+            .distance(dock_transform.translation.truncate());
+        let slots = occupied.entry(dock_entity).or_default();
+        if distance <= dock.radius && *slots < dock.slots {
+            *slots += 1;
+            *docking = Docking::Docked(dock_entity);
+        }
+    }
+}
+
+/// Holds the docked ships still while repairing and resupplying them, until
+/// they are full.
+fn repair_docked_ships(
+    mut ships: Query<
+        (
+            &ShipStats,
+            &mut Docking,
+            &mut Health,
+            &mut Ammo,
+            &mut Velocity,
+            &mut ExternalForce,
+        ),
+        With<Spaceship>,
+    >,
+    configs: Res<Configuration>,
+    time: Res<Time>,
+) {
+    for (stats, mut docking, mut health, mut ammo, mut velocity, mut ext_force) in ships.iter_mut()
+    {
+        if !matches!(*docking, Docking::Docked(_)) {
+            continue;
+        }
+        *velocity = Velocity::zero();
+        *ext_force = ExternalForce::default();
+        health.current =
+            (health.current + configs.dock_repair_rate * time.delta_secs()).min(health.max);
+        ammo.current = (ammo.current + stats.resupply_rate * time.delta_secs()).min(ammo.max);
+        if health.current >= health.max && ammo.current >= ammo.max {
+            *docking = Docking::Undocked;
+        }
+    }
+}
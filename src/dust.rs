@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::Spaceship;
+
+const DUST_COUNT: usize = 200;
+/// Side of the square around the camera in which the dust is kept.
+const FIELD_SIZE: f32 = 2400.;
+const DUST_SIZE: f32 = 1.5;
+const DUST_COLOR: Color = Color::srgba(0.8, 0.7, 0.6, 0.5);
+/// Length of the exhaust behind the ships, and cosine of its half angle.
+const EXHAUST_LENGTH: f32 = 150.;
+const EXHAUST_COS: f32 = 0.8;
+/// Speed given to the dust per unit of thrust per second, at the nozzle.
+const PUSH: f32 = 0.5;
+/// Fraction of its speed lost by the dust per second.
+const DRAG: f32 = 1.5;
+
+/// Sparse dust floating around the camera, blown away by the thrust of the
+/// ships passing by, which shows where they are accelerating.
+///
+/// Purely cosmetic: the dust isn't simulated, and wraps around the camera to
+/// always fill the view.
+pub struct DustPlugin;
+
+impl Plugin for DustPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_dust)
+            .add_systems(Update, (blow_dust, drift_dust).chain());
+    }
+}
+
+#[derive(Component, Default)]
+struct Dust {
+    velocity: Vec2,
+}
+
+fn spawn_dust(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    // Not the game random generator, the dust having no effect on the game
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+    let mesh = meshes.add(Circle::new(DUST_SIZE));
+    let material = materials.add(DUST_COLOR);
+    let half_size = FIELD_SIZE / 2.;
+    for _ in 0..DUST_COUNT {
+        let position = Vec2::new(
+            rng.gen_range(-half_size..half_size),
+            rng.gen_range(-half_size..half_size),
+        );
+        commands.spawn((
+            Dust::default(),
+            Mesh2d(mesh.clone()),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(position.extend(-0.3)),
+        ));
+    }
+}
+
+/// Pushes the dust in the exhaust of the ships, opposite to their thrust.
+fn blow_dust(
+    ships: Query<(&Transform, &ExternalForce), With<Spaceship>>,
+    mut dust: Query<(&mut Dust, &Transform), Without<Spaceship>>,
+    time: Res<Time>,
+) {
+    for (ship_transform, thrust) in ships.iter() {
+        let Some(exhaust) = (-thrust.force).try_normalize() else {
+            continue;
+        };
+        let nozzle = ship_transform.translation.truncate();
+        for (mut particle, transform) in dust.iter_mut() {
+            let offset = transform.translation.truncate() - nozzle;
+            let distance = offset.length();
+            if distance > EXHAUST_LENGTH || offset.dot(exhaust) < EXHAUST_COS * distance {
+                continue;
+            }
+            let falloff = 1. - distance / EXHAUST_LENGTH;
+            particle.velocity +=
+                exhaust * thrust.force.length() * PUSH * falloff * time.delta_secs();
+        }
+    }
+}
+
+/// Moves and slows down the dust, wrapping it around the camera.
+fn drift_dust(
+    mut dust: Query<(&mut Dust, &mut Transform)>,
+    camera: Query<&Transform, (With<Camera>, Without<Dust>)>,
+    time: Res<Time>,
+) {
+    let camera_position = camera
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation.truncate());
+    let field_min = camera_position - FIELD_SIZE / 2.;
+    for (mut particle, mut transform) in dust.iter_mut() {
+        particle.velocity *= (1. - DRAG * time.delta_secs()).max(0.);
+        let position = transform.translation.truncate() + particle.velocity * time.delta_secs();
+        let wrapped = (position - field_min).rem_euclid(Vec2::splat(FIELD_SIZE)) + field_min;
+        transform.translation = wrapped.extend(transform.translation.z);
+    }
+}
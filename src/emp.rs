@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    config::ShipStats,
+    launch,
+    settings::UserSettings,
+    sound::WeaponFired,
+    weapons::{self, Heat, Weapon, WeaponKind},
+    Faction, SimulationSet, Spaceship, Target,
+};
+
+/// Seconds a pulse stays on screen.
+const PULSE_SECS: f32 = 0.3;
+const PULSE_COLOR: Color = Color::srgb(0.4, 0.8, 1.);
+
+/// Ships with a [`WeaponKind::Emp`] emit a pulse every `fire_cooldown`
+/// seconds once their target is within their `emp_radius`, disabling the
+/// enemy ships around for `emp_secs`: they drift without propulsion nor
+/// targeting, see [`Disabled`].
+pub struct EmpPlugin;
+
+impl Plugin for EmpPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                recover_disabled_ships,
+                // After the weapon cooldowns
+                emit_pulses.after(weapons::fire_weapons),
+            )
+                .chain()
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, draw_pulses);
+    }
+}
+
+/// The ship was hit by an EMP, `apply_forces` and `update_targets` skip it
+/// until the timer finishes.
+#[derive(Component)]
+pub struct Disabled(pub Timer);
+
+/// Shown expanding to its radius.
+#[derive(Component)]
+struct Pulse {
+    radius: f32,
+    timer: Timer,
+}
+
+fn emit_pulses(
+    mut commands: Commands,
+    mut emitters: Query<
+        (
+            &Faction,
+            &Transform,
+            &ShipStats,
+            &Target,
+            &mut Weapon,
+            &mut Heat,
+            &mut Ammo,
+        ),
+        (With<Spaceship>, Without<Disabled>),
+    >,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    for (faction, transform, stats, target, mut weapon, mut heat, mut ammo) in emitters.iter_mut() {
+        if stats.weapon != WeaponKind::Emp
+            || weapon.cooldown_secs > 0.
+            || heat.overheated_secs > 0.
+            || ammo.is_empty()
+            || !target.hostile
+            || target.distance > stats.emp_radius
+        {
+            continue;
+        }
+
+        weapon.cooldown_secs = stats.fire_cooldown;
+        heat.current += stats.heat_per_shot;
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        ammo.spend();
+        let position = transform.translation.truncate();
+        for (ship, ship_faction, ship_transform) in ships.iter() {
+            if ship_faction != faction
+                && ship_transform.translation.truncate().distance(position) <= stats.emp_radius
+            {
+                // Drifts with its current velocity, without target to fire at,
+                // unless destroyed within the same step
+                commands.entity(ship).try_insert((
+                    Disabled(Timer::from_seconds(stats.emp_secs, TimerMode::Once)),
+                    ExternalForce::default(),
+                    Target::default(),
+                ));
+            }
+        }
+        commands.spawn((
+            Pulse {
+                radius: stats.emp_radius,
+                timer: Timer::from_seconds(PULSE_SECS, TimerMode::Once),
+            },
+            Transform::from_translation(transform.translation),
+        ));
+        fired.send(WeaponFired { position });
+    }
+}
+
+fn recover_disabled_ships(
+    mut commands: Commands,
+    mut ships: Query<(Entity, &mut Disabled)>,
+    time: Res<Time>,
+) {
+    for (entity, mut disabled) in ships.iter_mut() {
+        if disabled.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<Disabled>();
+        }
+    }
+}
+
+fn draw_pulses(
+    mut commands: Commands,
+    mut pulses: Query<(Entity, &mut Pulse, &Transform)>,
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    settings: Res<UserSettings>,
+) {
+    for (entity, mut pulse, transform) in pulses.iter_mut() {
+        if pulse.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let fraction = pulse.timer.fraction();
+        // Fading out at its full radius rather than spreading
+        let spread = if settings.accessibility.reduced_motion {
+            1.
+        } else {
+            fraction
+        };
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            pulse.radius * spread,
+            PULSE_COLOR.with_alpha(1. - fraction),
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::Configuration, gravity::SpatialGrid, missile::Missile, pool::Pooled,
+    weapons::Projectile, Faction,
+};
+
+/// Distance within which the ships watch the enemy munitions and the enemies
+/// on their tail.
+const THREAT_RADIUS: f32 = 300.;
+/// Distance from the ship within which a munition passes to be a threat.
+const NEAR_MISS: f32 = 40.;
+/// Cosine of the angle within which an enemy behind points at the ship.
+const TRACKING_COS: f32 = 0.95;
+/// Changes of side per second of the jinking ships.
+const JINK_RATE: f32 = 1.5;
+
+/// Enemy munitions flying toward the ships and enemy ships tracking them from
+/// behind, bucketed in cells of [`THREAT_RADIUS`].
+///
+/// The threatened ships jink: [`crate::apply_forces`] pushes them from side
+/// to side with [`IncomingFire::evasion`], weighted by the `evasion_weight` of
+/// the [`Configuration`], and shakes their rotation unless the motion is
+/// reduced.
+pub struct IncomingFire {
+    grid: SpatialGrid<Threat>,
+}
+
+#[derive(Clone, Copy)]
+enum Threat {
+    Munition { faction: Faction, velocity: Vec2 },
+    Tracker { faction: Faction, heading: Vec2 },
+}
+
+#[derive(SystemParam)]
+pub struct Munitions<'w, 's> {
+    projectiles: Query<
+        'w,
+        's,
+        (
+            &'static Projectile,
+            &'static Pooled,
+            &'static Transform,
+            &'static Velocity,
+        ),
+    >,
+    missiles: Query<'w, 's, (&'static Missile, &'static Transform, &'static Velocity)>,
+}
+
+impl IncomingFire {
+    /// The flying `munitions`, and the `ships` with their faction and transform.
+    pub fn new<'a>(
+        munitions: &Munitions,
+        ships: impl Iterator<Item = (Faction, &'a Transform)>,
+    ) -> Self {
+        let projectiles = munitions
+            .projectiles
+            .iter()
+            .filter(|(_, pooled, ..)| pooled.active)
+            .map(|(projectile, _, transform, velocity)| {
+                (projectile.faction, transform, velocity.linvel)
+            });
+        let missiles = munitions
+            .missiles
+            .iter()
+            .map(|(missile, transform, velocity)| (missile.faction, transform, velocity.linvel));
+        let munitions = projectiles
+            .chain(missiles)
+            .map(|(faction, transform, velocity)| {
+                (
+                    transform.translation.truncate(),
+                    Threat::Munition { faction, velocity },
+                )
+            });
+        let trackers = ships.map(|(faction, transform)| {
+            (
+                transform.translation.truncate(),
+                Threat::Tracker {
+                    faction,
+                    heading: transform.up().truncate(),
+                },
+            )
+        });
+        Self {
+            grid: SpatialGrid::new(THREAT_RADIUS, munitions.chain(trackers)),
+        }
+    }
+
+    /// Whether an enemy munition is about to pass close to the ship, or an
+    /// enemy behind it points at it.
+    pub fn threatened(&self, faction: Faction, transform: &Transform) -> bool {
+        let position = transform.translation.truncate();
+        let heading = transform.up().truncate();
+        self.grid
+            .neighbors(position)
+            .filter(|(other_position, _)| other_position.distance(position) <= THREAT_RADIUS)
+            .any(|(other_position, threat)| {
+                let offset = position - *other_position;
+                match *threat {
+                    Threat::Munition {
+                        faction: munition_faction,
+                        velocity,
+                    } => {
+                        let Some(direction) = velocity.try_normalize() else {
+                            return false;
+                        };
+                        // Closest approach ahead of the munition
+                        let along = offset.dot(direction);
+                        munition_faction != faction
+                            && along > 0.
+                            && (offset - direction * along).length() <= NEAR_MISS
+                    }
+                    Threat::Tracker {
+                        faction: tracker_faction,
+                        heading: tracker_heading,
+                    } => {
+                        tracker_faction != faction
+                            && offset.dot(heading) > 0.
+                            && offset.normalize_or_zero().dot(tracker_heading) >= TRACKING_COS
+                    }
+                }
+            })
+    }
+
+    /// Lateral force as a fraction of the thrust, switching side
+    /// [`JINK_RATE`] times per second, at a different moment for each ship.
+    pub fn evasion(
+        &self,
+        entity: Entity,
+        faction: Faction,
+        transform: &Transform,
+        elapsed_secs: f32,
+        configs: &Configuration,
+    ) -> Option<Vec2> {
+        if configs.evasion_weight <= 0. || !self.threatened(faction, transform) {
+            return None;
+        }
+        let phase = ((elapsed_secs * JINK_RATE) as u32).wrapping_add(entity.index());
+        let side = if phase % 2 == 0 { 1. } else { -1. };
+        Some(transform.up().truncate().perp() * side * configs.evasion_weight)
+    }
+}
@@ -0,0 +1,239 @@
+use bevy::{color::palettes::css, prelude::*};
+use serde::{de::Error as _, Deserialize, Deserializer};
+
+use crate::{
+    accessibility::{Badge, Palette},
+    config::AiBackend,
+    formation::FormationShape,
+    mods::LoadOrder,
+    ron_asset::RonAssetPlugin,
+    scenario::{Scenario, ScenarioHandle},
+    targeting::TargetingPolicy,
+    Faction, GameState,
+};
+
+const DEFAULT_FACTIONS_PATH: &str = "default.factions.ron";
+pub const DEFAULT_BUDGET: u32 = 100;
+/// Colors of the factions without definition.
+const FALLBACK_COLORS: [Srgba; 5] = [css::BLUE, css::RED, css::LIME, css::YELLOW, css::PURPLE];
+
+/// Data-driven faction identities: `assets/default.factions.ron`, then the
+/// `factions/*.factions.ron` of the mods, then the `factions` of the scenario,
+/// each definition replacing the previous one of the same faction.
+///
+/// Any number of factions can be defined, undefined ones get fallback values.
+pub struct FactionsPlugin;
+
+impl Plugin for FactionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<FactionDefinitions>::new(&["factions.ron"]))
+            .init_resource::<Factions>()
+            .add_systems(Startup, load_default_factions)
+            // The scenario is loaded once leaving `Loading`
+            .add_systems(OnExit(GameState::Loading), resolve_factions)
+            .add_systems(
+                Update,
+                resolve_factions.run_if(on_event::<AssetEvent<FactionDefinitions>>),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct FactionDefinitions(pub Vec<FactionDefinition>);
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct FactionDefinition {
+    pub id: Faction,
+    pub name: String,
+    /// Hex color, e.g. `"#1e90ff"`.
+    #[serde(deserialize_with = "hex_color")]
+    pub color: Color,
+    /// Center of the initial fleet.
+    pub home: Vec2,
+    #[serde(default)]
+    pub personality: Personality,
+    /// How the ships of the faction choose among the enemies.
+    #[serde(default)]
+    pub targeting: TargetingPolicy,
+    /// Shape in which the ships of the faction fly behind their leader.
+    #[serde(default)]
+    pub formation: Option<FormationShape>,
+    /// How the ships of the faction decide, else the `ai_backend` of the
+    /// [`crate::config::Configuration`].
+    #[serde(default)]
+    pub ai_backend: Option<AiBackend>,
+    /// Default cost of the initial fleet picked in the lobby.
+    #[serde(default = "default_budget")]
+    pub budget: u32,
+}
+
+/// How the ships of the faction pick their target.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Personality {
+    /// Chases the closest enemy.
+    #[default]
+    Aggressive,
+    /// Only chases the enemies within `radius` of its home, else goes back home.
+    Defensive { radius: f32 },
+}
+
+impl FactionDefinition {
+    /// Target of a ship of the faction, given the closest enemy.
+    pub fn restrain_target(&self, enemy: Vec3) -> Vec3 {
+        match self.personality {
+            Personality::Aggressive => enemy,
+            Personality::Defensive { radius } => {
+                if enemy.truncate().distance(self.home) > radius {
+                    self.home.extend(enemy.z)
+                } else {
+                    enemy
+                }
+            }
+        }
+    }
+}
+
+/// Resolved definitions, sorted by faction, and how the factions are told
+/// apart on screen.
+#[derive(Resource, Default)]
+pub struct Factions {
+    definitions: Vec<FactionDefinition>,
+    palette: Palette,
+    cues: bool,
+}
+
+impl Factions {
+    pub fn definitions(&self) -> impl Iterator<Item = &FactionDefinition> {
+        self.definitions.iter()
+    }
+
+    pub fn get(&self, faction: Faction) -> Option<&FactionDefinition> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.id == faction)
+    }
+
+    /// Faction of the name, ignoring the case, or of the number.
+    pub fn find(&self, name: &str) -> Option<Faction> {
+        self.definitions
+            .iter()
+            .find(|definition| definition.name.eq_ignore_ascii_case(name))
+            .map(|definition| definition.id)
+            .or_else(|| name.parse().ok().map(Faction))
+    }
+
+    pub fn name(&self, faction: Faction) -> String {
+        self.get(faction).map_or_else(
+            || format!("Faction {}", faction.0),
+            |definition| definition.name.clone(),
+        )
+    }
+
+    /// Name prefixed with the badge of the faction when the cues are shown.
+    pub fn label(&self, faction: Faction) -> String {
+        if self.cues {
+            format!("{} {}", Badge::of(faction).symbol(), self.name(faction))
+        } else {
+            self.name(faction)
+        }
+    }
+
+    /// Color of the definition, unless replaced by a colorblind palette.
+    pub fn color(&self, faction: Faction) -> Color {
+        if let Some(colors) = self.palette.colors() {
+            return colors[faction.0 as usize % colors.len()].into();
+        }
+        self.get(faction).map_or_else(
+            || FALLBACK_COLORS[faction.0 as usize % FALLBACK_COLORS.len()].into(),
+            |definition| definition.color,
+        )
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    /// Whether the non-color cues are shown.
+    pub fn cues(&self) -> bool {
+        self.cues
+    }
+
+    pub fn set_appearance(&mut self, palette: Palette, cues: bool) {
+        self.palette = palette;
+        self.cues = cues;
+    }
+
+    pub fn home(&self, faction: Faction) -> Option<Vec2> {
+        self.get(faction).map(|definition| definition.home)
+    }
+
+    pub fn ai_backend(&self, faction: Faction) -> Option<AiBackend> {
+        self.get(faction)
+            .and_then(|definition| definition.ai_backend)
+    }
+
+    pub fn budget(&self, faction: Faction) -> u32 {
+        self.get(faction)
+            .map_or(DEFAULT_BUDGET, |definition| definition.budget)
+    }
+}
+
+/// Strong handle keeping the default factions loaded.
+#[derive(Resource)]
+pub struct DefaultFactionsHandle(pub Handle<FactionDefinitions>);
+
+fn default_budget() -> u32 {
+    DEFAULT_BUDGET
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Srgba::hex(&hex)
+        .map(Color::from)
+        .map_err(|err| D::Error::custom(format!("{hex}: {err}")))
+}
+
+fn load_default_factions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DefaultFactionsHandle(
+        asset_server.load(DEFAULT_FACTIONS_PATH),
+    ));
+}
+
+fn resolve_factions(
+    mut factions: ResMut<Factions>,
+    definitions: Res<Assets<FactionDefinitions>>,
+    asset_server: Res<AssetServer>,
+    load_order: Res<LoadOrder>,
+    scenario: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+) {
+    let mut files: Vec<(usize, &FactionDefinitions)> = definitions
+        .iter()
+        .map(|(id, file)| {
+            let priority = asset_server
+                .get_path(id)
+                .map_or(0, |path| load_order.priority(&path));
+            (priority, file)
+        })
+        .collect();
+    files.sort_by_key(|(priority, _)| *priority);
+    let scenario_factions = scenario
+        .and_then(|handle| scenarios.get(&handle.0))
+        .map(|scenario| scenario.factions.as_slice())
+        .unwrap_or_default();
+
+    let mut resolved: Vec<FactionDefinition> = vec![];
+    for definition in files
+        .iter()
+        .flat_map(|(_, file)| &file.0)
+        .chain(scenario_factions)
+    {
+        match resolved.iter_mut().find(|known| known.id == definition.id) {
+            Some(known) => *known = definition.clone(),
+            None => resolved.push(definition.clone()),
+        }
+    }
+    resolved.sort_by_key(|definition| definition.id.0);
+    factions.definitions = resolved;
+}
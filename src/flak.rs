@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    config::ShipStats,
+    damage::{self, damage_enemies_around, DamageEvent},
+    emp::Disabled,
+    factions::Factions,
+    launch,
+    scenario::ScenarioEntity,
+    sound::{Explosion, WeaponFired},
+    weapons::{self, Heat, Weapon, WeaponKind},
+    Faction, SimulationSet, Spaceship, Target,
+};
+
+const SHELL_SIZE: f32 = 6.;
+/// Distance of the muzzle ahead of the ship center.
+const MUZZLE_OFFSET: f32 = 35.;
+/// Seconds a burst keeps damaging the ships within it.
+const BURST_SECS: f32 = 0.6;
+const BURST_COLOR: Color = Color::srgb(1., 0.6, 0.2);
+
+/// Ships with a [`WeaponKind::Flak`] fire a shell every `fire_cooldown`
+/// seconds at their hostile target within `weapon_range`, fused to burst at
+/// the distance of the target when fired.
+///
+/// A burst damages the enemy ships within `flak_radius` by `flak_dps` per
+/// second for a short while, whether or not the target is still there, making
+/// tight formations risky.
+pub struct FlakPlugin;
+
+impl Plugin for FlakPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                // After the weapon cooldowns
+                fire_shells.after(weapons::fire_weapons),
+                move_shells.run_if(any_with_component::<Shell>),
+                damage_bursts.run_if(any_with_component::<Burst>),
+            )
+                .chain()
+                .before(damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, draw_bursts);
+    }
+}
+
+/// Flak shell, moved straight until its fuse burns out.
+#[derive(Component)]
+pub struct Shell {
+    pub faction: Faction,
+    pub velocity: Vec2,
+    pub radius: f32,
+    pub dps: f32,
+    /// Seconds until the burst.
+    pub fuse_secs: f32,
+}
+
+/// Damage sphere left by a [`Shell`].
+#[derive(Component)]
+pub struct Burst {
+    pub faction: Faction,
+    pub radius: f32,
+    pub dps: f32,
+    pub timer: Timer,
+}
+
+fn fire_shells(
+    mut commands: Commands,
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &Velocity,
+            &ShipStats,
+            &Target,
+            &mut Weapon,
+            &mut Heat,
+            &mut Ammo,
+        ),
+        (With<Spaceship>, Without<Disabled>),
+    >,
+    factions: Res<Factions>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    for (faction, transform, velocity, stats, target, mut weapon, mut heat, mut ammo) in
+        ships.iter_mut()
+    {
+        if stats.weapon != WeaponKind::Flak
+            || weapon.cooldown_secs > 0.
+            || heat.overheated_secs > 0.
+            || ammo.is_empty()
+            || !target.hostile
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+
+        weapon.cooldown_secs = stats.fire_cooldown;
+        heat.current += stats.heat_per_shot;
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        ammo.spend();
+        let heading = transform.up().truncate();
+        let muzzle = transform.translation.truncate() + heading * MUZZLE_OFFSET;
+        let to_target = target.translation.truncate() - muzzle;
+        let direction = to_target.normalize_or(heading);
+        commands.spawn((
+            ScenarioEntity,
+            Shell {
+                faction: *faction,
+                // Fused on the distance to travel, the ship velocity only offsets the burst
+                velocity: direction * stats.projectile_speed + velocity.linvel,
+                radius: stats.flak_radius,
+                dps: stats.flak_dps,
+                fuse_secs: to_target.length() / stats.projectile_speed,
+            },
+            Sprite::from_color(factions.color(*faction), Vec2::splat(SHELL_SIZE)),
+            Transform::from_translation(muzzle.extend(0.)),
+        ));
+        fired.send(WeaponFired { position: muzzle });
+    }
+}
+
+/// Moves the shells and turns the ones whose fuse burnt out into bursts.
+fn move_shells(
+    mut commands: Commands,
+    mut shells: Query<(Entity, &mut Shell, &mut Transform)>,
+    time: Res<Time>,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for (entity, mut shell, mut transform) in shells.iter_mut() {
+        transform.translation += (shell.velocity * time.delta_secs()).extend(0.);
+        shell.fuse_secs -= time.delta_secs();
+        if shell.fuse_secs > 0. {
+            continue;
+        }
+
+        commands.entity(entity).despawn_recursive();
+        commands.spawn((
+            ScenarioEntity,
+            Burst {
+                faction: shell.faction,
+                radius: shell.radius,
+                dps: shell.dps,
+                timer: Timer::from_seconds(BURST_SECS, TimerMode::Once),
+            },
+            Transform::from_translation(transform.translation),
+        ));
+        explosions.send(Explosion {
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
+fn damage_bursts(
+    mut commands: Commands,
+    mut bursts: Query<(Entity, &mut Burst, &Transform)>,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    time: Res<Time>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (entity, mut burst, transform) in bursts.iter_mut() {
+        damage_enemies_around(
+            ships.iter(),
+            burst.faction,
+            transform.translation.truncate(),
+            burst.radius,
+            burst.dps * time.delta_secs(),
+            &mut damages,
+        );
+        if burst.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn draw_bursts(bursts: Query<(&Burst, &Transform)>, mut gizmos: Gizmos) {
+    for (burst, transform) in bursts.iter() {
+        gizmos.circle_2d(
+            transform.translation.truncate(),
+            burst.radius,
+            BURST_COLOR.with_alpha(1. - burst.timer.fraction()),
+        );
+    }
+}
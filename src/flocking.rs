@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::{config::Configuration, gravity::SpatialGrid, Faction};
+
+/// Ships of every faction bucketed in cells of `flocking_radius`, to steer
+/// them like boids: away from their closest allies (separation), along the
+/// heading of the allies around (alignment) and toward their center
+/// (cohesion).
+///
+/// The forces are fractions of the thrust of the ships, weighted by the
+/// `*_weight`s of the [`Configuration`], and added to it by
+/// [`crate::apply_forces`].
+pub struct Flock {
+    grid: SpatialGrid<(Entity, Faction, Vec2)>,
+    radius: f32,
+    separation_distance: f32,
+}
+
+impl Flock {
+    /// Ships with their position and velocity.
+    pub fn new(
+        ships: impl Iterator<Item = (Entity, Faction, Vec2, Vec2)>,
+        configs: &Configuration,
+    ) -> Self {
+        Self {
+            grid: SpatialGrid::new(
+                configs.flocking_radius.max(1.),
+                ships.map(|(entity, faction, position, velocity)| {
+                    (position, (entity, faction, velocity))
+                }),
+            ),
+            radius: configs.flocking_radius,
+            separation_distance: configs.separation_distance,
+        }
+    }
+
+    /// Center of the allies within the `flocking_radius`, if any.
+    pub fn allies_center(&self, entity: Entity, faction: Faction, position: Vec2) -> Option<Vec2> {
+        let (sum, count) = self
+            .allies(entity, faction, position)
+            .fold((Vec2::ZERO, 0), |(sum, count), (ally_position, _)| {
+                (sum + ally_position, count + 1)
+            });
+        (count > 0).then(|| sum / count as f32)
+    }
+
+    /// Positions and velocities of the allies within the `flocking_radius`.
+    fn allies(
+        &self,
+        entity: Entity,
+        faction: Faction,
+        position: Vec2,
+    ) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        self.grid
+            .neighbors(position)
+            .filter(move |(other_position, (other, other_faction, _))| {
+                *other != entity
+                    && *other_faction == faction
+                    && other_position.distance(position) <= self.radius
+            })
+            .map(|(other_position, (.., velocity))| (*other_position, *velocity))
+    }
+
+    /// Sum of the weighted forces, as a fraction of the thrust.
+    pub fn steering(
+        &self,
+        entity: Entity,
+        faction: Faction,
+        position: Vec2,
+        configs: &Configuration,
+    ) -> Vec2 {
+        let mut separation = Vec2::ZERO;
+        let mut heading = Vec2::ZERO;
+        let mut center = Vec2::ZERO;
+        let mut allies = 0;
+        for (other_position, velocity) in self.allies(entity, faction, position) {
+            let offset = position - other_position;
+            let distance = offset.length();
+            if distance < self.separation_distance {
+                separation +=
+                    offset.normalize_or_zero() * (1. - distance / self.separation_distance);
+            }
+            heading += velocity;
+            center += other_position;
+            allies += 1;
+        }
+        if allies == 0 {
+            return Vec2::ZERO;
+        }
+        let alignment = heading.normalize_or_zero();
+        let cohesion = (center / allies as f32 - position).normalize_or_zero();
+        separation.clamp_length_max(1.) * configs.separation_weight
+            + alignment * configs.alignment_weight
+            + cohesion * configs.cohesion_weight
+    }
+}
@@ -0,0 +1,120 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use itertools::Itertools;
+use serde::Deserialize;
+
+use crate::{config::Configuration, factions::Factions, launch, Faction, SimulationSet, Spaceship};
+
+/// Ships of the factions with a `formation` in their definition fly in a
+/// [`FormationShape`] behind a leader, the first ship of the faction, each
+/// follower holding its [`FormationSlot`].
+///
+/// [`crate::apply_forces`] pulls the patrolling followers to their slot with
+/// [`hold_formation`], until combat breaks them loose.
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            assign_formation_slots
+                .before(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FormationShape {
+    /// Abreast of the leader.
+    Line,
+    /// Staggered behind the leader on both sides.
+    Wedge,
+    /// Around the leader.
+    Circle,
+}
+
+impl FormationShape {
+    /// Offset of the slot `index` (from 1) of a formation of `size` ships,
+    /// relative to the leader facing up.
+    fn offset(self, index: usize, size: usize, spacing: f32) -> Vec2 {
+        let rank = index.div_ceil(2) as f32 * spacing;
+        let side = if index % 2 == 1 { 1. } else { -1. };
+        match self {
+            Self::Line => Vec2::new(side * rank, 0.),
+            Self::Wedge => Vec2::new(side * rank, -rank),
+            Self::Circle => {
+                let followers = size.saturating_sub(1).max(1);
+                let radius = (spacing * followers as f32 / TAU).max(spacing);
+                Vec2::from_angle(TAU * (index - 1) as f32 / followers as f32).rotate(Vec2::Y)
+                    * radius
+            }
+        }
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Debug)]
+pub struct FormationSlot {
+    pub leader: Entity,
+    shape: FormationShape,
+    /// From 1, the leader being 0.
+    index: usize,
+    /// Number of ships in the formation, with the leader.
+    size: usize,
+}
+
+fn assign_formation_slots(
+    mut commands: Commands,
+    ships: Query<(Entity, &Faction, Option<&FormationSlot>), With<Spaceship>>,
+    factions: Res<Factions>,
+) {
+    let by_faction = ships
+        .iter()
+        .map(|(entity, faction, slot)| (*faction, (entity, slot)))
+        .into_group_map();
+    for (faction, mut members) in by_faction {
+        let shape = factions
+            .get(faction)
+            .and_then(|definition| definition.formation);
+        members.sort_by_key(|(entity, _)| *entity);
+        let Some(&(leader, _)) = members.first() else {
+            continue;
+        };
+        let size = members.len();
+        for (index, (entity, slot)) in members.into_iter().enumerate() {
+            let new_slot = shape.filter(|_| index > 0).map(|shape| FormationSlot {
+                leader,
+                shape,
+                index,
+                size,
+            });
+            match (slot, new_slot) {
+                (Some(slot), Some(new_slot)) if *slot == new_slot => {}
+                (_, Some(new_slot)) => {
+                    commands.entity(entity).insert(new_slot);
+                }
+                (Some(_), None) => {
+                    commands.entity(entity).remove::<FormationSlot>();
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// Force pulling the ship at `position` to its `slot` around the `leader`, as
+/// a fraction of the thrust weighted by the `formation_weight`.
+pub fn hold_formation(
+    slot: &FormationSlot,
+    leader: &Transform,
+    position: Vec2,
+    configs: &Configuration,
+) -> Vec2 {
+    let spacing = configs.formation_spacing.max(1.);
+    let offset = slot.shape.offset(slot.index, slot.size, spacing);
+    let slot_position =
+        leader.translation.truncate() + leader.rotation.mul_vec3(offset.extend(0.)).truncate();
+    ((slot_position - position) / spacing).clamp_length_max(1.) * configs.formation_weight
+}
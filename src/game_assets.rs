@@ -0,0 +1,120 @@
+use bevy::{asset::LoadState, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{config::ShipStats, GameState, Spaceship};
+
+/// Height of the thrust texture at full propulsion.
+const THRUST_LENGTH: f32 = 32.;
+
+/// Textures of `assets/textures`, loaded through the `AssetServer` when first
+/// entering [`GameState::Loading`], and hot reloaded with the `dev-tools`
+/// feature. Planets and asteroids fall back to mesh primitives when their
+/// texture is missing.
+pub struct GameAssetsPlugin;
+
+impl Plugin for GameAssetsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Loading),
+            load_game_assets.run_if(not(resource_exists::<GameAssets>)),
+        )
+        .add_observer(add_thrust_flame)
+        .add_systems(Update, update_thrust_flames);
+    }
+}
+
+#[derive(Resource)]
+pub struct GameAssets {
+    /// White ship of the classes without sprite, tinted with the faction color.
+    pub ship: Handle<Image>,
+    pub planet: Handle<Image>,
+    pub asteroid: Handle<Image>,
+    /// Flame behind the ships, stretched with their propulsion.
+    pub thrust: Handle<Image>,
+    /// Masks drawn over the default ship to tell the factions apart, see
+    /// [`crate::accessibility::HullPattern`].
+    pub hull_patterns: Vec<Handle<Image>>,
+}
+
+impl GameAssets {
+    /// Whether every texture is loaded or failed to, the game waits for it.
+    pub fn is_settled(&self, asset_server: &AssetServer) -> bool {
+        [&self.ship, &self.planet, &self.asteroid, &self.thrust]
+            .into_iter()
+            .chain(&self.hull_patterns)
+            .all(|handle| {
+                matches!(
+                    asset_server.load_state(handle),
+                    LoadState::Loaded | LoadState::Failed(_)
+                )
+            })
+    }
+}
+
+/// The texture, unless it failed to load.
+pub fn loaded_texture(asset_server: &AssetServer, handle: &Handle<Image>) -> Option<Handle<Image>> {
+    asset_server.is_loaded(handle).then(|| handle.clone())
+}
+
+#[derive(Component)]
+struct ThrustFlame;
+
+fn load_game_assets(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameAssets {
+        ship: asset_server.load("textures/ship.png"),
+        planet: asset_server.load("textures/planet.png"),
+        asteroid: asset_server.load("textures/asteroid.png"),
+        thrust: asset_server.load("textures/thrust.png"),
+        hull_patterns: ["stripes", "dots", "chevrons"]
+            .iter()
+            .map(|pattern| asset_server.load(format!("textures/patterns/{pattern}.png")))
+            .collect(),
+    });
+}
+
+fn add_thrust_flame(
+    trigger: Trigger<OnAdd, Spaceship>,
+    game_assets: Option<Res<GameAssets>>,
+    asset_server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let Some(image) = game_assets.and_then(|assets| loaded_texture(&asset_server, &assets.thrust))
+    else {
+        return;
+    };
+    commands
+        .entity(trigger.entity())
+        // The flame inherits the visibility of the ship
+        .insert(Visibility::default())
+        .with_children(|ship| {
+            ship.spawn((ThrustFlame, Sprite::from_image(image), Visibility::Hidden));
+        });
+}
+
+/// Stretches the flames behind the ships with their propulsion.
+fn update_thrust_flames(
+    ships: Query<(&ExternalForce, &ShipStats, &Collider), With<Spaceship>>,
+    mut flames: Query<(&Parent, &mut Transform, &mut Visibility), With<ThrustFlame>>,
+) {
+    for (parent, mut transform, mut visibility) in flames.iter_mut() {
+        let Ok((force, stats, collider)) = ships.get(parent.get()) else {
+            continue;
+        };
+        let throttle = if stats.propulsion_force > 0. {
+            (force.force.length() / stats.propulsion_force).min(1.)
+        } else {
+            0.
+        };
+        *visibility = if throttle > 0. {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let half_height = collider
+            .as_cuboid()
+            .map_or(30., |cuboid| cuboid.half_extents().y);
+        let length = THRUST_LENGTH * throttle;
+        transform.translation = Vec3::new(0., -half_height - length / 2., -0.1);
+        transform.scale = Vec3::new(1., throttle, 1.);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{config::Configuration, launch, SimulationSet, Spaceship};
+
+/// Scales the mass of the wells into accelerations, in pixels per second
+/// squared at a distance of 1 pixel for a mass of 1.
+const GRAVITATIONAL_CONSTANT: f32 = 60.;
+/// Distance beyond which the ships don't attract each other, also the size
+/// of the cells of the [`SpatialGrid`].
+const SHIP_GRAVITY_RANGE: f32 = 300.;
+/// Distance within which the attraction of a ship stops increasing.
+const SHIP_GRAVITY_RADIUS: f32 = 30.;
+
+/// Attracts the dynamic bodies toward the [`GravityWell`]s, with an
+/// acceleration decreasing with the square of the distance, whatever the
+/// mass of the bodies.
+///
+/// The attraction changes the velocity of the bodies, on top of their thrust
+/// and the other forces.
+///
+/// With `ship_gravity`, the ships also attract each other with their
+/// `ship_mass`, within a short range so that only the ships in the
+/// neighboring cells of a [`SpatialGrid`] are checked.
+pub struct GravityPlugin;
+
+impl Plugin for GravityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                apply_gravity.run_if(any_with_component::<GravityWell>),
+                apply_ship_gravity.run_if(|configs: Res<Configuration>| configs.ship_gravity),
+            )
+                // After the thrust of the ships
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+/// Body attracting the others, like the planets.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct GravityWell {
+    pub mass: f32,
+    /// Distance within which the attraction stops increasing.
+    pub radius: f32,
+}
+
+impl GravityWell {
+    /// Acceleration of a body at `offset` from the center of the well.
+    pub fn acceleration(&self, offset: Vec2) -> Vec2 {
+        let distance = offset.length().max(self.radius);
+        -offset.normalize_or_zero() * GRAVITATIONAL_CONSTANT * self.mass / distance.powi(2)
+    }
+}
+
+/// Centers of the wells, to compute accelerations with [`acceleration_at`].
+pub fn wells<'a>(
+    wells: impl Iterator<Item = (&'a Transform, &'a GravityWell)>,
+) -> Vec<(Vec2, GravityWell)> {
+    wells
+        .map(|(transform, well)| (transform.translation.truncate(), *well))
+        .collect()
+}
+
+/// Acceleration of all the `wells` at `position`.
+pub fn acceleration_at(position: Vec2, wells: &[(Vec2, GravityWell)]) -> Vec2 {
+    wells
+        .iter()
+        .map(|(center, well)| well.acceleration(position - *center))
+        .sum()
+}
+
+fn apply_gravity(
+    wells: Query<(&Transform, &GravityWell)>,
+    mut bodies: Query<(&RigidBody, &Transform, &mut Velocity), Without<GravityWell>>,
+    time: Res<Time>,
+) {
+    let wells = self::wells(wells.iter());
+    for (body, transform, mut velocity) in bodies.iter_mut() {
+        if *body != RigidBody::Dynamic {
+            continue;
+        }
+        let acceleration = acceleration_at(transform.translation.truncate(), &wells);
+        velocity.linvel += acceleration * time.delta_secs();
+    }
+}
+
+/// Positions bucketed in square cells, to find the close ones without
+/// checking every pair.
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Vec2, T)>>,
+}
+
+impl<T> SpatialGrid<T> {
+    pub fn new(cell_size: f32, items: impl Iterator<Item = (Vec2, T)>) -> Self {
+        let mut grid = Self {
+            cell_size,
+            cells: HashMap::new(),
+        };
+        for (position, item) in items {
+            let cell = grid.cell(position);
+            grid.cells.entry(cell).or_default().push((position, item));
+        }
+        grid
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Items of the cell of `position` and the 8 around it, including all the
+    /// items within `cell_size`.
+    pub fn neighbors(&self, position: Vec2) -> impl Iterator<Item = &(Vec2, T)> {
+        let center = self.cell(position);
+        (-1..=1)
+            .flat_map(move |x| (-1..=1).map(move |y| center + IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+    }
+}
+
+fn apply_ship_gravity(
+    mut ships: Query<(Entity, &Transform, &mut Velocity), With<Spaceship>>,
+    configs: Res<Configuration>,
+    time: Res<Time>,
+) {
+    let grid = SpatialGrid::new(
+        SHIP_GRAVITY_RANGE,
+        ships
+            .iter()
+            .map(|(entity, transform, _)| (transform.translation.truncate(), entity)),
+    );
+    let well = GravityWell {
+        mass: configs.ship_mass,
+        radius: SHIP_GRAVITY_RADIUS,
+    };
+    for (entity, transform, mut velocity) in ships.iter_mut() {
+        let position = transform.translation.truncate();
+        let acceleration: Vec2 = grid
+            .neighbors(position)
+            .filter(|(other_position, other)| {
+                *other != entity && other_position.distance(position) <= SHIP_GRAVITY_RANGE
+            })
+            .map(|(other_position, _)| well.acceleration(position - *other_position))
+            .sum();
+        velocity.linvel += acceleration * time.delta_secs();
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    damage::DamageEvent,
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    star_system::{self, StarSystem},
+    GameState, SimulationSet, Spaceship,
+};
+
+const HAZARD_COLOR: Color = Color::srgba(0.5, 1., 0.2, 0.15);
+/// Seconds between two doses of damage.
+const TICK_SECS: f32 = 1.;
+/// Distance ahead of the ships within which they steer around the hazards.
+const LOOKAHEAD: f32 = 250.;
+/// Distance from the edge of the hazards kept by the ships going around.
+const AVOID_MARGIN: f32 = 60.;
+
+/// Radiation zones of the `hazards` of the scenario, or around the star and
+/// anomalies of the generated [`StarSystem`], damaging the ships inside
+/// with their `dps` every second.
+///
+/// The ships steer around the hazards lying on their way, without avoiding
+/// them when their target is inside.
+pub struct HazardsPlugin;
+
+impl Plugin for HazardsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            spawn_hazards.after(star_system::generate_star_system),
+        )
+        .add_systems(
+            FixedUpdate,
+            irradiate_ships
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Hazard>.and(launch::is_authoritative)),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HazardDefinition {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Damage per second to the ships inside.
+    #[serde(default = "default_dps")]
+    pub dps: f32,
+}
+
+fn default_dps() -> f32 {
+    10.
+}
+
+#[derive(Component)]
+pub struct Hazard {
+    pub radius: f32,
+    pub dps: f32,
+    tick: Timer,
+}
+
+fn spawn_hazards(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    star_system: Option<Res<StarSystem>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let hazards = match (handle, &star_system) {
+        (Some(handle), _) => match scenarios.get(&handle.0) {
+            Some(scenario) => scenario.hazards.as_slice(),
+            None => return,
+        },
+        (None, Some(star_system)) => star_system.hazards.as_slice(),
+        (None, None) => return,
+    };
+    let material = materials.add(HAZARD_COLOR);
+    for hazard in hazards {
+        commands.spawn((
+            ScenarioEntity,
+            Hazard {
+                radius: hazard.radius,
+                dps: hazard.dps,
+                tick: Timer::from_seconds(TICK_SECS, TimerMode::Repeating),
+            },
+            Mesh2d(meshes.add(Circle::new(hazard.radius))),
+            MeshMaterial2d(material.clone()),
+            // Above the planets, below the ships
+            Transform::from_translation(hazard.position.extend(-0.5)),
+        ));
+    }
+}
+
+fn irradiate_ships(
+    mut hazards: Query<(&mut Hazard, &Transform)>,
+    ships: Query<(Entity, &Transform), With<Spaceship>>,
+    time: Res<Time>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (mut hazard, hazard_transform) in hazards.iter_mut() {
+        if !hazard.tick.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let center = hazard_transform.translation.truncate();
+        for (ship, transform) in ships.iter() {
+            if transform.translation.truncate().distance(center) > hazard.radius {
+                continue;
+            }
+            damages.send(DamageEvent {
+                target: ship,
+                amount: hazard.dps * TICK_SECS,
+                source: None,
+                slingshot: false,
+                direction: None,
+                attacker: None,
+            });
+        }
+    }
+}
+
+/// Centers and radii of the hazards.
+pub fn zones(hazards: &Query<(&Hazard, &Transform)>) -> Vec<(Vec2, f32)> {
+    hazards
+        .iter()
+        .map(|(hazard, transform)| (transform.translation.truncate(), hazard.radius))
+        .collect()
+}
+
+/// Point to steer to from `position` to reach `target` around the `zones`
+/// lying ahead, pushed away from them more the deeper the way goes through.
+pub fn avoid(position: Vec2, target: Vec2, zones: &[(Vec2, f32)]) -> Vec2 {
+    let Some(direction) = (target - position).try_normalize() else {
+        return target;
+    };
+    let ahead = position + direction * LOOKAHEAD.min(position.distance(target));
+    let mut steering = direction;
+    for &(center, radius) in zones {
+        // Going in on purpose
+        if target.distance(center) <= radius {
+            continue;
+        }
+        let closest = closest_on_segment(position, ahead, center);
+        let clearance = radius + AVOID_MARGIN;
+        let distance = closest.distance(center);
+        if distance >= clearance {
+            continue;
+        }
+        // Straight at the center, around by the left
+        let away = (closest - center)
+            .try_normalize()
+            .unwrap_or(direction.perp());
+        steering += away * (1. - distance / clearance) * 2.;
+    }
+    if steering == direction {
+        return target;
+    }
+    position + steering.normalize_or(direction) * position.distance(target)
+}
+
+fn closest_on_segment(start: Vec2, end: Vec2, point: Vec2) -> Vec2 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    if length_squared == 0. {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / length_squared).clamp(0., 1.);
+    start + segment * t
+}
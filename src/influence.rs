@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{damage::Health, launch, Faction, GameState, SimulationSet, Spaceship};
+
+/// Size of the cells of the [`InfluenceMap`].
+const CELL_SIZE: f32 = 400.;
+/// Strength below which a faction is gone from a cell, what is left once its
+/// ships left being rounding errors.
+const MIN_STRENGTH: f32 = 1.;
+/// Health traded for a pixel of distance when picking the weakest flank.
+const DISTANCE_COST: f32 = 0.05;
+/// Ratio of enemy to allied strength from which the patrolling ships keep
+/// away from an objective.
+pub const OVERWHELMING_RATIO: f32 = 2.;
+
+/// Coarse map of the strength of each faction, the health of its ships per
+/// cell of [`CELL_SIZE`], updated incrementally as the ships change cell,
+/// health or faction, or are destroyed.
+///
+/// The commanders attack the weakest enemy flank rather than the center of
+/// the enemy fleet, see [`crate::commander`], and the patrolling ships keep
+/// away from the overwhelming enemy concentrations, see
+/// [`crate::apply_forces`].
+pub struct InfluencePlugin;
+
+impl Plugin for InfluencePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InfluenceMap>()
+            .add_systems(OnEnter(GameState::InGame), reset_influence)
+            .add_systems(
+                FixedUpdate,
+                update_influence
+                    .before(crate::update_targets)
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct InfluenceMap {
+    /// Strength of each faction present in the cell.
+    cells: HashMap<IVec2, Vec<(Faction, f32)>>,
+    /// Strength last added by each ship.
+    contributions: HashMap<Entity, Contribution>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Contribution {
+    cell: IVec2,
+    faction: Faction,
+    strength: f32,
+}
+
+impl InfluenceMap {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    fn add(&mut self, contribution: Contribution, sign: f32) {
+        let factions = self.cells.entry(contribution.cell).or_default();
+        match factions
+            .iter_mut()
+            .find(|(faction, _)| *faction == contribution.faction)
+        {
+            Some((_, strength)) => *strength += contribution.strength * sign,
+            None => factions.push((contribution.faction, contribution.strength * sign)),
+        }
+        factions.retain(|(_, strength)| *strength >= MIN_STRENGTH);
+        if factions.is_empty() {
+            self.cells.remove(&contribution.cell);
+        }
+    }
+
+    fn set(&mut self, entity: Entity, contribution: Option<Contribution>) {
+        let previous = match contribution {
+            Some(contribution) => self.contributions.insert(entity, contribution),
+            None => self.contributions.remove(&entity),
+        };
+        if previous == contribution {
+            return;
+        }
+        if let Some(previous) = previous {
+            self.add(previous, -1.);
+        }
+        if let Some(contribution) = contribution {
+            self.add(contribution, 1.);
+        }
+    }
+
+    /// Strength of the factions in the cell of `position` and the 8 around,
+    /// allied to `faction` or not.
+    fn around(&self, position: Vec2, faction: Faction, allied: bool) -> f32 {
+        let center = Self::cell(position);
+        (-1..=1)
+            .flat_map(|x| (-1..=1).map(move |y| center + IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(|(other_faction, _)| (*other_faction == faction) == allied)
+            .map(|(_, strength)| strength)
+            .sum()
+    }
+
+    pub fn ally_strength(&self, position: Vec2, faction: Faction) -> f32 {
+        self.around(position, faction, true)
+    }
+
+    pub fn enemy_strength(&self, position: Vec2, faction: Faction) -> f32 {
+        self.around(position, faction, false)
+    }
+
+    /// Center of the enemy cell where the enemies around outweigh the allies
+    /// the least, the closer to `from` the better.
+    ///
+    /// Ties are broken on the cell coordinates rather than the order of the
+    /// map, so that the lockstep peers pick the same cell.
+    pub fn weakest_flank(&self, faction: Faction, from: Vec2) -> Option<Vec2> {
+        self.cells
+            .iter()
+            .filter(|(_, factions)| factions.iter().any(|(other, _)| *other != faction))
+            .map(|(cell, _)| {
+                let center = (cell.as_vec2() + 0.5) * CELL_SIZE;
+                let score = self.enemy_strength(center, faction)
+                    - self.ally_strength(center, faction)
+                    + center.distance(from) * DISTANCE_COST;
+                (*cell, center, score)
+            })
+            .min_by(|(a_cell, _, a), (b_cell, _, b)| {
+                a.total_cmp(b)
+                    .then(a_cell.to_array().cmp(&b_cell.to_array()))
+            })
+            .map(|(_, center, _)| center)
+    }
+}
+
+fn reset_influence(mut influence: ResMut<InfluenceMap>) {
+    *influence = InfluenceMap::default();
+}
+
+fn update_influence(
+    mut influence: ResMut<InfluenceMap>,
+    ships: Query<(Entity, &Faction, &Transform, &Health), With<Spaceship>>,
+    mut destroyed: RemovedComponents<Spaceship>,
+) {
+    for entity in destroyed.read() {
+        influence.set(entity, None);
+    }
+    for (entity, faction, transform, health) in ships.iter() {
+        let contribution = Contribution {
+            cell: InfluenceMap::cell(transform.translation.truncate()),
+            faction: *faction,
+            strength: health.current.max(0.),
+        };
+        influence.set(entity, Some(contribution));
+    }
+}
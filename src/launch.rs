@@ -0,0 +1,201 @@
+use std::{net::SocketAddr, path::PathBuf, str::FromStr};
+
+use bevy::prelude::*;
+
+use crate::share_code::ShareCode;
+
+const USAGE: &str = "\
+Usage: gravity-war [OPTIONS]
+
+Options:
+  --seed <u64>              Seed of the game random generator [default: 42]
+  --scenario <path>         Scenario to play, relative to the assets folder
+                            (or `mods://` for the mods folder)
+  --code <code>             Share code of a game, setting its seed and scenario
+  --headless                Run the simulation without window nor rendering
+  --factions <count>        Spawn an initial fleet for each of the factions [default: 0]
+  --window <width>x<height> Window size [default: from the user settings]
+  --timescale <factor>      Speed of the simulation [default: 1]
+  --host <port>             Host a networked game on the UDP port
+  --connect <ip>:<port>     Join the networked game of a host
+  --browse                  List the games hosted on the LAN to join one
+  --lockstep                Exchange only the inputs, every peer simulating the
+                            battle (same seed, scenario and settings needed)
+  --spectate                Join with --connect or --browse as a spectator
+  --api <port>              Serve the JSON control API over WebSocket on localhost
+  --chat <channel>          Spawn the ships asked with `!spawn <faction> [class]`
+                            in the chat channel
+  --chat-server <host>:<port>
+                            IRC server of the chat [default: irc.chat.twitch.tv:6667]
+  --help                    Print this message";
+
+const DEFAULT_CHAT_SERVER: &str = "irc.chat.twitch.tv:6667";
+
+/// Startup options given on the command line.
+#[derive(Resource, Debug, Clone)]
+pub struct LaunchOptions {
+    pub seed: u64,
+    pub scenario: Option<PathBuf>,
+    pub headless: bool,
+    pub factions: u32,
+    pub window: Option<(f32, f32)>,
+    pub timescale: f32,
+    pub network: Option<NetworkRole>,
+    pub lockstep: bool,
+    /// Watches a networked game without playing.
+    pub spectator: bool,
+    /// Port of the WebSocket control API.
+    pub api: Option<u16>,
+    /// IRC channel of the chat integration.
+    pub chat: Option<String>,
+    pub chat_server: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NetworkRole {
+    /// Runs the simulation and streams it to the clients.
+    Host(u16),
+    /// Sends inputs to the host and displays the state it receives.
+    Client(SocketAddr),
+    /// Looks for hosts on the LAN, then becomes a client.
+    Browse,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            scenario: None,
+            headless: false,
+            factions: 0,
+            window: None,
+            timescale: 1.,
+            network: None,
+            lockstep: false,
+            spectator: false,
+            api: None,
+            chat: None,
+            chat_server: DEFAULT_CHAT_SERVER.to_string(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    /// Parses the process arguments, exits with the usage on invalid ones.
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--seed" => options.seed = parse_value(&arg, args.next())?,
+                "--scenario" => options.scenario = Some(parse_value(&arg, args.next())?),
+                "--code" => {
+                    let code: ShareCode = parse_value(&arg, args.next())?;
+                    options.seed = code.seed;
+                    options.scenario = code.scenario;
+                }
+                "--headless" => options.headless = true,
+                "--factions" => options.factions = parse_value(&arg, args.next())?,
+                "--window" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    options.window = value
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .map(Some)
+                        .ok_or_else(|| format!("Invalid value for {arg}: {value}"))?;
+                }
+                "--timescale" => {
+                    let value: f32 = parse_value(&arg, args.next())?;
+                    // Time can't run backward nor at an undefined speed
+                    if !value.is_finite() || value < 0. {
+                        return Err(format!("Invalid value for {arg}: {value}"));
+                    }
+                    options.timescale = value;
+                }
+                "--host" => {
+                    options.network = Some(NetworkRole::Host(parse_value(&arg, args.next())?))
+                }
+                "--lockstep" => options.lockstep = true,
+                "--spectate" => options.spectator = true,
+                "--api" => options.api = Some(parse_value(&arg, args.next())?),
+                "--chat" => options.chat = Some(parse_value(&arg, args.next())?),
+                "--chat-server" => options.chat_server = parse_value(&arg, args.next())?,
+                "--browse" => options.network = Some(NetworkRole::Browse),
+                "--connect" => {
+                    options.network = Some(NetworkRole::Client(parse_value(&arg, args.next())?))
+                }
+                "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("Unknown argument: {arg}")),
+            }
+        }
+
+        if options.spectator {
+            if !matches!(
+                options.network,
+                Some(NetworkRole::Client(_) | NetworkRole::Browse)
+            ) {
+                return Err("--spectate needs --connect or --browse".to_string());
+            }
+            if options.lockstep {
+                return Err("Lockstep games can't be spectated".to_string());
+            }
+        }
+
+        Ok(options)
+    }
+
+    pub fn is_lockstep(&self) -> bool {
+        self.lockstep && self.network.is_some()
+    }
+}
+
+fn parse_value<T: FromStr>(arg: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Missing value for {arg}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {arg}: {value}"))
+}
+
+/// Clients of a replicated networked game only display the state received
+/// from the host, while lockstep peers all simulate the battle.
+pub fn is_authoritative(options: Res<LaunchOptions>) -> bool {
+    options.lockstep
+        || !matches!(
+            options.network,
+            Some(NetworkRole::Client(_) | NetworkRole::Browse)
+        )
+}
+
+/// Spectators control the camera instead of a fleet.
+pub fn is_spectator(options: Res<LaunchOptions>) -> bool {
+    options.spectator
+}
+
+/// Inputs are applied on the fixed ticks agreed by the lockstep peers.
+pub fn is_lockstep(options: Res<LaunchOptions>) -> bool {
+    options.is_lockstep()
+}
+
+/// Runs without window, so the cameras see nothing.
+pub fn is_headless(options: Res<LaunchOptions>) -> bool {
+    options.headless
+}
+
+pub fn apply_timescale(options: Res<LaunchOptions>, mut time: ResMut<Time<Virtual>>) {
+    time.set_relative_speed(options.timescale);
+}
@@ -0,0 +1,314 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    launch::LaunchOptions,
+    localization::Localization,
+    scenario::{Scenario, ScenarioEnded, ScenarioHandle, Waves, WinCondition},
+    scripting::Score,
+    settings::UserSettings,
+};
+
+/// Entries shown on the screen.
+const SHOWN_ENTRIES: usize = 15;
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Results of the survival scenarios (`Survive` win condition) and of the race
+/// scenarios (`Eliminate` win condition, as fast as possible), kept in
+/// `~/.local/share/gravity-war/leaderboard.ron` (or the platform equivalent).
+///
+/// The leaderboard screen is toggled with `F8` by default. There, the digit
+/// keys sort the entries and `F` cycles through the scenarios.
+pub struct LeaderboardPlugin;
+
+impl Plugin for LeaderboardPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Leaderboard::load())
+            .init_resource::<LeaderboardView>()
+            .add_systems(
+                Update,
+                (
+                    record_result.run_if(on_event::<ScenarioEnded>),
+                    toggle_screen,
+                    (change_view, update_screen)
+                        .chain()
+                        .run_if(any_with_component::<LeaderboardText>),
+                )
+                    .chain(),
+            );
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default)]
+struct Leaderboard {
+    entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+enum Mode {
+    Survival,
+    Race,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct LeaderboardEntry {
+    mode: Mode,
+    /// Name of the scenario.
+    scenario: String,
+    /// Points awarded by the scripts to the player faction.
+    score: i64,
+    /// Number of waves spawned during the run.
+    wave: u32,
+    time_secs: f32,
+    seed: u64,
+    /// Seconds since the Unix epoch.
+    date: u64,
+    /// Survived, or eliminated the other faction.
+    won: bool,
+}
+
+#[derive(Clone, Copy, Default)]
+enum SortKey {
+    #[default]
+    Score,
+    Wave,
+    /// Shortest first.
+    Time,
+    /// Most recent first.
+    Date,
+}
+
+#[derive(Resource, Default)]
+struct LeaderboardView {
+    sort: SortKey,
+    /// Only shows the entries of this scenario.
+    scenario: Option<String>,
+}
+
+#[derive(Component)]
+struct LeaderboardText;
+
+impl Leaderboard {
+    /// `None` on platforms without a data directory.
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("gravity-war").join("leaderboard.ron"))
+    }
+
+    /// Empty when the file is missing or invalid.
+    fn load() -> Self {
+        let Some(text) = Self::path().and_then(|path| fs::read_to_string(path).ok()) else {
+            return Self::default();
+        };
+        ron::from_str(&text).unwrap_or_else(|err| {
+            warn!("Ignoring invalid leaderboard: {err}");
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Scenario names, sorted.
+    fn scenarios(&self) -> Vec<String> {
+        let mut scenarios: Vec<String> = self
+            .entries
+            .iter()
+            .map(|entry| entry.scenario.clone())
+            .collect();
+        scenarios.sort();
+        scenarios.dedup();
+        scenarios
+    }
+}
+
+fn record_result(
+    mut events: EventReader<ScenarioEnded>,
+    mut leaderboard: ResMut<Leaderboard>,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    score: Res<Score>,
+    waves: Res<Waves>,
+    options: Res<LaunchOptions>,
+) {
+    let Some(scenario) = handle.and_then(|handle| scenarios.get(&handle.0)) else {
+        return;
+    };
+    for event in events.read() {
+        let (mode, faction, won) = match scenario.win_condition {
+            WinCondition::Survive { faction, .. } => {
+                (Mode::Survival, faction, event.winner == Some(faction))
+            }
+            // Only the finished races are recorded
+            WinCondition::Eliminate(_) => match event.winner {
+                Some(winner) => (Mode::Race, winner, true),
+                None => continue,
+            },
+            WinCondition::LastFactionStanding | WinCondition::None => continue,
+        };
+
+        leaderboard.entries.push(LeaderboardEntry {
+            mode,
+            scenario: scenario.name.clone(),
+            score: score.0.get(&faction).copied().unwrap_or(0),
+            wave: waves.0,
+            time_secs: event.elapsed_secs,
+            seed: options.seed,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            won,
+        });
+        match leaderboard.save() {
+            Ok(()) => info!("Recorded the result in the leaderboard"),
+            Err(err) => error!("Failed to save the leaderboard: {err}"),
+        }
+    }
+}
+
+fn toggle_screen(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    texts: Query<Entity, With<LeaderboardText>>,
+) {
+    if !keyboard.just_pressed(settings.input.leaderboard) {
+        return;
+    }
+
+    if texts.is_empty() {
+        commands.spawn((
+            LeaderboardText,
+            Text::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+        ));
+    } else {
+        for text in texts.iter() {
+            commands.entity(text).despawn_recursive();
+        }
+    }
+}
+
+fn change_view(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    leaderboard: Res<Leaderboard>,
+    mut view: ResMut<LeaderboardView>,
+) {
+    for (key, sort) in [
+        (KeyCode::Digit1, SortKey::Score),
+        (KeyCode::Digit2, SortKey::Wave),
+        (KeyCode::Digit3, SortKey::Time),
+        (KeyCode::Digit4, SortKey::Date),
+    ] {
+        if keyboard.just_pressed(key) {
+            view.sort = sort;
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        // All the scenarios, then each one
+        let scenarios = leaderboard.scenarios();
+        let next_index = match &view.scenario {
+            Some(scenario) => scenarios.iter().position(|s| s == scenario).map(|i| i + 1),
+            None => Some(0),
+        };
+        view.scenario = next_index.and_then(|index| scenarios.get(index)).cloned();
+    }
+}
+
+fn update_screen(
+    mut texts: Query<&mut Text, With<LeaderboardText>>,
+    leaderboard: Res<Leaderboard>,
+    view: Res<LeaderboardView>,
+    localization: Res<Localization>,
+) {
+    let Ok(mut text) = texts.get_single_mut() else {
+        return;
+    };
+
+    let mut entries: Vec<&LeaderboardEntry> = leaderboard
+        .entries
+        .iter()
+        .filter(|entry| view.scenario.as_ref().is_none_or(|s| *s == entry.scenario))
+        .collect();
+    match view.sort {
+        SortKey::Score => entries.sort_by(|a, b| b.score.cmp(&a.score)),
+        SortKey::Wave => entries.sort_by(|a, b| b.wave.cmp(&a.wave)),
+        SortKey::Time => entries.sort_by(|a, b| a.time_secs.total_cmp(&b.time_secs)),
+        SortKey::Date => entries.sort_by(|a, b| b.date.cmp(&a.date)),
+    }
+
+    let scenario = match &view.scenario {
+        Some(scenario) => scenario.clone(),
+        None => localization.text("leaderboard-all-scenarios"),
+    };
+    let mut lines = vec![
+        localization.format("leaderboard-title", &[("scenario", scenario.into())]),
+        localization.text("leaderboard-help"),
+    ];
+    lines.extend(entries.iter().take(SHOWN_ENTRIES).map(|entry| {
+        let secs = entry.time_secs as u32;
+        let mode = localization.text(match entry.mode {
+            Mode::Survival => "leaderboard-survival",
+            Mode::Race => "leaderboard-race",
+        });
+        localization.format(
+            "leaderboard-entry",
+            &[
+                ("mode", mode.into()),
+                ("scenario", entry.scenario.as_str().into()),
+                ("score", entry.score.into()),
+                ("wave", entry.wave.into()),
+                ("time", format!("{}:{:02}", secs / 60, secs % 60).into()),
+                ("won", if entry.won { "yes" } else { "no" }.into()),
+                // Formatted as a string, the numbers of Fluent lose precision
+                ("seed", entry.seed.to_string().into()),
+                ("date", format_date(entry.date).into()),
+            ],
+        )
+    }));
+    if entries.is_empty() {
+        lines.push(localization.text("leaderboard-empty"));
+    }
+    text.0 = lines.join("\n");
+}
+
+/// `YYYY-MM-DD` of a Unix time, in UTC.
+pub fn format_date(unix_secs: u64) -> String {
+    // Days to civil date, from Howard Hinnant's algorithm
+    let days = (unix_secs / SECS_PER_DAY) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year}-{month:02}-{day:02}")
+}
@@ -0,0 +1,163 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    prelude::*,
+    render::mesh::{Indices, PrimitiveTopology},
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{gravity::GravityWell, scenario::Planet, solar_wind::Star, Spaceship};
+
+/// Darkness of the side of the ships away from the stars.
+const SHADE_ALPHA: f32 = 0.6;
+/// Darkness of the shadows of the planets, fading out along their length.
+const SHADOW_ALPHA: f32 = 0.5;
+/// Length of the shadows, in radii of their planet.
+const SHADOW_LENGTH: f32 = 6.;
+
+/// Ships lit from the direction of the closest [`Star`], and planets casting
+/// soft shadows away from it, darkening the ships flying through them.
+///
+/// Drawn as overlays of gradients turned away from the star, children of the
+/// lit bodies. Only built with the `lighting` feature, and hidden in systems
+/// without star.
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_lighting).add_systems(
+            Update,
+            (shade_ships, cast_planet_shadows, turn_away_from_stars).chain(),
+        );
+    }
+}
+
+/// Overlay turned away from the closest star, its local `+Y` the darkest.
+#[derive(Component)]
+struct Unlit;
+
+/// Overlay of a ship, scaled to its collider.
+#[derive(Component)]
+struct Shade;
+
+#[derive(Resource)]
+struct LightingMeshes {
+    shade: Handle<Mesh>,
+    shadow: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+}
+
+fn setup_lighting(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(LightingMeshes {
+        // Unit square, lit at the bottom and dark at the top
+        shade: meshes.add(gradient_quad(-0.5, 0.5, 0., SHADE_ALPHA)),
+        // From the center of the planet, dark near it and fading out
+        shadow: meshes.add(gradient_quad(0., 1., SHADOW_ALPHA, 0.)),
+        // White, to keep the colors of the vertices
+        material: materials.add(Color::WHITE),
+    });
+}
+
+/// Black quad of unit width between `bottom` and `top`, its alpha going from
+/// `bottom_alpha` to `top_alpha`.
+fn gradient_quad(bottom: f32, top: f32, bottom_alpha: f32, top_alpha: f32) -> Mesh {
+    let positions = vec![
+        [-0.5, bottom, 0.],
+        [0.5, bottom, 0.],
+        [0.5, top, 0.],
+        [-0.5, top, 0.],
+    ];
+    let colors = vec![
+        [0., 0., 0., bottom_alpha],
+        [0., 0., 0., bottom_alpha],
+        [0., 0., 0., top_alpha],
+        [0., 0., 0., top_alpha],
+    ];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(vec![0, 1, 2, 0, 2, 3]))
+}
+
+/// Shades the new ships, and fits the shades to the edited ship definitions.
+fn shade_ships(
+    mut commands: Commands,
+    ships: Query<(Entity, &Collider, Option<&Children>), (With<Spaceship>, Changed<Collider>)>,
+    mut shades: Query<&mut Transform, With<Shade>>,
+    lighting: Res<LightingMeshes>,
+) {
+    for (ship, collider, children) in ships.iter() {
+        // Covers the ship whatever its rotation
+        let size = collider
+            .as_cuboid()
+            .map_or(20., |cuboid| cuboid.half_extents().max_element() * 2.);
+        let scale = Vec3::new(size, size, 1.);
+        let shade = children
+            .into_iter()
+            .flatten()
+            .find(|child| shades.contains(**child));
+        match shade.and_then(|shade| shades.get_mut(*shade).ok()) {
+            Some(mut transform) => transform.scale = scale,
+            None => {
+                commands.entity(ship).with_child((
+                    Unlit,
+                    Shade,
+                    Mesh2d(lighting.shade.clone()),
+                    MeshMaterial2d(lighting.material.clone()),
+                    Transform::from_xyz(0., 0., 0.1).with_scale(scale),
+                ));
+            }
+        }
+    }
+}
+
+fn cast_planet_shadows(
+    mut commands: Commands,
+    planets: Query<(Entity, &GravityWell), Added<Planet>>,
+    lighting: Res<LightingMeshes>,
+) {
+    for (planet, well) in planets.iter() {
+        commands.entity(planet).with_child((
+            Unlit,
+            Mesh2d(lighting.shadow.clone()),
+            MeshMaterial2d(lighting.material.clone()),
+            // Above the ships, the planets being behind them
+            Transform::from_xyz(0., 0., 1.5).with_scale(Vec3::new(
+                well.radius * 2.,
+                well.radius * SHADOW_LENGTH,
+                1.,
+            )),
+        ));
+    }
+}
+
+fn turn_away_from_stars(
+    stars: Query<&GlobalTransform, With<Star>>,
+    bodies: Query<&GlobalTransform, Without<Unlit>>,
+    mut overlays: Query<(&Parent, &mut Transform, &mut Visibility), With<Unlit>>,
+) {
+    for (parent, mut transform, mut visibility) in overlays.iter_mut() {
+        let Ok(body) = bodies.get(parent.get()) else {
+            continue;
+        };
+        let position = body.translation().truncate();
+        let closest_star = stars
+            .iter()
+            .map(|star| star.translation().truncate())
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+        let Some(away) = closest_star.and_then(|star| (position - star).try_normalize()) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        let (_, body_rotation, _) = body.to_scale_rotation_translation();
+        transform.rotation =
+            body_rotation.inverse() * Quat::from_rotation_z(Vec2::Y.angle_to(away));
+    }
+}
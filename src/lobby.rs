@@ -0,0 +1,625 @@
+use std::{
+    f32::consts::TAU,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+use bevy::{ecs::system::SystemParam, prelude::*, time::common_conditions::on_real_timer};
+use bevy_renet::{
+    netcode::{NetcodeClientTransport, NetcodeServerTransport},
+    renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    factions::{Factions, DEFAULT_BUDGET},
+    launch::{LaunchOptions, NetworkRole},
+    localization::Localization,
+    network::{client_transport, decode, encode, is_spectator_client},
+    rng::GameRng,
+    scenario::ScenarioEntity,
+    ships::ShipDefinitions,
+    spaceship_bundle, Faction, GameState, ShipClass,
+};
+
+/// UDP port on which hosts advertise their sessions on the LAN.
+const DISCOVERY_PORT: u16 = 47_001;
+const ADVERTISE_PERIOD: Duration = Duration::from_secs(1);
+/// Sessions not advertised for this long are removed from the browser.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(5);
+/// Roster id of the host, clients use their netcode id.
+pub const HOST_ID: u64 = 0;
+const BUDGET_STEP: u32 = 10;
+/// Cost of the ships whose definition isn't loaded yet.
+const FALLBACK_SHIP_COST: u32 = 10;
+
+/// Pre-game [`GameState::Lobby`] of networked games: the host advertises the
+/// session on the LAN, players pick their faction and fleet budget, and the
+/// host launches everyone into the same seeded scenario.
+///
+/// Players started with `--browse` list the advertised sessions and join one.
+/// Spectators can also join once the game is launched.
+pub struct LobbyPlugin;
+
+impl Plugin for LobbyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Roster>()
+            .init_resource::<LocalChoice>()
+            .add_systems(OnEnter(GameState::Lobby), spawn_lobby_text)
+            .add_systems(OnExit(GameState::Lobby), despawn_lobby_text)
+            .add_systems(
+                Update,
+                (
+                    (
+                        advertise_session.run_if(on_real_timer(ADVERTISE_PERIOD)),
+                        update_roster,
+                        host_launch,
+                    )
+                        .run_if(resource_exists::<RenetServer>),
+                    (discover_sessions, join_session)
+                        .chain()
+                        .run_if(resource_exists::<SessionBrowser>),
+                    client_lobby.run_if(resource_exists::<RenetClient>),
+                    change_choice,
+                    update_lobby_text,
+                )
+                    .run_if(in_state(GameState::Lobby)),
+            )
+            .add_systems(
+                Update,
+                welcome_late_clients
+                    .run_if(resource_exists::<RenetServer>.and(not(in_state(GameState::Lobby)))),
+            )
+            .add_systems(
+                OnEnter(GameState::InGame),
+                spawn_budget_fleets.run_if(crate::launch::is_authoritative),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum LobbyMessage {
+    /// From a client: its faction and budget.
+    Choice(LocalChoice),
+    /// From the host: every player of the session.
+    Roster(Vec<LobbyPlayer>),
+    /// From the host: everyone starts the game.
+    Launch { seed: u64, scenario: Option<String> },
+}
+
+/// Advertised by hosts on the [`DISCOVERY_PORT`].
+#[derive(Serialize, Deserialize)]
+struct Advertisement {
+    name: String,
+    port: u16,
+    players: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LobbyPlayer {
+    pub id: u64,
+    pub faction: Faction,
+    /// Cost of the initial fleet of the player.
+    pub budget: u32,
+}
+
+/// Players of the networked game, in the same order for everyone.
+#[derive(Resource)]
+pub struct Roster {
+    pub players: Vec<LobbyPlayer>,
+}
+
+impl Default for Roster {
+    fn default() -> Self {
+        Self {
+            players: vec![LobbyPlayer {
+                id: HOST_ID,
+                faction: Faction(1),
+                budget: DEFAULT_BUDGET,
+            }],
+        }
+    }
+}
+
+impl Roster {
+    pub fn faction_of(&self, id: u64) -> Option<Faction> {
+        self.players
+            .iter()
+            .find(|player| player.id == id)
+            .map(|player| player.faction)
+    }
+
+    /// Faction of the first client.
+    pub fn guest_faction(&self) -> Faction {
+        self.players
+            .iter()
+            .find(|player| player.id != HOST_ID)
+            .map_or(Faction(2), |player| player.faction)
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq)]
+struct LocalChoice {
+    faction: Faction,
+    budget: u32,
+}
+
+impl Default for LocalChoice {
+    fn default() -> Self {
+        Self {
+            faction: Faction(1),
+            budget: DEFAULT_BUDGET,
+        }
+    }
+}
+
+/// Sessions advertised on the LAN, for players started with `--browse`.
+#[derive(Resource)]
+pub struct SessionBrowser {
+    socket: UdpSocket,
+    sessions: Vec<Session>,
+}
+
+struct Session {
+    name: String,
+    address: SocketAddr,
+    players: u32,
+    last_seen: Duration,
+}
+
+impl SessionBrowser {
+    pub fn new() -> std::io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            sessions: vec![],
+        })
+    }
+}
+
+/// Broadcasts the advertisements of the host.
+#[derive(Resource)]
+pub struct Advertiser {
+    socket: UdpSocket,
+    port: u16,
+}
+
+impl Advertiser {
+    pub fn new(port: u16) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))?;
+        socket.set_broadcast(true)?;
+        Ok(Self { socket, port })
+    }
+}
+
+#[derive(Component)]
+struct LobbyText;
+
+fn spawn_lobby_text(mut commands: Commands) {
+    commands.spawn((
+        LobbyText,
+        Text::default(),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            left: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
+
+fn despawn_lobby_text(mut commands: Commands, texts: Query<Entity, With<LobbyText>>) {
+    for text in texts.iter() {
+        commands.entity(text).despawn_recursive();
+    }
+}
+
+fn advertise_session(advertiser: Option<Res<Advertiser>>, roster: Res<Roster>) {
+    let Some(advertiser) = advertiser else {
+        return;
+    };
+    let name = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "host".to_string());
+    let advertisement = Advertisement {
+        name,
+        port: advertiser.port,
+        players: roster.players.len() as u32,
+    };
+    let target = (Ipv4Addr::BROADCAST, DISCOVERY_PORT);
+    if let Err(err) = advertiser.socket.send_to(&encode(&advertisement), target) {
+        warn!("Failed to advertise the session: {err}");
+    }
+}
+
+/// Keeps the roster in sync with the connected clients and their choices.
+/// Spectators aren't in the roster, but receive it.
+fn update_roster(
+    mut server: ResMut<RenetServer>,
+    transport: Res<NetcodeServerTransport>,
+    mut events: EventReader<ServerEvent>,
+    mut roster: ResMut<Roster>,
+    choice: Res<LocalChoice>,
+    options: Res<LaunchOptions>,
+    factions: Res<Factions>,
+) {
+    let mut changed = choice.is_changed();
+    if let Some(host) = roster
+        .players
+        .iter_mut()
+        .find(|player| player.id == HOST_ID)
+    {
+        host.faction = choice.faction;
+        host.budget = choice.budget;
+    }
+
+    for event in events.read() {
+        match event {
+            ServerEvent::ClientConnected { client_id } => {
+                if is_spectator_client(&transport, *client_id) {
+                    if options.is_lockstep() {
+                        info!("Refusing spectator {client_id}: lockstep games can't be spectated");
+                        server.disconnect(*client_id);
+                    } else {
+                        info!("Spectator {client_id} joined");
+                        changed = true;
+                    }
+                    continue;
+                }
+                if roster.players.iter().any(|player| player.id != HOST_ID) {
+                    info!("Refusing client {client_id}: the session is full");
+                    server.disconnect(*client_id);
+                    continue;
+                }
+                let faction = (1..)
+                    .map(Faction)
+                    .find(|faction| {
+                        roster
+                            .players
+                            .iter()
+                            .all(|player| player.faction != *faction)
+                    })
+                    .expect("there are always free factions");
+                roster.players.push(LobbyPlayer {
+                    id: *client_id,
+                    faction,
+                    budget: factions.budget(faction),
+                });
+            }
+            ServerEvent::ClientDisconnected { client_id, .. } => {
+                roster.players.retain(|player| player.id != *client_id);
+            }
+        }
+        changed = true;
+    }
+
+    for client_id in server.clients_id() {
+        while let Some(bytes) = server.receive_message(client_id, DefaultChannel::ReliableOrdered) {
+            if let Some(LobbyMessage::Choice(choice)) = decode(&bytes) {
+                if let Some(player) = roster
+                    .players
+                    .iter_mut()
+                    .find(|player| player.id == client_id)
+                {
+                    player.faction = choice.faction;
+                    player.budget = choice.budget;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    if changed {
+        server.broadcast_message(
+            DefaultChannel::ReliableOrdered,
+            encode(&LobbyMessage::Roster(roster.players.clone())),
+        );
+    }
+}
+
+fn host_launch(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut server: ResMut<RenetServer>,
+    options: Res<LaunchOptions>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Enter) {
+        return;
+    }
+    // Same random sequence as the clients from now on
+    *rng = GameRng::new(options.seed);
+    server.broadcast_message(
+        DefaultChannel::ReliableOrdered,
+        encode(&launch_message(&options)),
+    );
+    info!("Launching the game");
+    next_state.set(GameState::Loading);
+}
+
+fn launch_message(options: &LaunchOptions) -> LobbyMessage {
+    LobbyMessage::Launch {
+        seed: options.seed,
+        scenario: options
+            .scenario
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned()),
+    }
+}
+
+/// Launches the spectators joining a game in progress, and refuses the players.
+fn welcome_late_clients(
+    mut server: ResMut<RenetServer>,
+    transport: Res<NetcodeServerTransport>,
+    mut events: EventReader<ServerEvent>,
+    roster: Res<Roster>,
+    options: Res<LaunchOptions>,
+) {
+    for event in events.read() {
+        let ServerEvent::ClientConnected { client_id } = event else {
+            continue;
+        };
+        if !is_spectator_client(&transport, *client_id) || options.is_lockstep() {
+            info!("Refusing client {client_id}: the game is in progress");
+            server.disconnect(*client_id);
+            continue;
+        }
+        info!("Spectator {client_id} joined");
+        for message in [
+            LobbyMessage::Roster(roster.players.clone()),
+            launch_message(&options),
+        ] {
+            server.send_message(
+                *client_id,
+                DefaultChannel::ReliableOrdered,
+                encode(&message),
+            );
+        }
+    }
+}
+
+fn client_lobby(
+    mut client: ResMut<RenetClient>,
+    transport: Res<NetcodeClientTransport>,
+    mut choice: ResMut<LocalChoice>,
+    mut roster: ResMut<Roster>,
+    mut options: ResMut<LaunchOptions>,
+    mut rng: ResMut<GameRng>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !client.is_connected() {
+        return;
+    }
+    if choice.is_changed() && !options.spectator {
+        client.send_message(
+            DefaultChannel::ReliableOrdered,
+            encode(&LobbyMessage::Choice(*choice)),
+        );
+    }
+
+    while let Some(bytes) = client.receive_message(DefaultChannel::ReliableOrdered) {
+        match decode(&bytes) {
+            Some(LobbyMessage::Roster(players)) => {
+                // Picks the faction assigned by the host, without sending it back
+                if let Some(player) = players
+                    .iter()
+                    .find(|player| player.id == transport.client_id())
+                {
+                    *choice.bypass_change_detection() = LocalChoice {
+                        faction: player.faction,
+                        budget: player.budget,
+                    };
+                }
+                roster.players = players;
+            }
+            Some(LobbyMessage::Launch { seed, scenario }) => {
+                info!("The host launched the game");
+                *rng = GameRng::new(seed);
+                options.seed = seed;
+                options.scenario = scenario.map(Into::into);
+                next_state.set(GameState::Loading);
+                // The next messages are for the game
+                return;
+            }
+            Some(LobbyMessage::Choice(_)) | None => {}
+        }
+    }
+}
+
+fn discover_sessions(mut browser: ResMut<SessionBrowser>, time: Res<Time<Real>>) {
+    let now = time.elapsed();
+    let mut buffer = [0; 512];
+    while let Ok((len, source)) = browser.socket.recv_from(&mut buffer) {
+        let Some(advertisement) = decode::<Advertisement>(&buffer[..len]) else {
+            continue;
+        };
+        let address = SocketAddr::new(source.ip(), advertisement.port);
+        let session = Session {
+            name: advertisement.name,
+            address,
+            players: advertisement.players,
+            last_seen: now,
+        };
+        match browser
+            .sessions
+            .iter_mut()
+            .find(|session| session.address == address)
+        {
+            Some(known) => *known = session,
+            None => browser.sessions.push(session),
+        }
+    }
+    browser
+        .sessions
+        .retain(|session| now - session.last_seen < SESSION_TIMEOUT);
+}
+
+/// Joins the session of the pressed digit.
+fn join_session(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    browser: Res<SessionBrowser>,
+    mut options: ResMut<LaunchOptions>,
+) {
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let Some(session) = DIGITS
+        .iter()
+        .position(|digit| keyboard.just_pressed(*digit))
+        .and_then(|index| browser.sessions.get(index))
+    else {
+        return;
+    };
+
+    match client_transport(session.address, options.spectator) {
+        Ok(transport) => {
+            info!("Joining {} at {}", session.name, session.address);
+            options.network = Some(NetworkRole::Client(session.address));
+            commands.insert_resource(RenetClient::new(ConnectionConfig::default()));
+            commands.insert_resource(transport);
+            commands.remove_resource::<SessionBrowser>();
+        }
+        Err(err) => error!("Failed to join {}: {err}", session.address),
+    }
+}
+
+/// Picking a faction resets the budget to its default one.
+fn change_choice(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut choice: ResMut<LocalChoice>,
+    factions: Res<Factions>,
+) {
+    let faction = choice.faction;
+    if keyboard.just_pressed(KeyCode::ArrowRight) {
+        choice.faction.0 += 1;
+    }
+    if keyboard.just_pressed(KeyCode::ArrowLeft) && choice.faction.0 > 1 {
+        choice.faction.0 -= 1;
+    }
+    if choice.faction != faction || factions.is_changed() {
+        choice.budget = factions.budget(choice.faction);
+    }
+    if keyboard.just_pressed(KeyCode::ArrowUp) {
+        choice.budget += BUDGET_STEP;
+    }
+    if keyboard.just_pressed(KeyCode::ArrowDown) {
+        choice.budget = choice.budget.saturating_sub(BUDGET_STEP);
+    }
+}
+
+/// Players gathered in the lobby, or the sessions to join.
+#[derive(SystemParam)]
+struct LobbySession<'w> {
+    roster: Res<'w, Roster>,
+    choice: Res<'w, LocalChoice>,
+    browser: Option<Res<'w, SessionBrowser>>,
+    server: Option<Res<'w, RenetServer>>,
+}
+
+fn update_lobby_text(
+    mut texts: Query<&mut Text, With<LobbyText>>,
+    session: LobbySession,
+    options: Res<LaunchOptions>,
+    factions: Res<Factions>,
+    localization: Res<Localization>,
+) {
+    let LobbySession {
+        roster,
+        choice,
+        browser,
+        server,
+    } = session;
+    let Ok(mut text) = texts.get_single_mut() else {
+        return;
+    };
+
+    text.0 = if let Some(browser) = browser {
+        let mut lines = vec![localization.text("lobby-sessions")];
+        lines.extend(browser.sessions.iter().enumerate().map(|(index, session)| {
+            localization.format(
+                "lobby-session",
+                &[
+                    ("number", (index + 1).into()),
+                    ("name", session.name.as_str().into()),
+                    ("players", session.players.into()),
+                ],
+            )
+        }));
+        lines.join("\n")
+    } else {
+        let mut lines = vec![if options.spectator {
+            localization.text("lobby-spectating")
+        } else {
+            localization.format(
+                "lobby-choice",
+                &[
+                    ("faction", factions.label(choice.faction).into()),
+                    ("budget", choice.budget.into()),
+                ],
+            )
+        }];
+        lines.extend(roster.players.iter().map(|player| {
+            let name = if player.id == HOST_ID {
+                localization.text("lobby-host")
+            } else {
+                localization.format("lobby-player", &[("id", player.id.to_string().into())])
+            };
+            localization.format(
+                "lobby-roster-entry",
+                &[
+                    ("name", name.into()),
+                    ("faction", factions.label(player.faction).into()),
+                    ("budget", player.budget.into()),
+                ],
+            )
+        }));
+        lines.push(if server.is_some() {
+            localization.text("lobby-launch")
+        } else {
+            localization.text("lobby-waiting")
+        });
+        lines.join("\n")
+    };
+}
+
+/// Buys the initial fleet of each player with its budget, at the home of its
+/// faction or on a circle around the center.
+fn spawn_budget_fleets(
+    mut commands: Commands,
+    roster: Res<Roster>,
+    factions: Res<Factions>,
+    definitions: ShipDefinitions,
+    mut rng: ResMut<GameRng>,
+) {
+    let class = ShipClass::default();
+    let cost = definitions
+        .get(&class)
+        .map_or(FALLBACK_SHIP_COST, |definition| definition.cost)
+        .max(1);
+
+    for (index, player) in roster.players.iter().enumerate() {
+        let fleet_center = factions.home(player.faction).unwrap_or_else(|| {
+            let angle = index as f32 / roster.players.len() as f32 * TAU;
+            Vec2::from_angle(angle) * 600.
+        });
+        for _ in 0..player.budget / cost {
+            let offset = Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0));
+            let position = fleet_center + offset;
+            commands.spawn((
+                ScenarioEntity,
+                spaceship_bundle(player.faction, class.clone(), position.x, position.y),
+            ));
+        }
+    }
+}
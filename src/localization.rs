@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    prelude::*,
+};
+use fluent_bundle::{concurrent::FluentBundle, FluentArgs, FluentResource, FluentValue};
+use unic_langid::LanguageIdentifier;
+
+use crate::settings::UserSettings;
+
+/// Languages of the `assets/locales/<language>.ftl` files, the first one is the
+/// default and the fallback of the missing messages.
+pub const LANGUAGES: &[&str] = &["en-US", "fr-FR"];
+
+/// User-facing text translated from Fluent files, switched at runtime with
+/// `F6` by default and saved in the [`UserSettings`].
+///
+/// Texts that are shown once use [`LocalizedText`] to follow the language,
+/// the others are formatted again by the systems updating them.
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Translations>()
+            .register_asset_loader(FtlLoader)
+            .init_resource::<Localization>()
+            .add_systems(Startup, load_translations)
+            .add_systems(
+                Update,
+                (
+                    switch_language,
+                    build_bundles.run_if(
+                        on_event::<AssetEvent<Translations>>.or(resource_changed::<UserSettings>),
+                    ),
+                    update_localized_texts,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Parsed `.ftl` file.
+#[derive(Asset, TypePath)]
+pub struct Translations(Arc<FluentResource>);
+
+/// Handles of the [`LANGUAGES`], in the same order.
+#[derive(Resource)]
+struct TranslationHandles(Vec<Handle<Translations>>);
+
+#[derive(Resource, Default)]
+pub struct Localization {
+    /// Bundle of the current language, then of the default one.
+    bundles: Vec<FluentBundle<Arc<FluentResource>>>,
+}
+
+impl Localization {
+    pub fn text(&self, id: &str) -> String {
+        self.format(id, &[])
+    }
+
+    /// Message `id` with its variables, or the id itself when no language has it.
+    pub fn format(&self, id: &str, args: &[(&str, FluentValue)]) -> String {
+        let mut fluent_args = FluentArgs::new();
+        for (name, value) in args {
+            fluent_args.set(*name, value.clone());
+        }
+        for bundle in &self.bundles {
+            let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+                continue;
+            };
+            let mut errors = vec![];
+            let text = bundle.format_pattern(pattern, Some(&fluent_args), &mut errors);
+            for err in errors {
+                warn!("Failed to format message {id}: {err}");
+            }
+            return text.into_owned();
+        }
+        id.to_string()
+    }
+}
+
+/// Text entity formatted again when the language changes.
+#[derive(Component)]
+#[require(Text)]
+pub struct LocalizedText {
+    pub id: &'static str,
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl LocalizedText {
+    pub fn new(id: &'static str) -> Self {
+        Self { id, args: vec![] }
+    }
+
+    pub fn with_arg(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.args.push((name, value.into()));
+        self
+    }
+}
+
+#[derive(Default)]
+struct FtlLoader;
+
+impl AssetLoader for FtlLoader {
+    type Asset = Translations;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Translations, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let source = String::from_utf8(bytes)?;
+        let resource = FluentResource::try_new(source).map_err(|(_, errors)| {
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        })?;
+        Ok(Translations(Arc::new(resource)))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ftl"]
+    }
+}
+
+fn load_translations(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = LANGUAGES
+        .iter()
+        .map(|language| asset_server.load(format!("locales/{language}.ftl")))
+        .collect();
+    commands.insert_resource(TranslationHandles(handles));
+}
+
+/// Cycles through the [`LANGUAGES`].
+fn switch_language(keyboard: Res<ButtonInput<KeyCode>>, mut settings: ResMut<UserSettings>) {
+    if !keyboard.just_pressed(settings.input.switch_language) {
+        return;
+    }
+    let index = LANGUAGES
+        .iter()
+        .position(|language| Some(*language) == settings.language.as_deref())
+        .map_or(1, |index| index + 1)
+        % LANGUAGES.len();
+    info!("Language: {}", LANGUAGES[index]);
+    settings.language = Some(LANGUAGES[index].to_string());
+}
+
+fn build_bundles(
+    mut localization: ResMut<Localization>,
+    settings: Res<UserSettings>,
+    handles: Res<TranslationHandles>,
+    translations: Res<Assets<Translations>>,
+) {
+    let current = LANGUAGES
+        .iter()
+        .position(|language| Some(*language) == settings.language.as_deref())
+        .unwrap_or(0);
+    let mut indices = vec![current];
+    if current != 0 {
+        indices.push(0);
+    }
+    let mut bundles = vec![];
+    for index in indices {
+        let Some(translations) = translations.get(&handles.0[index]) else {
+            continue;
+        };
+        let language: LanguageIdentifier = LANGUAGES[index]
+            .parse()
+            .expect("the languages are valid identifiers");
+        let mut bundle = FluentBundle::new_concurrent(vec![language]);
+        // Bevy text has no use of the bidirectional isolation marks
+        bundle.set_use_isolating(false);
+        if let Err(errors) = bundle.add_resource(translations.0.clone()) {
+            warn!("Duplicated messages in {}: {errors:?}", LANGUAGES[index]);
+        }
+        bundles.push(bundle);
+    }
+    localization.bundles = bundles;
+}
+
+fn update_localized_texts(
+    localization: Res<Localization>,
+    mut texts: Query<(Ref<LocalizedText>, &mut Text)>,
+) {
+    for (localized, mut text) in texts.iter_mut() {
+        if !localization.is_changed() && !localized.is_changed() {
+            continue;
+        }
+        let args: Vec<(&str, FluentValue)> = localized
+            .args
+            .iter()
+            .map(|(name, value)| (*name, FluentValue::from(value.as_str())))
+            .collect();
+        text.0 = localization.format(localized.id, &args);
+    }
+}
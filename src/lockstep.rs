@@ -0,0 +1,307 @@
+use std::{
+    collections::BTreeMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    time::Duration,
+};
+
+use bevy::{app::RunFixedMainLoopSystem, ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
+use bevy_renet::renet::{DefaultChannel, RenetClient, RenetServer};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    arrows_direction,
+    brain::BrainScheduler,
+    lobby::{Roster, HOST_ID},
+    move_fleet,
+    network::{decode, encode},
+    spaceship_bundle, Faction, FleetTransforms, GameState, SimulationSet, Spaceship, SpawnRequest,
+};
+
+/// Ticks between an input and its application, hiding the network latency.
+const INPUT_DELAY: u32 = 6;
+/// Ticks between two comparisons of the peer states.
+const HASH_PERIOD: u32 = 60;
+
+/// Deterministic lockstep between the host and one client: only the inputs
+/// of each fixed tick are exchanged, and both peers run the same simulation.
+///
+/// The simulation of a fixed tick, physics included, is skipped until the
+/// inputs of both peers are known. States are hashed every [`HASH_PERIOD`]
+/// ticks to detect desyncs.
+pub struct LockstepPlugin;
+
+impl Plugin for LockstepPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Lockstep>()
+            .add_systems(Startup, disable_time_budget)
+            .add_systems(
+                Update,
+                (collect_local_inputs, receive_peer_messages)
+                    .run_if(not(in_state(GameState::Lobby))),
+            )
+            .add_systems(
+                RunFixedMainLoop,
+                limit_fixed_ticks.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            )
+            .configure_sets(FixedUpdate, SimulationSet.run_if(is_simulating))
+            .configure_sets(
+                FixedPostUpdate,
+                (
+                    PhysicsSet::SyncBackend,
+                    PhysicsSet::StepSimulation,
+                    PhysicsSet::Writeback,
+                )
+                    .run_if(is_simulating),
+            )
+            .add_systems(FixedFirst, begin_tick)
+            .add_systems(FixedPreUpdate, apply_tick_inputs.run_if(is_simulating))
+            .add_systems(
+                FixedPostUpdate,
+                hash_state.after(PhysicsSet::Writeback).run_if(
+                    is_simulating.and(|lockstep: Res<Lockstep>| lockstep.tick % HASH_PERIOD == 0),
+                ),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum LockstepMessage {
+    Inputs { tick: u32, inputs: TickInputs },
+    Hash { tick: u32, hash: u64 },
+}
+
+/// Everything a player did during a tick.
+#[derive(Serialize, Deserialize, Clone, Default)]
+struct TickInputs {
+    spawns: Vec<SpawnRequest>,
+    direction: Vec2,
+}
+
+#[derive(Resource)]
+struct Lockstep {
+    /// Next tick to simulate.
+    tick: u32,
+    /// Whether the current fixed tick is simulated.
+    simulating: bool,
+    /// Inputs of the host and of the client, by tick.
+    inputs: BTreeMap<u32, [Option<TickInputs>; 2]>,
+    /// Local inputs since the last tick.
+    pending: TickInputs,
+    /// First received hash of each tick, waiting for the other peer.
+    hashes: BTreeMap<u32, u64>,
+}
+
+impl Default for Lockstep {
+    fn default() -> Self {
+        // Nobody can act during the first ticks
+        let inputs = (0..INPUT_DELAY)
+            .map(|tick| (tick, [Some(default()), Some(default())]))
+            .collect();
+        Self {
+            tick: 0,
+            simulating: false,
+            inputs,
+            pending: default(),
+            hashes: default(),
+        }
+    }
+}
+
+impl Lockstep {
+    fn is_ready(&self, tick: u32) -> bool {
+        self.inputs
+            .get(&tick)
+            .is_some_and(|inputs| inputs.iter().all(Option::is_some))
+    }
+
+    /// Number of consecutive ticks whose inputs are all known.
+    fn ready_ticks(&self) -> u32 {
+        (self.tick..)
+            .take_while(|tick| self.is_ready(*tick))
+            .count() as u32
+    }
+
+    /// Compares the hashes of both peers once known.
+    fn check_hash(&mut self, tick: u32, hash: u64) {
+        match self.hashes.remove(&tick) {
+            Some(other_hash) if other_hash != hash => error!("Desync detected at tick {tick}"),
+            Some(_) => {}
+            None => {
+                self.hashes.insert(tick, hash);
+            }
+        }
+    }
+}
+
+/// The other peer, either the client of the host or the host of the client.
+#[derive(SystemParam)]
+struct Peer<'w> {
+    server: Option<ResMut<'w, RenetServer>>,
+    client: Option<ResMut<'w, RenetClient>>,
+}
+
+impl Peer<'_> {
+    /// Index of the local inputs in [`Lockstep::inputs`].
+    fn local_index(&self) -> usize {
+        if self.server.is_some() {
+            0
+        } else {
+            1
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        match (&self.server, &self.client) {
+            (Some(server), _) => server.connected_clients() > 0,
+            (_, Some(client)) => client.is_connected(),
+            _ => false,
+        }
+    }
+
+    fn send(&mut self, message: &LockstepMessage) {
+        let bytes = encode(message);
+        if let Some(server) = &mut self.server {
+            server.broadcast_message(DefaultChannel::ReliableOrdered, bytes);
+        } else if let Some(client) = &mut self.client {
+            client.send_message(DefaultChannel::ReliableOrdered, bytes);
+        }
+    }
+
+    fn receive(&mut self) -> Vec<LockstepMessage> {
+        let mut messages = vec![];
+        if let Some(server) = &mut self.server {
+            for client_id in server.clients_id() {
+                while let Some(bytes) =
+                    server.receive_message(client_id, DefaultChannel::ReliableOrdered)
+                {
+                    messages.extend(decode(&bytes));
+                }
+            }
+        } else if let Some(client) = &mut self.client {
+            while let Some(bytes) = client.receive_message(DefaultChannel::ReliableOrdered) {
+                messages.extend(decode(&bytes));
+            }
+        }
+        messages
+    }
+}
+
+/// Decisions must only depend on the simulation, not on the machine speed.
+fn disable_time_budget(mut scheduler: ResMut<BrainScheduler>) {
+    scheduler.time_budget = Duration::MAX;
+}
+
+fn collect_local_inputs(
+    mut lockstep: ResMut<Lockstep>,
+    mut requests: EventReader<SpawnRequest>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    lockstep.pending.spawns.extend(requests.read().cloned());
+    lockstep.pending.direction = arrows_direction(&keyboard);
+}
+
+fn receive_peer_messages(mut lockstep: ResMut<Lockstep>, mut peer: Peer) {
+    let remote_index = 1 - peer.local_index();
+    for message in peer.receive() {
+        match message {
+            LockstepMessage::Inputs { tick, inputs } => {
+                lockstep.inputs.entry(tick).or_default()[remote_index] = Some(inputs);
+            }
+            LockstepMessage::Hash { tick, hash } => lockstep.check_hash(tick, hash),
+        }
+    }
+}
+
+/// Drops the accumulated time of the fixed ticks that can't run yet, so the
+/// fixed schedule mostly runs simulated ticks.
+fn limit_fixed_ticks(
+    lockstep: Res<Lockstep>,
+    peer: Peer,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    virtual_time: Res<Time<Virtual>>,
+) {
+    let ready_ticks = if peer.is_connected() {
+        lockstep.ready_ticks()
+    } else {
+        0
+    };
+    let allowed = fixed_time.timestep() * ready_ticks;
+    let accumulated = fixed_time.overstep() + virtual_time.delta();
+    fixed_time.discard_overstep(accumulated.saturating_sub(allowed));
+}
+
+fn begin_tick(mut lockstep: ResMut<Lockstep>, peer: Peer, state: Res<State<GameState>>) {
+    lockstep.simulating = *state.get() == GameState::InGame
+        && peer.is_connected()
+        && lockstep.is_ready(lockstep.tick);
+}
+
+fn is_simulating(lockstep: Res<Lockstep>) -> bool {
+    lockstep.simulating
+}
+
+fn apply_tick_inputs(
+    mut commands: Commands,
+    mut lockstep: ResMut<Lockstep>,
+    mut peer: Peer,
+    mut spaceships: FleetTransforms,
+    roster: Res<Roster>,
+    time: Res<Time>,
+) {
+    let local_index = peer.local_index();
+    let input_tick = lockstep.tick + INPUT_DELAY;
+    let inputs = std::mem::take(&mut lockstep.pending);
+    peer.send(&LockstepMessage::Inputs {
+        tick: input_tick,
+        inputs: inputs.clone(),
+    });
+    lockstep.inputs.entry(input_tick).or_default()[local_index] = Some(inputs);
+
+    let tick = lockstep.tick;
+    // Checked by `begin_tick`
+    let Some([Some(host), Some(client)]) = lockstep.inputs.remove(&tick) else {
+        return;
+    };
+    lockstep.tick += 1;
+
+    // Same order on both peers
+    let host_faction = roster.faction_of(HOST_ID).unwrap_or(Faction(1));
+    let client_faction = roster.guest_faction();
+    for (inputs, fleet, is_client) in [(host, host_faction, false), (client, client_faction, true)]
+    {
+        for request in inputs.spawns {
+            // The client only spawns ships of its own faction
+            let faction = if is_client { fleet } else { request.faction };
+            commands.spawn(spaceship_bundle(
+                faction,
+                request.class,
+                request.position.x,
+                request.position.y,
+            ));
+        }
+        move_fleet(&mut spaceships, fleet, inputs.direction, &time);
+    }
+}
+
+fn hash_state(
+    mut lockstep: ResMut<Lockstep>,
+    mut peer: Peer,
+    spaceships: Query<(&Faction, &Transform), With<Spaceship>>,
+) {
+    // The tick was incremented by `apply_tick_inputs`
+    let Some(tick) = lockstep.tick.checked_sub(1) else {
+        return;
+    };
+    let mut hasher = DefaultHasher::new();
+    for (faction, transform) in spaceships.iter() {
+        faction.hash(&mut hasher);
+        for value in transform.translation.to_array() {
+            value.to_bits().hash(&mut hasher);
+        }
+    }
+    let hash = hasher.finish();
+
+    lockstep.check_hash(tick, hash);
+    peer.send(&LockstepMessage::Hash { tick, hash });
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    closest_target, emp::Disabled, group_targets_by_faction, nebula::Concealed,
+    targeting::Candidate, Faction, Spaceship, Target,
+};
+
+/// Distance from the edge of a camera view under which ships are fully simulated.
+const FULL_SIMULATION_MARGIN: f32 = 500.;
+const COARSE_TARGETING_PERIOD: Duration = Duration::from_secs(1);
+
+/// Level of detail of the simulation: only ships near a camera get forces and
+/// collisions, the others are [`Coarse`].
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                update_simulation_lod.before(crate::update_targets),
+                update_coarse_targets
+                    .after(update_simulation_lod)
+                    .run_if(on_timer(COARSE_TARGETING_PERIOD)),
+            )
+                // Cameras differ between lockstep peers, so they all fully simulate,
+                // and headless runs must resolve like the windowed ones
+                .run_if(
+                    any_with_component::<Spaceship>
+                        .and(crate::launch::is_authoritative)
+                        .and(not(crate::launch::is_lockstep))
+                        .and(not(crate::launch::is_headless)),
+                ),
+        );
+    }
+}
+
+/// Ship far from every camera, moving in a straight line as a kinematic body
+/// and retargeted every [`COARSE_TARGETING_PERIOD`].
+#[derive(Component)]
+pub struct Coarse;
+
+fn update_simulation_lod(
+    mut commands: Commands,
+    cameras: Query<(&GlobalTransform, &OrthographicProjection), With<Camera>>,
+    mut spaceships: Query<
+        (
+            Entity,
+            &Transform,
+            &mut RigidBody,
+            &mut Velocity,
+            Has<Coarse>,
+        ),
+        With<Spaceship>,
+    >,
+) {
+    profile_scope!("update_simulation_lod");
+    let full_simulation_areas: Vec<Rect> = cameras
+        .iter()
+        .map(|(camera_transform, projection)| {
+            let center = camera_transform.translation().truncate() + projection.area.center();
+            Rect::from_center_size(center, projection.area.size()).inflate(FULL_SIMULATION_MARGIN)
+        })
+        .collect();
+
+    for (entity, transform, mut rigid_body, mut velocity, coarse) in spaceships.iter_mut() {
+        let position = transform.translation.truncate();
+        let near_camera = full_simulation_areas
+            .iter()
+            .any(|area| area.contains(position));
+
+        if near_camera && coarse {
+            *rigid_body = RigidBody::Dynamic;
+            commands
+                .entity(entity)
+                .remove::<(Coarse, ColliderDisabled)>()
+                .insert(TransformInterpolation::default());
+        } else if !near_camera && !coarse {
+            *rigid_body = RigidBody::KinematicVelocityBased;
+            velocity.angvel = 0.;
+            commands.entity(entity).insert((Coarse, ColliderDisabled));
+        }
+    }
+}
+
+/// Point coarse ships towards their closest target, keeping their speed.
+fn update_coarse_targets(
+    mut spaceships: Query<
+        (
+            Entity,
+            &Faction,
+            &mut Transform,
+            &mut Target,
+            &mut Velocity,
+            Option<&Concealed>,
+            Has<Coarse>,
+            Has<Disabled>,
+        ),
+        With<Spaceship>,
+    >,
+) {
+    profile_scope!("update_coarse_targets");
+    let targets_by_faction = group_targets_by_faction(spaceships.iter().map(
+        |(entity, faction, transform, _, _, concealed, ..)| {
+            (*faction, Candidate::new(entity, transform, concealed))
+        },
+    ));
+
+    for (_, faction, mut transform, mut target, mut velocity, _, coarse, disabled) in
+        spaceships.iter_mut()
+    {
+        if !coarse || disabled {
+            continue;
+        }
+        let Some(closest_target) =
+            closest_target(&targets_by_faction, *faction, transform.translation)
+        else {
+            continue;
+        };
+
+        let direction = (closest_target.translation - transform.translation)
+            .truncate()
+            .normalize_or_zero();
+        if direction != Vec2::ZERO {
+            velocity.linvel = direction * velocity.linvel.length();
+            transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, direction);
+        }
+        *target = closest_target;
+    }
+}
@@ -1,189 +1,978 @@
+use std::{f32::consts::TAU, time::Duration};
+
 use bevy::{
-    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    app::ScheduleRunnerPlugin,
+    ecs::system::SystemParam,
     prelude::*,
-    sprite::MaterialMesh2dBundle,
-    utils::{HashMap, HashSet},
-    window::{close_on_esc, PresentMode},
+    render::{settings::WgpuSettings, RenderPlugin},
+    window::{ExitCondition, PresentMode, PrimaryWindow, WindowResolution},
+    winit::WinitPlugin,
 };
-use bevy_inspector_egui::prelude::*;
-use bevy_inspector_egui::quick::ResourceInspectorPlugin;
 use bevy_rapier2d::prelude::*;
-use itertools::Itertools;
 
-use bevy_prototype_debug_lines::*;
-use rand::rngs::SmallRng;
 use rand::Rng;
-use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+#[macro_use]
+mod profiling;
+mod accessibility;
+#[cfg(not(target_arch = "wasm32"))]
+mod achievements;
+mod ammo;
+#[cfg(not(target_arch = "wasm32"))]
+mod api;
+mod asteroids;
+#[cfg(not(target_arch = "wasm32"))]
+mod autosave;
+mod avoidance;
+mod behavior;
+mod black_hole;
+mod boarding;
+mod bounds;
+mod brain;
+#[cfg(not(target_arch = "wasm32"))]
+mod chat;
+mod comets;
+mod commander;
+mod config;
+mod damage;
+mod debris;
+mod debug_draw;
+#[cfg(feature = "dev-tools")]
+mod dev_tools;
+mod difficulty;
+mod docking;
+mod dust;
+mod emp;
+mod evasion;
+mod factions;
+mod flak;
+mod flocking;
+mod formation;
+mod game_assets;
+mod gravity;
+mod hazards;
+mod influence;
+mod launch;
+#[cfg(not(target_arch = "wasm32"))]
+mod leaderboard;
+#[cfg(feature = "lighting")]
+mod lighting;
+#[cfg(not(target_arch = "wasm32"))]
+mod lobby;
+mod localization;
+#[cfg(not(target_arch = "wasm32"))]
+mod lockstep;
+mod lod;
+mod mines;
+mod missile;
+mod mixer;
+mod mods;
+mod nebula;
+#[cfg(not(target_arch = "wasm32"))]
+mod network;
+mod orbits;
+mod personality;
+mod point_defense;
+mod pool;
+mod ramming;
+mod rng;
+mod ron_asset;
+#[cfg(not(target_arch = "wasm32"))]
+mod saves;
+mod scenario;
+mod scripting;
+mod settings;
+mod share_code;
+mod ships;
+mod snapshot;
+mod solar_wind;
+mod sound;
+#[cfg(not(target_arch = "wasm32"))]
+mod spectator;
+mod squads;
+mod star_system;
+mod starfield;
+mod stations;
+mod tactics;
+mod targeting;
+mod telemetry;
+mod torpedo;
+mod tractor;
+mod turrets;
+mod utility;
+mod weapons;
+mod world_events;
+mod wormholes;
+
+use ammo::Ammo;
+use behavior::{AiState, PatrolRoute};
+use brain::{Brain, BrainScheduler};
+use commander::{Order, Orders};
+use config::{Configuration, ShipStats};
+use damage::{Health, LastAttacker, Shield};
+use difficulty::{AiDifficulty, Reaction};
+use docking::Docking;
+use emp::Disabled;
+use factions::Factions;
+use formation::FormationSlot;
+use influence::InfluenceMap;
+use launch::LaunchOptions;
+use personality::Personality;
+use point_defense::PointDefense;
+use rng::GameRng;
+use ron_asset::RonAssetPlugin;
+use scenario::ScenarioEntity;
+use scripting::ScriptedTarget;
+use settings::UserSettings;
+use ships::ShipDefinitions;
+use squads::SquadMember;
+use tactics::Maneuver;
+use targeting::{
+    aim_at, Candidate, CandidatesByFaction, Lead, Marked, TargetingPolicies, TargetingPolicy,
+    ThreatTable,
+};
+use tractor::TractorBeam;
+use weapons::{Weapon, WeaponKind};
+
+/// Timestep shared by rapier and the gameplay systems of the `FixedUpdate` schedule.
+const FIXED_TIMESTEP: f32 = 1. / 60.;
+
+const INITIAL_FLEET_SIZE: usize = 10;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Gravity War".into(),
-                present_mode: PresentMode::AutoVsync,
-                ..default()
-            }),
-            ..default()
-        }))
-        .add_plugin(DebugLinesPlugin::default())
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0))
-        .add_plugin(RapierDebugRenderPlugin::default())
-        .add_plugin(FrameTimeDiagnosticsPlugin::default())
-        .add_plugin(LogDiagnosticsPlugin::default())
-        .insert_resource(ClearColor(Color::BLACK))
-        .init_resource::<Configuration>() // `ResourceInspectorPlugin` won't initialize the resource
+    let options = LaunchOptions::from_args();
+    let settings = UserSettings::load();
+    let (width, height) = options
+        .window
+        .unwrap_or((settings.window.width, settings.window.height));
+
+    let mut app = App::new();
+    // Registers the `mods://` asset source, so must come before the `AssetPlugin`
+    app.add_plugins(mods::ModsPlugin::discover());
+    if options.headless {
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    close_when_requested: false,
+                })
+                .set(RenderPlugin {
+                    render_creation: WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }
+                    .into(),
+                    ..default()
+                })
+                .disable::<WinitPlugin>(),
+            ScheduleRunnerPlugin::run_loop(Duration::from_secs_f32(FIXED_TIMESTEP)),
+        ));
+    } else {
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Gravity War".into(),
+                        resolution: WindowResolution::new(width, height),
+                        present_mode: if settings.window.vsync {
+                            PresentMode::AutoVsync
+                        } else {
+                            PresentMode::AutoNoVsync
+                        },
+                        canvas: Some("#bevy".into()),
+                        fit_canvas_to_parent: true,
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(AssetPlugin {
+                    // Hot reload of gameplay files, needs `bevy/file_watcher`
+                    watch_for_changes_override: Some(cfg!(feature = "dev-tools")),
+                    ..default()
+                }),
+        );
+    }
+
+    let physics = RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(100.0);
+    if options.is_lockstep() {
+        // Peers step the physics exactly once per exchanged tick
+        app.add_plugins(physics.in_fixed_schedule())
+            .insert_resource(TimestepMode::Fixed {
+                dt: FIXED_TIMESTEP,
+                substeps: 1,
+            });
+    } else {
+        app.add_plugins(physics)
+            .insert_resource(TimestepMode::Interpolated {
+                dt: FIXED_TIMESTEP,
+                time_scale: 1.,
+                substeps: 1,
+            });
+    }
+
+    app.insert_resource(Time::<Fixed>::from_seconds(FIXED_TIMESTEP.into()))
+        .insert_resource(GameRng::new(options.seed))
+        // Networked games start in the lobby
+        .insert_state(if options.network.is_some() {
+            GameState::Lobby
+        } else {
+            GameState::Loading
+        })
+        .init_resource::<SelectedClass>()
+        .add_event::<SpawnRequest>()
+        .add_event::<ShipDestroyed>()
+        // `ResourceInspectorPlugin` won't initialize the resource
+        .insert_resource(settings.gameplay.clone().unwrap_or_default())
         .register_type::<Configuration>() // you need to register your type to display it
-        .add_plugin(ResourceInspectorPlugin::<Configuration>::default())
-        .add_startup_systems((setup_graphics, spawn_stars))
-        .add_systems((
-            update_targets,
-            apply_forces.after(update_targets),
-            camera_follow_spaceships,
-            close_on_esc,
-            move_spaceship,
-            spawn_by_click,
-        ))
-        .run();
+        .register_type::<ShipStats>()
+        .add_plugins(RonAssetPlugin::<Configuration>::new(&["config.ron"]))
+        .add_plugins(settings::SettingsPlugin)
+        .add_plugins(localization::LocalizationPlugin)
+        .add_plugins(sound::SoundPlugin)
+        .add_plugins(mixer::MixerPlugin)
+        .add_plugins(scenario::ScenarioPlugin)
+        .add_plugins(star_system::StarSystemPlugin)
+        .add_plugins(starfield::StarfieldPlugin)
+        .add_plugins(gravity::GravityPlugin)
+        .add_plugins(orbits::OrbitsPlugin)
+        .add_plugins(black_hole::BlackHolePlugin)
+        .add_plugins(wormholes::WormholesPlugin)
+        .add_plugins(nebula::NebulaPlugin)
+        .add_plugins(solar_wind::SolarWindPlugin)
+        .add_plugins(stations::StationsPlugin)
+        .add_plugins(comets::CometsPlugin)
+        .add_plugins(bounds::BoundsPlugin)
+        .add_plugins(debris::DebrisPlugin)
+        .add_plugins(docking::DockingPlugin)
+        .add_plugins(dust::DustPlugin)
+        .add_plugins(hazards::HazardsPlugin)
+        .add_plugins(world_events::WorldEventsPlugin)
+        .add_plugins(behavior::BehaviorPlugin)
+        .add_plugins(utility::UtilityPlugin)
+        .add_plugins(formation::FormationPlugin)
+        .add_plugins(squads::SquadsPlugin)
+        .add_plugins(difficulty::DifficultyPlugin)
+        .add_plugins(commander::CommanderPlugin)
+        .add_plugins(personality::PersonalityPlugin)
+        .add_plugins(influence::InfluencePlugin)
+        .add_plugins(tactics::TacticsPlugin)
+        .add_plugins(targeting::TargetingPlugin)
+        .add_plugins(asteroids::AsteroidsPlugin)
+        .add_plugins(share_code::ShareCodePlugin)
+        .add_plugins(factions::FactionsPlugin)
+        .add_plugins(accessibility::AccessibilityPlugin)
+        .add_plugins(game_assets::GameAssetsPlugin)
+        .add_plugins(scripting::ScriptingPlugin)
+        .add_plugins(ships::ShipsPlugin)
+        .add_plugins(brain::BrainPlugin)
+        .add_plugins(debug_draw::DebugDrawPlugin)
+        .add_plugins(lod::LodPlugin)
+        .add_plugins(snapshot::SnapshotPlugin)
+        .add_plugins(telemetry::TelemetryPlugin)
+        .add_plugins(weapons::WeaponsPlugin)
+        .add_plugins(damage::DamagePlugin)
+        .add_plugins(ammo::AmmoPlugin)
+        .add_plugins(boarding::BoardingPlugin)
+        .add_plugins(missile::MissilePlugin)
+        .add_plugins(mines::MinesPlugin)
+        .add_plugins(turrets::TurretsPlugin)
+        .add_plugins(point_defense::PointDefensePlugin)
+        .add_plugins(emp::EmpPlugin)
+        .add_plugins(flak::FlakPlugin)
+        .add_plugins(torpedo::TorpedoPlugin)
+        .add_plugins(tractor::TractorPlugin)
+        .add_plugins(ramming::RammingPlugin)
+        .add_observer(config::resolve_ship_stats)
+        .add_systems(
+            Startup,
+            (
+                setup_graphics,
+                config::load_configuration_file,
+                launch::apply_timescale,
+            ),
+        )
+        // Once the factions of the scenario are known
+        .add_systems(
+            OnEnter(GameState::InGame),
+            spawn_initial_fleets.run_if(launch::is_authoritative),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                // Kept stale while blinded
+                update_targets.run_if(not(world_events::targeting_blinded)),
+                apply_forces,
+            )
+                .chain()
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Spaceship>.and(launch::is_authoritative)),
+        )
+        .add_systems(
+            Update,
+            (
+                camera_follow_spaceships
+                    .run_if(any_with_component::<Spaceship>.and(not(launch::is_spectator))),
+                spawn_by_click.run_if(not(in_state(GameState::Lobby))),
+            )
+                .distributive_run_if(any_with_component::<Camera>),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    move_spaceship.run_if(any_with_component::<Spaceship>),
+                    spawn_requested_ships.run_if(on_event::<SpawnRequest>),
+                )
+                    .run_if(launch::is_authoritative.and(not(launch::is_lockstep))),
+                select_class,
+                config::apply_configuration_file.run_if(on_event::<AssetEvent<Configuration>>),
+                config::refresh_ship_stats.run_if(
+                    resource_changed::<Configuration>
+                        .or(on_event::<AssetEvent<ships::ShipDefinition>>),
+                ),
+            ),
+        );
+
+    // There is no window to close nor file system in a browser tab
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_systems(Update, exit_on_esc).add_plugins((
+        autosave::AutosavePlugin,
+        saves::SavesPlugin,
+        leaderboard::LeaderboardPlugin,
+        achievements::AchievementsPlugin,
+    ));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(port) = options.api {
+        app.add_plugins(api::ApiPlugin { port });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(channel) = &options.chat {
+        app.add_plugins(chat::ChatPlugin {
+            server: options.chat_server.clone(),
+            channel: channel.clone(),
+        });
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(role) = options.network {
+        app.add_plugins(network::NetworkPlugin {
+            role,
+            lockstep: options.lockstep,
+            spectator: options.spectator,
+        });
+    }
+
+    #[cfg(feature = "dev-tools")]
+    if !options.headless {
+        app.add_plugins(dev_tools::DevToolsPlugin);
+    }
+
+    #[cfg(feature = "lighting")]
+    if !options.headless {
+        app.add_plugins(lighting::LightingPlugin);
+    }
+
+    app.insert_resource(options).insert_resource(settings).run();
 }
 
-#[derive(Reflect, Resource, InspectorOptions)]
-#[reflect(Resource, InspectorOptions)]
-struct Configuration {
-    rotation_force: f32,
-    propulsion_force: f32,
-    aim_distance: f32,
-    rotation_max: f32,
+/// The game waits in `Loading` for the scenario, if any. Networked games
+/// start in the `Lobby` until the host launches them.
+#[derive(States, Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GameState {
+    Lobby,
+    #[default]
+    Loading,
+    InGame,
 }
 
-impl Default for Configuration {
+#[derive(Component, Default)]
+#[require(
+    Target,
+    Brain,
+    Weapon,
+    PointDefense,
+    TractorBeam,
+    LastAttacker,
+    Docking,
+    AiState,
+    Reaction,
+    Personality,
+    ThreatTable,
+    Maneuver
+)]
+struct Spaceship;
+
+/// Name of the [`ships::ShipDefinition`] of a ship.
+#[derive(Component, Reflect, Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
+#[reflect(Component)]
+#[serde(transparent)]
+pub struct ShipClass(pub String);
+
+impl Default for ShipClass {
     fn default() -> Self {
-        Self {
-            rotation_force: 0.02,
-            propulsion_force: 50.,
-            aim_distance: 100.,
-            rotation_max: 0.05,
-        }
+        Self("fighter".into())
     }
 }
 
-#[derive(Component, Default)]
-struct Spaceship;
+/// Gameplay systems of the `FixedUpdate` schedule, paused as a whole by lockstep.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulationSet;
+
+/// Ship spawn asked by a player, applied by the authoritative simulation.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+pub struct SpawnRequest {
+    pub faction: Faction,
+    pub class: ShipClass,
+    pub position: Vec2,
+}
+
+/// A ship was destroyed, by the ships of `killer` if any.
+#[derive(Event, Clone, Copy, Debug)]
+// Mostly read by the achievements, which aren't available on the web
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+pub struct ShipDestroyed {
+    pub victim: Faction,
+    pub killer: Option<Faction>,
+    /// The killing shot was bent by gravity.
+    pub slingshot: bool,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Class of the ships spawned by click, cycled with `Tab` by default.
+#[derive(Resource, Default)]
+struct SelectedClass(ShipClass);
 
 #[derive(Component, Default)]
 struct Target {
     translation: Vec3,
     distance: f32,
+    /// Whether the target is an enemy ship, rather than a point to go to.
+    hostile: bool,
 }
 
-#[derive(Component, Hash, Clone, Copy, PartialEq, Eq)]
-struct Faction(pub u32);
-
-impl From<Faction> for Color {
-    fn from(value: Faction) -> Self {
-        const COLORS: [Color; 5] = [
-            Color::BLUE,
-            Color::RED,
-            Color::GREEN,
-            Color::YELLOW,
-            Color::PURPLE,
-        ];
-        COLORS[value.0 as usize]
+impl Target {
+    /// Point to go to at `translation`, seen from `from`.
+    fn point(translation: Vec3, from: Vec3) -> Self {
+        Self {
+            translation,
+            distance: translation.distance(from),
+            hostile: false,
+        }
     }
 }
 
+#[derive(
+    Component, Serialize, Deserialize, Hash, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+pub struct Faction(pub u32);
+
 fn setup_graphics(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
+    commands.spawn(Camera2d);
 }
 
-fn update_targets(mut targets: Query<(&Faction, &Transform, &mut Target)>) {
-    let targets_by_faction: HashMap<Faction, Vec<Vec3>> = targets
-        .iter()
-        .group_by(|(faction, ..)| **faction)
-        .into_iter()
-        .map(|(faction, group)| {
-            (
-                faction,
-                group
-                    .map(|(_, transform, _)| transform.translation)
-                    .collect(),
-            )
-        })
-        .collect();
+/// What the ships pick their target among and with, besides the other ships.
+#[derive(SystemParam)]
+struct TargetingContext<'w, 's> {
+    wormholes: Query<'w, 's, (&'static wormholes::Wormhole, &'static Transform)>,
+    stations: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Faction,
+            &'static Transform,
+            &'static Health,
+            Has<Marked>,
+        ),
+        With<stations::Station>,
+    >,
+    docks: Query<'w, 's, &'static Transform, With<docking::Dock>>,
+    squad_members: Query<'w, 's, &'static SquadMember>,
+    personalities: Query<'w, 's, &'static Personality>,
+    threat_tables: Query<'w, 's, &'static ThreatTable>,
+    factions: Res<'w, Factions>,
+    policies: Res<'w, TargetingPolicies>,
+    orders: Res<'w, Orders>,
+    difficulty: Res<'w, AiDifficulty>,
+}
 
-    for (faction, transform, mut target) in targets.iter_mut() {
-        if let Some((&closest_target, target_distance)) = targets_by_faction
+/// Targets of the far ships are handled by `lod::update_coarse_targets`.
+fn update_targets(
+    mut targets: Query<
+        (
+            Entity,
+            &Faction,
+            &Transform,
+            &Velocity,
+            &mut Target,
+            Option<&ScriptedTarget>,
+            &Ammo,
+            &Docking,
+            Option<&nebula::Concealed>,
+            &Health,
+            &ShipStats,
+            Has<Marked>,
+            Option<&TargetingPolicy>,
+            Has<lod::Coarse>,
+            Has<Disabled>,
+        ),
+        With<Spaceship>,
+    >,
+    context: TargetingContext,
+    mut reactions: Query<&mut Reaction>,
+    mut scheduler: ResMut<BrainScheduler>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+    configs: Res<Configuration>,
+) {
+    let TargetingContext {
+        wormholes,
+        stations,
+        docks,
+        squad_members,
+        personalities,
+        threat_tables,
+        factions,
+        policies,
+        orders,
+        difficulty,
+    } = context;
+    let mouths = if configs.wormhole_targeting {
+        wormholes::mouths(&wormholes)
+    } else {
+        vec![]
+    };
+    let targets_by_faction = group_targets_by_faction(
+        targets
             .iter()
-            .filter(|(target_faction, _)| *target_faction != faction)
-            .flat_map(|(_, translations)| translations)
-            .map(|target| (target, target.distance(transform.translation)))
-            .min_by(|(_, a_distance), (_, b_distance)| a_distance.total_cmp(b_distance))
+            .map(
+                |(
+                    entity,
+                    faction,
+                    transform,
+                    velocity,
+                    ..,
+                    concealed,
+                    health,
+                    stats,
+                    marked,
+                    _,
+                    _,
+                    _,
+                )| {
+                    let candidate = Candidate::new(entity, transform, concealed)
+                        .with_velocity(velocity.linvel)
+                        .with_health(health.current)
+                        .with_threat(stats)
+                        .with_mark(marked);
+                    (*faction, candidate)
+                },
+            )
+            // The enemy stations are targets too
+            .chain(
+                stations
+                    .iter()
+                    .map(|(entity, faction, transform, health, marked)| {
+                        let candidate = Candidate::new(entity, transform, None)
+                            .with_health(health.current)
+                            .with_mark(marked);
+                        (*faction, candidate)
+                    }),
+            ),
+    );
+
+    profile_scope!("find_targets");
+    scheduler.run_batch(|entity| {
+        // Position and enemy of the squad leader
+        let leader = squad_members
+            .get(entity)
+            .ok()
+            .and_then(|member| targets.get(member.leader).ok())
+            .map(|(_, _, leader_transform, _, leader_target, ..)| {
+                let enemy = leader_target.hostile.then_some(leader_target.translation);
+                (leader_transform.translation, enemy)
+            });
+        // The attacker which hurt the ship the most lately
+        let attacker = threat_tables
+            .get(entity)
+            .ok()
+            .and_then(ThreatTable::highest)
+            .and_then(|attacker| targets.get(attacker).ok())
+            .map(
+                |(attacker, attacker_faction, attacker_transform, attacker_velocity, ..)| {
+                    let candidate = Candidate::new(attacker, attacker_transform, None)
+                        .with_velocity(attacker_velocity.linvel);
+                    (*attacker_faction, candidate)
+                },
+            );
+        let Ok((
+            _,
+            faction,
+            transform,
+            velocity,
+            mut target,
+            scripted_target,
+            ammo,
+            docking,
+            ..,
+            stats,
+            _,
+            policy,
+            coarse,
+            disabled,
+        )) = targets.get_mut(entity)
+        else {
+            return false;
+        };
+        // Keeps no target until recovered
+        if disabled {
+            return true;
+        }
+        if let Some(scripted_target) = scripted_target {
+            *target = Target::point(scripted_target.0.extend(0.), transform.translation);
+        } else if let Some(dock) = docking.dock().and_then(|dock| docks.get(dock).ok()) {
+            // Badly damaged, to the dock for repairs
+            *target = Target::point(
+                dock.translation.truncate().extend(0.),
+                transform.translation,
+            );
+        } else if let Some(home) = factions.home(*faction).filter(|_| ammo.resupplying) {
+            // Out of ammo, back home to resupply
+            *target = Target::point(home.extend(0.), transform.translation);
+        } else if let Some(Order::Retreat(home)) = orders.get(*faction) {
+            // Ordered out of the fight
+            *target = Target::point(home.extend(0.), transform.translation);
+        } else if !coarse
+            && reactions.get_mut(entity).map_or(true, |mut reaction| {
+                reaction.react(time.elapsed_secs(), difficulty.reaction_secs(*faction))
+            })
         {
-            *target = Target {
-                translation: closest_target,
-                distance: target_distance,
+            let policy = policy.copied().unwrap_or(policies.get(*faction));
+            // Beams hit instantly
+            let lead = (configs.aim_lead && stats.weapon != WeaponKind::Beam).then(|| Lead {
+                velocity: velocity.linvel,
+                projectile_speed: stats.projectile_speed,
+            });
+            let attacker = attacker.filter(|(attacker_faction, attacker)| {
+                attacker_faction != faction
+                    && attacker.translation.distance(transform.translation)
+                        <= configs.engage_distance
+            });
+            let enemy = match (attacker, leader) {
+                // Fighting back
+                (Some((_, attacker)), _) => Some(aim_at(&attacker, transform.translation, lead)),
+                // Focusing the enemy of the squad leader
+                (_, Some((_, Some(enemy)))) => Some(Target {
+                    hostile: true,
+                    ..Target::point(enemy, transform.translation)
+                }),
+                _ => policy.select(
+                    &targets_by_faction,
+                    *faction,
+                    transform.translation,
+                    configs.engage_distance,
+                    lead,
+                ),
             };
+            if let Some(mut enemy) = enemy {
+                let error = difficulty.aim_error(*faction)
+                    + personalities
+                        .get(entity)
+                        .map_or(0., Personality::aim_spread);
+                if error > 0. {
+                    let offset = enemy.translation - transform.translation;
+                    enemy.translation = transform.translation
+                        + Quat::from_rotation_z(rng.gen_range(-error..=error)) * offset;
+                }
+                *target = enemy;
+            } else if let Some((leader_translation, None)) = leader {
+                // Idle, back to the squad leader
+                *target = Target::point(leader_translation, transform.translation);
+            }
+            if let Some(definition) = factions.get(*faction) {
+                let translation = definition.restrain_target(target.translation);
+                *target = Target {
+                    translation,
+                    distance: translation.distance(transform.translation),
+                    hostile: target.hostile && translation == target.translation,
+                };
+            }
+            let position = transform.translation.truncate();
+            if let Some(entry) =
+                wormholes::shortcut(position, target.translation.truncate(), &mouths)
+            {
+                // Through the wormhole first, out of range anyway
+                *target = Target::point(entry.extend(0.), transform.translation);
+            }
         }
+        true
+    });
+}
+
+fn group_targets_by_faction(
+    targets: impl Iterator<Item = (Faction, Candidate)>,
+) -> CandidatesByFaction {
+    profile_scope!("group_targets_by_faction");
+    let mut targets_by_faction = CandidatesByFaction::new();
+    for (faction, candidate) in targets {
+        targets_by_faction
+            .entry(faction)
+            .or_default()
+            .push(candidate);
     }
+    targets_by_faction
+}
+
+fn closest_target(
+    targets_by_faction: &CandidatesByFaction,
+    faction: Faction,
+    translation: Vec3,
+) -> Option<Target> {
+    TargetingPolicy::Nearest.select(targets_by_faction, faction, translation, 0., None)
+}
+
+/// What the ships steer with and around.
+#[derive(SystemParam)]
+struct Surroundings<'w, 's> {
+    ships: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Faction,
+            &'static Transform,
+            &'static Velocity,
+        ),
+        With<Spaceship>,
+    >,
+    hazards: Query<'w, 's, (&'static hazards::Hazard, &'static Transform)>,
+    obstacles: avoidance::Obstacles<'w, 's>,
+    munitions: evasion::Munitions<'w, 's>,
+}
+
+/// Decisions above the ships steering them.
+#[derive(SystemParam)]
+struct Strategy<'w> {
+    orders: Res<'w, Orders>,
+    influence: Res<'w, InfluenceMap>,
+    difficulty: Res<'w, AiDifficulty>,
 }
 
 fn apply_forces(
     mut spaceship_forces: Query<
-        (&Faction, &Target, &Transform, &mut ExternalForce),
-        With<Spaceship>,
+        (
+            Entity,
+            &Faction,
+            &ShipStats,
+            &Target,
+            &AiState,
+            &Transform,
+            &mut ExternalForce,
+            Option<&FormationSlot>,
+            Option<&PatrolRoute>,
+            &Personality,
+            &Maneuver,
+            Has<ScriptedTarget>,
+            &Docking,
+            &Ammo,
+        ),
+        (With<Spaceship>, Without<lod::Coarse>, Without<Disabled>),
     >,
-    mut lines: ResMut<DebugLines>,
+    surroundings: Surroundings,
+    strategy: Strategy,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
     configs: Res<Configuration>,
+    settings: Res<UserSettings>,
 ) {
-    for (faction, target, transform, mut ext_force) in spaceship_forces.iter_mut() {
-        let target_direction = target.translation - transform.translation;
-        let direction = transform.up();
-
-        let angle = direction
-            .truncate()
-            .angle_between(target_direction.truncate());
-
-        ext_force.torque =
-            (angle * configs.rotation_force).clamp(-configs.rotation_max, configs.rotation_max);
-        ext_force.force = (direction * configs.propulsion_force)
-                .truncate()
-                // .clamp_length_min(target_distance )
-                ;
-
-        let pos = transform.translation;
-        lines.line_colored(pos, pos + direction * 100., 0., Color::from(*faction));
-        // lines.line_colored(pos, pos + target_direction * 0.1, 0., Color::YELLOW);
+    profile_scope!("steer_spaceships");
+    let Surroundings {
+        ships,
+        hazards,
+        obstacles,
+        munitions,
+    } = surroundings;
+    let Strategy {
+        orders,
+        influence,
+        difficulty,
+    } = strategy;
+    let zones = hazards::zones(&hazards);
+    let flock = flocking::Flock::new(
+        ships.iter().map(|(entity, faction, transform, velocity)| {
+            (
+                entity,
+                *faction,
+                transform.translation.truncate(),
+                velocity.linvel,
+            )
+        }),
+        &configs,
+    );
+    let incoming_fire = evasion::IncomingFire::new(
+        &munitions,
+        ships
+            .iter()
+            .map(|(_, faction, transform, _)| (*faction, transform)),
+    );
+    for (
+        entity,
+        faction,
+        stats,
+        target,
+        state,
+        transform,
+        mut ext_force,
+        slot,
+        route,
+        personality,
+        maneuver,
+        scripted,
+        docking,
+        ammo,
+    ) in spaceship_forces.iter_mut()
+    {
+        let stats = &difficulty.scale(*faction, stats);
+        let position = transform.translation.truncate();
+        let target_position = target.translation.truncate();
+        let goal = match state {
+            AiState::Flee { from: Some(from) } => maneuver.aim.unwrap_or(2. * position - *from),
+            // Scripted, or heading for a dock or home rather than the objective
+            AiState::Patrol if scripted || docking.dock().is_some() || ammo.resupplying => {
+                target_position
+            }
+            AiState::Patrol => {
+                let objective = route
+                    .and_then(PatrolRoute::waypoint)
+                    .or_else(|| orders.get(*faction).map(Order::objective))
+                    .unwrap_or(target_position);
+                // Not alone into an overwhelming concentration of enemies
+                if influence.enemy_strength(objective, *faction)
+                    > influence.ally_strength(position, *faction) * influence::OVERWHELMING_RATIO
+                {
+                    flock
+                        .allies_center(entity, *faction, position)
+                        .unwrap_or(2. * position - objective)
+                } else {
+                    maneuver.aim.unwrap_or(objective)
+                }
+            }
+            AiState::Orbit => utility::orbit_point(position, target_position, &configs),
+            // No free slot found yet, away from the enemy meanwhile
+            AiState::Dock if docking.dock().is_none() && target.hostile => {
+                2. * position - target_position
+            }
+            AiState::Regroup => flock
+                .allies_center(entity, *faction, position)
+                .unwrap_or(target_position),
+            _ => target_position,
+        };
+        let waypoint = hazards::avoid(position, goal, &zones);
+        *ext_force = steer(transform, waypoint.extend(0.), stats);
+        ext_force.force *= state.thrust();
+        // Spaced out and moving as a swarm with the allies around
+        ext_force.force +=
+            flock.steering(entity, *faction, position, &configs) * stats.propulsion_force;
+        // In formation until combat breaks it
+        if let (AiState::Patrol, Some(slot)) = (state, slot) {
+            if let Ok((_, _, leader, _)) = ships.get(slot.leader) {
+                ext_force.force += formation::hold_formation(slot, leader, position, &configs)
+                    * stats.propulsion_force;
+            }
+        }
+        // Around the planets and asteroids ahead
+        ext_force.force += obstacles.steering(entity, transform, &configs)
+            * personality.caution
+            * stats.propulsion_force;
+        // Jinking under fire
+        if let Some(evasion) =
+            incoming_fire.evasion(entity, *faction, transform, time.elapsed_secs(), &configs)
+        {
+            ext_force.force += evasion * stats.propulsion_force;
+            // Drawn anyway, so that the setting doesn't change the random sequence
+            let shake = rng.gen_range(-1.0..=1.0);
+            if !settings.accessibility.reduced_motion {
+                ext_force.torque = (ext_force.torque + shake * stats.rotation_max)
+                    .clamp(-stats.rotation_max, stats.rotation_max);
+            }
+        }
     }
 }
 
+/// Turns toward `target` while thrusting ahead, also used by the missiles.
+fn steer(transform: &Transform, target: Vec3, stats: &ShipStats) -> ExternalForce {
+    let target_direction = target - transform.translation;
+    let direction = transform.up();
+
+    let angle = direction.truncate().angle_to(target_direction.truncate());
+
+    ExternalForce {
+        torque: (angle * stats.rotation_force).clamp(-stats.rotation_max, stats.rotation_max),
+        force: (direction * stats.propulsion_force).truncate(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn exit_on_esc(keyboard: Res<ButtonInput<KeyCode>>, mut exit: EventWriter<AppExit>) {
+    if keyboard.just_pressed(KeyCode::Escape) {
+        exit.send(AppExit::Success);
+    }
+}
+
+type FleetTransforms<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, &'static Faction), With<Spaceship>>;
+
 fn move_spaceship(
-    keyboard: Res<Input<KeyCode>>,
-    mut spaceships: Query<(&mut Transform, &Faction), With<Spaceship>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut spaceships: FleetTransforms,
     time: Res<Time>,
 ) {
+    move_fleet(
+        &mut spaceships,
+        Faction(1),
+        arrows_direction(&keyboard),
+        &time,
+    );
+}
+
+/// Unnormalized direction of the pressed arrow keys.
+fn arrows_direction(keyboard: &ButtonInput<KeyCode>) -> Vec2 {
+    let mut direction = Vec2::ZERO;
+    if keyboard.pressed(KeyCode::ArrowUp) {
+        direction += Vec2::Y;
+    }
+    if keyboard.pressed(KeyCode::ArrowDown) {
+        direction -= Vec2::Y;
+    }
+    if keyboard.pressed(KeyCode::ArrowLeft) {
+        direction -= Vec2::X;
+    }
+    if keyboard.pressed(KeyCode::ArrowRight) {
+        direction += Vec2::X;
+    }
+    direction
+}
+
+fn move_fleet(spaceships: &mut FleetTransforms, fleet: Faction, direction: Vec2, time: &Time) {
+    let speed: f32 = 1000. * time.delta_secs();
     for (mut transform, faction) in spaceships.iter_mut() {
-        if *faction != Faction(1) {
-            continue;
-        }
-        let speed: f32 = 1000. * time.delta_seconds();
-        if keyboard.pressed(KeyCode::Up) {
-            transform.translation += Vec3::Y * speed;
-        }
-        if keyboard.pressed(KeyCode::Down) {
-            transform.translation -= Vec3::Y * speed;
-        }
-        if keyboard.pressed(KeyCode::Left) {
-            transform.translation -= Vec3::X * speed;
-        }
-        if keyboard.pressed(KeyCode::Right) {
-            transform.translation += Vec3::X * speed;
+        if *faction == fleet {
+            transform.translation += (direction * speed).extend(0.);
         }
     }
 }
 
 fn spawn_by_click(
-    mut commands: Commands,
-    mouse_button_input: Res<Input<MouseButton>>,
-    mut cursor_moved_events: EventReader<CursorMoved>,
+    mut requests: EventWriter<SpawnRequest>,
+    mouse_button_input: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    selected_class: Res<SelectedClass>,
 ) {
     let faction_to_spawn = {
         if mouse_button_input.just_pressed(MouseButton::Left) {
@@ -195,37 +984,115 @@ fn spawn_by_click(
         }
     };
 
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+
     if let Some(faction) = faction_to_spawn {
-        if let Some(event) = cursor_moved_events.iter().last() {
-            commands.spawn(spaceship_bundle(
-                faction,
-                event.position.x - 1280. / 2.,
-                event.position.y - 720. / 2.,
-            ));
+        // The canvas is resized to its parent on the web, so the window size isn't fixed
+        if let Some(position) = window
+            .cursor_position()
+            .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+        {
+            requests.send(SpawnRequest {
+                faction: Faction(faction),
+                class: selected_class.0.clone(),
+                position,
+            });
         }
     }
 }
 
-fn spaceship_bundle(faction: u32, x: f32, y: f32) -> impl Bundle {
+fn spawn_requested_ships(mut commands: Commands, mut requests: EventReader<SpawnRequest>) {
+    for request in requests.read() {
+        commands.spawn(spaceship_bundle(
+            request.faction,
+            request.class.clone(),
+            request.position.x,
+            request.position.y,
+        ));
+    }
+}
+
+fn select_class(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    definitions: ShipDefinitions,
+    mut selected_class: ResMut<SelectedClass>,
+) {
+    if !keyboard.just_pressed(settings.input.select_class) {
+        return;
+    }
+
+    let classes = definitions.classes();
+    let next_index = classes
+        .iter()
+        .position(|class| *class == selected_class.0)
+        .map_or(0, |index| (index + 1) % classes.len());
+    if let Some(class) = classes.get(next_index) {
+        info!("Selected ship class: {}", class.0);
+        selected_class.0 = class.clone();
+    }
+}
+
+/// The [`ShipStats`] and the body of the [`ships::ShipDefinition`] are applied when spawned,
+/// the collider, damping and health here are fallbacks until the definition is loaded.
+fn spaceship_bundle(faction: Faction, class: ShipClass, x: f32, y: f32) -> impl Bundle {
     (
         Spaceship,
-        Faction(faction),
-        Target::default(),
+        faction,
+        class,
+        Health::default(),
+        Shield::default(),
         // Physic
         RigidBody::Dynamic,
         Sensor,
         Collider::cuboid(10., 30.),
         Restitution::coefficient(0.7),
         ExternalForce::default(),
+        ExternalImpulse::default(),
+        Velocity::default(),
+        TransformInterpolation::default(),
         GravityScale(0.),
         Damping {
             linear_damping: 1.,
             angular_damping: 2.,
         },
-        TransformBundle::from(Transform::from_xyz(x, y, 0.0)),
+        Transform::from_xyz(x, y, 0.0),
     )
 }
 
+/// Fleets of the `--factions` option, at the home of their faction or spread
+/// on a circle around the center.
+fn spawn_initial_fleets(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    factions: Res<Factions>,
+    mut rng: ResMut<GameRng>,
+) {
+    for faction in 1..=options.factions {
+        let fleet_center = factions.home(Faction(faction)).unwrap_or_else(|| {
+            let angle = faction as f32 / options.factions as f32 * TAU;
+            Vec2::from_angle(angle) * 600.
+        });
+
+        for _ in 0..INITIAL_FLEET_SIZE {
+            let offset = Vec2::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0));
+            let position = fleet_center + offset;
+            commands.spawn((
+                ScenarioEntity,
+                spaceship_bundle(
+                    Faction(faction),
+                    ShipClass::default(),
+                    position.x,
+                    position.y,
+                ),
+            ));
+        }
+    }
+}
+
 fn camera_follow_spaceships(
     mut camera: Query<&mut Transform, With<Camera>>,
     spaceships: Query<&Transform, (With<Spaceship>, Without<Camera>)>,
@@ -238,30 +1105,9 @@ fn camera_follow_spaceships(
     let translations = spaceships.iter().map(|t| t.translation.truncate());
     let avg_translation = translations.sum::<Vec2>() / count as f32;
 
-    let mut camera_transform = camera.single_mut();
+    let Ok(mut camera_transform) = camera.get_single_mut() else {
+        return;
+    };
     camera_transform.translation.x = avg_translation.x;
     camera_transform.translation.y = avg_translation.y;
 }
-
-fn spawn_stars(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-) {
-    let mut rng = SmallRng::seed_from_u64(42);
-
-    let mesh = meshes.add(shape::Circle::new(1.).into());
-    let material = materials.add(ColorMaterial::from(Color::WHITE));
-
-    for _ in 0..100 {
-        let x = rng.gen_range(-1000.0..1000.0);
-        let y = rng.gen_range(-1000.0..1000.0);
-
-        commands.spawn(MaterialMesh2dBundle {
-            mesh: mesh.clone().into(),
-            material: material.clone(),
-            transform: Transform::from_translation(Vec3::new(x, y, 0.)),
-            ..default()
-        });
-    }
-}
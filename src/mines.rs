@@ -0,0 +1,125 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use crate::{
+    damage::{self, damage_enemies_around, DamageEvent},
+    factions::Factions,
+    launch,
+    scenario::ScenarioEntity,
+    settings::UserSettings,
+    sound::Explosion,
+    Faction, GameState, SimulationSet, Spaceship,
+};
+
+/// Faction of the mines dropped with the key, the same as left click.
+const PLAYER_FACTION: Faction = Faction(1);
+const MINE_SIZE: f32 = 12.;
+/// Seconds before a dropped mine can be triggered.
+const ARMING_SECS: f32 = 1.;
+/// Distance of the enemy ships triggering a mine.
+const TRIGGER_RADIUS: f32 = 60.;
+/// Distance of the enemy ships damaged by the detonation.
+const BLAST_RADIUS: f32 = 100.;
+const BLAST_DAMAGE: f32 = 80.;
+
+/// Static mines dropped at the cursor with `E` by default, detonating when a
+/// ship of another faction comes close and damaging the enemies around.
+///
+/// Mines check the distance of the ships themselves rather than with sensor
+/// colliders, as the ships are sensors too unless they are solid.
+pub struct MinesPlugin;
+
+impl Plugin for MinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            drop_mine
+                .run_if(in_state(GameState::InGame))
+                .run_if(launch::is_authoritative.and(not(launch::is_lockstep))),
+        )
+        .add_systems(
+            FixedUpdate,
+            trigger_mines
+                .before(damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Mine>.and(launch::is_authoritative)),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Mine {
+    pub faction: Faction,
+    /// Seconds until the mine is armed.
+    pub arming_secs: f32,
+}
+
+fn drop_mine(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    factions: Res<Factions>,
+) {
+    if !keyboard.just_pressed(settings.input.drop_mine) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    commands.spawn((
+        ScenarioEntity,
+        Mine {
+            faction: PLAYER_FACTION,
+            arming_secs: ARMING_SECS,
+        },
+        Sprite::from_color(factions.color(PLAYER_FACTION), Vec2::splat(MINE_SIZE)),
+        Transform::from_translation(position.extend(0.))
+            .with_rotation(Quat::from_rotation_z(FRAC_PI_4)),
+    ));
+}
+
+fn trigger_mines(
+    mut commands: Commands,
+    mut mines: Query<(Entity, &mut Mine, &Transform)>,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    time: Res<Time>,
+    mut damages: EventWriter<DamageEvent>,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for (entity, mut mine, transform) in mines.iter_mut() {
+        if mine.arming_secs > 0. {
+            mine.arming_secs -= time.delta_secs();
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let triggered = ships.iter().any(|(_, faction, ship_transform)| {
+            *faction != mine.faction
+                && ship_transform.translation.truncate().distance(position) <= TRIGGER_RADIUS
+        });
+        if !triggered {
+            continue;
+        }
+
+        damage_enemies_around(
+            ships.iter(),
+            mine.faction,
+            position,
+            BLAST_RADIUS,
+            BLAST_DAMAGE,
+            &mut damages,
+        );
+        explosions.send(Explosion { position });
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    closest_target,
+    config::ShipStats,
+    damage::{damage_enemies_around, DamageEvent},
+    factions::Factions,
+    group_targets_by_faction, launch,
+    nebula::Concealed,
+    scenario::ScenarioEntity,
+    settings::UserSettings,
+    sound::{Explosion, WeaponFired},
+    steer,
+    targeting::Candidate,
+    weapons::{self, Heat, Weapon, WeaponKind},
+    Faction, GameState, SimulationSet, Spaceship, Target,
+};
+
+/// Faction of the missiles launched with the key, the same as left click.
+const PLAYER_FACTION: Faction = Faction(1);
+const MISSILE_SIZE: Vec2 = Vec2::new(4., 10.);
+const MISSILE_MASS: f32 = 0.2;
+/// Distance of the launcher ahead of the ship center.
+const MUZZLE_OFFSET: f32 = 35.;
+/// Seconds of thrust.
+const FUEL_SECS: f32 = 4.;
+/// Seconds of drift once out of fuel, before self-destructing.
+const COAST_SECS: f32 = 2.;
+/// Distance of the target triggering the explosion.
+const FUSE_RADIUS: f32 = 40.;
+/// Distance of the ships damaged by the explosion.
+const BLAST_RADIUS: f32 = 80.;
+const BLAST_DAMAGE: f32 = 50.;
+
+/// Homing missiles launched from the cursor with `Q` by default, and by the
+/// ships with a [`WeaponKind::Missiles`] weapon, steered toward the closest
+/// enemy with the same math as the ships, see [`steer`].
+///
+/// They thrust until their fuel runs out, then drift, and explode when an
+/// enemy gets close, damaging every enemy in the blast.
+pub struct MissilePlugin;
+
+impl Plugin for MissilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            launch_missile
+                .run_if(in_state(GameState::InGame))
+                .run_if(launch::is_authoritative.and(not(launch::is_lockstep))),
+        )
+        .add_systems(
+            FixedUpdate,
+            (
+                // After the weapon cooldowns
+                launch_ship_missiles.after(weapons::fire_weapons),
+                (update_missile_targets, steer_missiles, detonate_missiles)
+                    .chain()
+                    .run_if(any_with_component::<Missile>),
+            )
+                .chain()
+                .before(crate::damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+#[derive(Component)]
+#[require(Target)]
+pub struct Missile {
+    pub faction: Faction,
+    /// Seconds of thrust left, negative while drifting.
+    pub fuel_secs: f32,
+}
+
+/// Steering tuning of the missiles, lighter and more agile than the ships.
+fn missile_stats() -> ShipStats {
+    ShipStats {
+        rotation_force: 0.0005,
+        propulsion_force: 30.,
+        rotation_max: 0.001,
+        ..default()
+    }
+}
+
+fn launch_missile(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    factions: Res<Factions>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    if !keyboard.just_pressed(settings.input.launch_missile) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform))) = (windows.get_single(), cameras.get_single())
+    else {
+        return;
+    };
+    let Some(position) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+
+    spawn_missile(
+        &mut commands,
+        &factions,
+        PLAYER_FACTION,
+        Transform::from_translation(position.extend(0.)),
+        Vec2::ZERO,
+    );
+    fired.send(WeaponFired { position });
+}
+
+/// Launches a missile every `fire_cooldown` seconds from the ships with a
+/// [`WeaponKind::Missiles`] weapon, once their hostile target is within their
+/// `weapon_range`.
+fn launch_ship_missiles(
+    mut commands: Commands,
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &Velocity,
+            &ShipStats,
+            &Target,
+            &mut Weapon,
+            &mut Heat,
+            &mut Ammo,
+        ),
+        With<Spaceship>,
+    >,
+    factions: Res<Factions>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    for (faction, transform, velocity, stats, target, mut weapon, mut heat, mut ammo) in
+        ships.iter_mut()
+    {
+        if stats.weapon != WeaponKind::Missiles
+            || weapon.cooldown_secs > 0.
+            || heat.overheated_secs > 0.
+            || ammo.is_empty()
+            || !target.hostile
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+
+        weapon.cooldown_secs = stats.fire_cooldown;
+        heat.current += stats.heat_per_shot;
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        ammo.spend();
+        let position = transform.translation.truncate() + transform.up().truncate() * MUZZLE_OFFSET;
+        spawn_missile(
+            &mut commands,
+            &factions,
+            *faction,
+            Transform::from_translation(position.extend(0.)).with_rotation(transform.rotation),
+            velocity.linvel,
+        );
+        fired.send(WeaponFired { position });
+    }
+}
+
+fn spawn_missile(
+    commands: &mut Commands,
+    factions: &Factions,
+    faction: Faction,
+    transform: Transform,
+    velocity: Vec2,
+) {
+    commands.spawn((
+        ScenarioEntity,
+        Missile {
+            faction,
+            fuel_secs: FUEL_SECS,
+        },
+        Sprite::from_color(factions.color(faction), MISSILE_SIZE),
+        RigidBody::Dynamic,
+        Sensor,
+        Collider::ball(MISSILE_SIZE.x),
+        ColliderMassProperties::Mass(MISSILE_MASS),
+        ExternalForce::default(),
+        Velocity::linear(velocity),
+        GravityScale(0.),
+        Damping {
+            linear_damping: 0.5,
+            angular_damping: 2.,
+        },
+        transform,
+    ));
+}
+
+fn update_missile_targets(
+    mut missiles: Query<(&Missile, &Transform, &mut Target)>,
+    ships: Query<(Entity, &Faction, &Transform, Option<&Concealed>), With<Spaceship>>,
+) {
+    let targets_by_faction =
+        group_targets_by_faction(ships.iter().map(|(entity, faction, transform, concealed)| {
+            (*faction, Candidate::new(entity, transform, concealed))
+        }));
+    for (missile, transform, mut target) in missiles.iter_mut() {
+        *target = closest_target(&targets_by_faction, missile.faction, transform.translation)
+            // Drifts without enemies
+            .unwrap_or_default();
+    }
+}
+
+fn steer_missiles(
+    mut missiles: Query<(&mut Missile, &Target, &Transform, &mut ExternalForce)>,
+    time: Res<Time>,
+) {
+    let stats = missile_stats();
+    for (mut missile, target, transform, mut ext_force) in missiles.iter_mut() {
+        missile.fuel_secs -= time.delta_secs();
+        *ext_force = if missile.fuel_secs > 0. && target.hostile {
+            steer(transform, target.translation, &stats)
+        } else {
+            ExternalForce::default()
+        };
+    }
+}
+
+/// Explodes the missiles close to their target, and removes the ones drifting
+/// for too long.
+fn detonate_missiles(
+    mut commands: Commands,
+    missiles: Query<(Entity, &Missile, &Target, &Transform)>,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    mut damages: EventWriter<DamageEvent>,
+    mut explosions: EventWriter<Explosion>,
+) {
+    for (entity, missile, target, transform) in missiles.iter() {
+        if missile.fuel_secs < -COAST_SECS {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        if !target.hostile || target.distance > FUSE_RADIUS {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        damage_enemies_around(
+            ships.iter(),
+            missile.faction,
+            position,
+            BLAST_RADIUS,
+            BLAST_DAMAGE,
+            &mut damages,
+        );
+        explosions.send(Explosion { position });
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,214 @@
+use bevy::prelude::*;
+
+use crate::{
+    localization::Localization,
+    settings::{AudioSettings, UserSettings},
+};
+
+const VOLUME_STEP: f32 = 0.1;
+const BUSES: [Bus; 3] = [Bus::Master, Bus::Music, Bus::Effects];
+
+/// Groups the sounds in buses, whose volumes and mute toggles are kept in
+/// the [`AudioSettings`] and applied to the sinks every frame.
+///
+/// The mixer panel is toggled with `F4` by default. There, the digit keys
+/// select a bus, `-` and `+` change its volume and `M` mutes it.
+pub struct MixerPlugin;
+
+impl Plugin for MixerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SelectedBus>()
+            .add_systems(
+                Update,
+                (
+                    toggle_panel,
+                    (change_volumes, update_panel)
+                        .chain()
+                        .run_if(any_with_component::<MixerText>),
+                )
+                    .chain(),
+            )
+            // Once the sounds set their level
+            .add_systems(
+                PostUpdate,
+                apply_volumes.run_if(any_with_component::<Mixed>),
+            );
+    }
+}
+
+/// Group of sounds sharing a volume, under the master one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bus {
+    Master,
+    Music,
+    Effects,
+}
+
+/// Sound played through a bus, at its own `level` scaled by the bus volume.
+#[derive(Component)]
+pub struct Mixed {
+    pub bus: Bus,
+    pub level: f32,
+}
+
+impl Mixed {
+    pub fn new(bus: Bus, level: f32) -> Self {
+        Self { bus, level }
+    }
+}
+
+#[derive(Resource, Default)]
+struct SelectedBus(usize);
+
+#[derive(Component)]
+struct MixerText;
+
+impl AudioSettings {
+    fn volume_mut(&mut self, bus: Bus) -> (&mut f32, &mut bool) {
+        match bus {
+            Bus::Master => (&mut self.master_volume, &mut self.master_muted),
+            Bus::Music => (&mut self.music_volume, &mut self.music_muted),
+            Bus::Effects => (&mut self.effects_volume, &mut self.effects_muted),
+        }
+    }
+
+    fn volume_and_muted(&self, bus: Bus) -> (f32, bool) {
+        match bus {
+            Bus::Master => (self.master_volume, self.master_muted),
+            Bus::Music => (self.music_volume, self.music_muted),
+            Bus::Effects => (self.effects_volume, self.effects_muted),
+        }
+    }
+
+    fn volume(&self, bus: Bus) -> f32 {
+        let (volume, muted) = self.volume_and_muted(bus);
+        if muted {
+            0.
+        } else {
+            volume
+        }
+    }
+
+    /// Volume of the sounds of the bus, including the master volume.
+    pub fn gain(&self, bus: Bus) -> f32 {
+        match bus {
+            Bus::Master => self.volume(Bus::Master),
+            _ => self.volume(Bus::Master) * self.volume(bus),
+        }
+    }
+}
+
+fn toggle_panel(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    texts: Query<Entity, With<MixerText>>,
+) {
+    if !keyboard.just_pressed(settings.input.mixer) {
+        return;
+    }
+
+    if texts.is_empty() {
+        commands.spawn((
+            MixerText,
+            Text::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+        ));
+    } else {
+        for text in texts.iter() {
+            commands.entity(text).despawn_recursive();
+        }
+    }
+}
+
+fn change_volumes(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut selected: ResMut<SelectedBus>,
+    mut settings: ResMut<UserSettings>,
+) {
+    for (index, key) in [KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3]
+        .into_iter()
+        .enumerate()
+    {
+        if keyboard.just_pressed(key) {
+            selected.0 = index;
+        }
+    }
+
+    let step = if keyboard.just_pressed(KeyCode::Equal) {
+        VOLUME_STEP
+    } else if keyboard.just_pressed(KeyCode::Minus) {
+        -VOLUME_STEP
+    } else {
+        0.
+    };
+    let mute = keyboard.just_pressed(KeyCode::KeyM);
+    if step == 0. && !mute {
+        return;
+    }
+    let (volume, muted) = settings.audio.volume_mut(BUSES[selected.0]);
+    *volume = (*volume + step).clamp(0., 1.);
+    if mute {
+        *muted = !*muted;
+    }
+}
+
+fn update_panel(
+    mut texts: Query<&mut Text, With<MixerText>>,
+    selected: Res<SelectedBus>,
+    settings: Res<UserSettings>,
+    localization: Res<Localization>,
+) {
+    let Ok(mut text) = texts.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = vec![localization.text("mixer-title")];
+    lines.extend(BUSES.iter().enumerate().map(|(index, bus)| {
+        let (volume, muted) = settings.audio.volume_and_muted(*bus);
+        localization.format(
+            "mixer-bus",
+            &[
+                ("number", (index + 1).into()),
+                (
+                    "bus",
+                    localization
+                        .text(match bus {
+                            Bus::Master => "mixer-master",
+                            Bus::Music => "mixer-music",
+                            Bus::Effects => "mixer-effects",
+                        })
+                        .into(),
+                ),
+                ("volume", ((volume * 100.).round() as u32).into()),
+                ("muted", if muted { "yes" } else { "no" }.into()),
+                (
+                    "selected",
+                    if index == selected.0 { "yes" } else { "no" }.into(),
+                ),
+            ],
+        )
+    }));
+    lines.push(localization.text("mixer-help"));
+    text.0 = lines.join("\n");
+}
+
+fn apply_volumes(
+    settings: Res<UserSettings>,
+    sounds: Query<(&Mixed, Option<&AudioSink>, Option<&SpatialAudioSink>)>,
+) {
+    for (mixed, sink, spatial_sink) in sounds.iter() {
+        let volume = mixed.level * settings.audio.gain(mixed.bus);
+        if let Some(sink) = sink {
+            sink.set_volume(volume);
+        }
+        if let Some(sink) = spatial_sink {
+            sink.set_volume(volume);
+        }
+    }
+}
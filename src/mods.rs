@@ -0,0 +1,149 @@
+use std::{ffi::OsStr, fs, path::PathBuf};
+
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::asset::io::file::FileAssetReader;
+use bevy::{
+    asset::{io::AssetSourceBuilder, AssetPath},
+    prelude::*,
+};
+
+/// Folder of the mods, next to the `assets` folder, read as the `mods://` asset source.
+const MODS_FOLDER: &str = "mods";
+/// Optional list of the mod folders to load, in order.
+const LOAD_ORDER_FILE: &str = "load_order.ron";
+/// Sub-folders of a mod loaded at startup.
+const ASSET_FOLDERS: &[&str] = &["ships", "scenarios", "sprites", "factions"];
+
+/// Drop-in folders of `mods/`, each one with optional `ships/*.ship.ron`,
+/// `scenarios/*.scenario.ron`, `sprites/` and `factions/*.factions.ron`.
+///
+/// Mods are loaded in the order of `mods/load_order.ron` when present (unlisted
+/// folders are disabled), else alphabetically. Later mods override the ship
+/// classes and the factions of earlier ones, and of the bundled assets.
+///
+/// Must be added before the `AssetPlugin`, which reads the asset sources.
+pub struct ModsPlugin {
+    mods: Vec<Mod>,
+}
+
+struct Mod {
+    name: String,
+    /// Existing folders of [`ASSET_FOLDERS`].
+    folders: Vec<&'static str>,
+}
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_asset_source(
+            MODS_FOLDER,
+            AssetSourceBuilder::platform_default(MODS_FOLDER, None),
+        );
+
+        let folders: Vec<String> = self
+            .mods
+            .iter()
+            .flat_map(|m| {
+                m.folders
+                    .iter()
+                    .map(move |folder| format!("{MODS_FOLDER}://{}/{folder}", m.name))
+            })
+            .collect();
+        let names: Vec<String> = self.mods.iter().map(|m| m.name.clone()).collect();
+        app.insert_resource(LoadOrder(names)).add_systems(
+            Startup,
+            move |mut commands: Commands,
+                  asset_server: Res<AssetServer>,
+                  load_order: Res<LoadOrder>| {
+                if !load_order.0.is_empty() {
+                    info!("Loading mods: {}", load_order.0.join(", "));
+                }
+                let handles = folders
+                    .iter()
+                    .map(|folder| asset_server.load_folder(folder).untyped())
+                    .collect();
+                commands.insert_resource(ModHandles(handles));
+            },
+        );
+    }
+}
+
+/// Names of the loaded mods, the last one having the precedence.
+#[derive(Resource, Default)]
+pub struct LoadOrder(Vec<String>);
+
+impl LoadOrder {
+    /// Rank of the mod an asset comes from, 0 for the bundled assets.
+    pub fn priority(&self, path: &AssetPath) -> usize {
+        if path.source().as_str() != Some(MODS_FOLDER) {
+            return 0;
+        }
+        let Some(name) = path.path().iter().next() else {
+            return 0;
+        };
+        self.0
+            .iter()
+            .position(|mod_name| name == OsStr::new(mod_name))
+            .map_or(0, |index| index + 1)
+    }
+}
+
+/// Strong handles keeping the mod folders loaded, never read.
+#[derive(Resource)]
+#[allow(dead_code)]
+struct ModHandles(Vec<UntypedHandle>);
+
+/// Folders can't be listed on the web, so mods are only supported natively.
+#[cfg(not(target_arch = "wasm32"))]
+fn mods_dir() -> Option<PathBuf> {
+    Some(FileAssetReader::get_base_path().join(MODS_FOLDER))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn mods_dir() -> Option<PathBuf> {
+    None
+}
+
+impl ModsPlugin {
+    /// Scans the mods folder. Runs before the logger, so problems are printed.
+    pub fn discover() -> Self {
+        let Some(dir) = mods_dir().filter(|dir| dir.is_dir()) else {
+            return Self { mods: vec![] };
+        };
+
+        let names = match fs::read_to_string(dir.join(LOAD_ORDER_FILE)) {
+            Ok(text) => ron::from_str::<Vec<String>>(&text).unwrap_or_else(|err| {
+                eprintln!("Ignoring invalid mod load order: {err}");
+                vec![]
+            }),
+            Err(_) => {
+                let mut names: Vec<String> = fs::read_dir(&dir)
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .collect();
+                names.sort();
+                names
+            }
+        };
+
+        let mods = names
+            .into_iter()
+            .filter_map(|name| {
+                let mod_dir = dir.join(&name);
+                if !mod_dir.is_dir() {
+                    eprintln!("Mod {name:?} of the load order isn't in {}", dir.display());
+                    return None;
+                }
+                let folders = ASSET_FOLDERS
+                    .iter()
+                    .copied()
+                    .filter(|folder| mod_dir.join(folder).is_dir())
+                    .collect();
+                Some(Mod { name, folders })
+            })
+            .collect();
+        Self { mods }
+    }
+}
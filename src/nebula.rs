@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    star_system::{self, StarSystem},
+    GameState, SimulationSet, Spaceship,
+};
+
+const NEBULA_COLOR: Color = Color::srgba(0.4, 0.5, 0.9, 0.2);
+
+/// Nebulae of the `nebulae` of the scenario, or of the generated
+/// [`StarSystem`] without scenario: large clouds slowing down the ships
+/// inside with their `drag`, and hiding them from the ships farther than
+/// their `sensor_range`.
+///
+/// Nebulae are sensors, the shots and the solid ships go through them.
+pub struct NebulaPlugin;
+
+impl Plugin for NebulaPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            spawn_nebulae.after(star_system::generate_star_system),
+        )
+        .add_systems(
+            FixedUpdate,
+            enter_nebulae
+                .before(crate::update_targets)
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Nebula>.and(launch::is_authoritative)),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NebulaDefinition {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Linear damping added to the ships inside.
+    #[serde(default = "default_drag")]
+    pub drag: f32,
+    /// Distance within which the ships inside can be targeted.
+    #[serde(default = "default_sensor_range")]
+    pub sensor_range: f32,
+}
+
+fn default_drag() -> f32 {
+    2.
+}
+
+fn default_sensor_range() -> f32 {
+    200.
+}
+
+#[derive(Component)]
+pub struct Nebula {
+    pub radius: f32,
+    pub drag: f32,
+    pub sensor_range: f32,
+}
+
+/// The ship is inside a nebula, only visible within `range`.
+#[derive(Component)]
+pub struct Concealed {
+    pub range: f32,
+    /// Linear damping of the ship outside the nebula.
+    linear_damping: f32,
+}
+
+/// Distance within which a ship can be targeted.
+pub fn visibility(concealed: Option<&Concealed>) -> f32 {
+    concealed.map_or(f32::INFINITY, |concealed| concealed.range)
+}
+
+fn spawn_nebulae(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    star_system: Option<Res<StarSystem>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let nebulae = match (handle, &star_system) {
+        (Some(handle), _) => match scenarios.get(&handle.0) {
+            Some(scenario) => scenario.nebulae.as_slice(),
+            None => return,
+        },
+        (None, Some(star_system)) => star_system.nebulae.as_slice(),
+        (None, None) => return,
+    };
+    let material = materials.add(NEBULA_COLOR);
+    for nebula in nebulae {
+        commands.spawn((
+            ScenarioEntity,
+            Nebula {
+                radius: nebula.radius,
+                drag: nebula.drag,
+                sensor_range: nebula.sensor_range,
+            },
+            Collider::ball(nebula.radius),
+            Sensor,
+            Mesh2d(meshes.add(Circle::new(nebula.radius))),
+            MeshMaterial2d(material.clone()),
+            // Above the planets, below the ships
+            Transform::from_translation(nebula.position.extend(-0.5)),
+        ));
+    }
+}
+
+/// Conceals and slows down the ships inside the nebulae, restoring the others.
+fn enter_nebulae(
+    mut commands: Commands,
+    nebulae: Query<(&Nebula, &Transform)>,
+    mut ships: Query<(Entity, &Transform, &mut Damping, Option<&Concealed>), With<Spaceship>>,
+) {
+    for (entity, transform, mut damping, concealed) in ships.iter_mut() {
+        let position = transform.translation.truncate();
+        let nebula = nebulae.iter().find(|(nebula, nebula_transform)| {
+            nebula_transform.translation.truncate().distance(position) <= nebula.radius
+        });
+        match (nebula, concealed) {
+            (Some((nebula, _)), None) => {
+                commands.entity(entity).insert(Concealed {
+                    range: nebula.sensor_range,
+                    linear_damping: damping.linear_damping,
+                });
+                damping.linear_damping += nebula.drag;
+            }
+            (None, Some(concealed)) => {
+                commands.entity(entity).remove::<Concealed>();
+                damping.linear_damping = concealed.linear_damping;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer};
+use bevy_rapier2d::prelude::*;
+use bevy_renet::{
+    client_connected,
+    netcode::{
+        ClientAuthentication, NetcodeClientPlugin, NetcodeClientTransport, NetcodeServerPlugin,
+        NetcodeServerTransport, ServerAuthentication, ServerConfig, NETCODE_USER_DATA_BYTES,
+    },
+    renet::{ClientId, ConnectionConfig, DefaultChannel, RenetClient, RenetServer, ServerEvent},
+    RenetClientPlugin, RenetServerPlugin,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    arrows_direction,
+    launch::{LaunchOptions, NetworkRole},
+    lobby::{Advertiser, LobbyPlugin, Roster, SessionBrowser},
+    lockstep::LockstepPlugin,
+    move_fleet, spaceship_bundle,
+    spectator::SpectatorPlugin,
+    Faction, FleetTransforms, GameState, ShipClass, Spaceship, SpawnRequest,
+};
+
+/// Bumped when the messages change, so incompatible builds can't connect.
+const PROTOCOL_ID: u64 = 1;
+/// One player and the spectators.
+const MAX_CLIENTS: usize = 1 + MAX_SPECTATORS;
+const MAX_SPECTATORS: usize = 8;
+/// First byte of the connection user data of the spectators.
+const SPECTATOR_FLAG: u8 = 1;
+const STATE_PERIOD: Duration = Duration::from_millis(50);
+
+/// LAN multiplayer: the host runs the authoritative simulation, clients send
+/// their inputs and spawn requests, and display the ship states streamed by
+/// the host, interpolated between two updates.
+///
+/// Networked games start in the [`LobbyPlugin`], where each player picks a faction.
+/// Spectators receive the state like clients, but send no input.
+pub struct NetworkPlugin {
+    pub role: NetworkRole,
+    /// Exchanges inputs with [`LockstepPlugin`] instead of replicating the state.
+    pub lockstep: bool,
+    /// Joins as a spectator, with the [`SpectatorPlugin`].
+    pub spectator: bool,
+}
+
+impl Plugin for NetworkPlugin {
+    fn build(&self, app: &mut App) {
+        match self.role {
+            NetworkRole::Host(port) => {
+                let transport = match server_transport(port) {
+                    Ok(transport) => transport,
+                    Err(err) => {
+                        error!("Failed to host on port {port}: {err}");
+                        return;
+                    }
+                };
+                info!("Hosting on port {port}");
+                app.add_plugins((RenetServerPlugin, NetcodeServerPlugin))
+                    .insert_resource(RenetServer::new(ConnectionConfig::default()))
+                    .insert_resource(transport);
+                match Advertiser::new(port) {
+                    Ok(advertiser) => {
+                        app.insert_resource(advertiser);
+                    }
+                    Err(err) => warn!("The session won't be advertised on the LAN: {err}"),
+                }
+                if !self.lockstep {
+                    app.init_resource::<RemotePlayers>().add_systems(
+                        Update,
+                        (
+                            handle_server_events,
+                            receive_client_messages.run_if(in_state(GameState::InGame)),
+                            move_remote_fleets.run_if(any_with_component::<Spaceship>),
+                            broadcast_state.run_if(on_real_timer(STATE_PERIOD)),
+                        )
+                            .chain(),
+                    );
+                }
+            }
+            NetworkRole::Client(server) => {
+                let transport = match client_transport(server, self.spectator) {
+                    Ok(transport) => transport,
+                    Err(err) => {
+                        error!("Failed to connect to {server}: {err}");
+                        return;
+                    }
+                };
+                info!("Connecting to {server}");
+                app.add_plugins((RenetClientPlugin, NetcodeClientPlugin))
+                    .insert_resource(RenetClient::new(ConnectionConfig::default()))
+                    .insert_resource(transport);
+                self.add_client_systems(app);
+            }
+            NetworkRole::Browse => {
+                let browser = match SessionBrowser::new() {
+                    Ok(browser) => browser,
+                    Err(err) => {
+                        error!("Failed to listen to the sessions of the LAN: {err}");
+                        return;
+                    }
+                };
+                // The client is created once a session is picked in the lobby
+                app.add_plugins((RenetClientPlugin, NetcodeClientPlugin))
+                    .insert_resource(browser);
+                self.add_client_systems(app);
+            }
+        }
+
+        app.add_plugins(LobbyPlugin);
+        if self.lockstep {
+            app.add_plugins(LockstepPlugin);
+        }
+    }
+}
+
+impl NetworkPlugin {
+    fn add_client_systems(&self, app: &mut App) {
+        if !self.lockstep {
+            app.add_systems(
+                Update,
+                (
+                    send_inputs.run_if(|options: Res<LaunchOptions>| !options.spectator),
+                    receive_state,
+                    interpolate_replicas,
+                )
+                    .chain()
+                    .run_if(client_connected.and(in_state(GameState::InGame))),
+            );
+        }
+        if self.spectator {
+            app.add_plugins(SpectatorPlugin);
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum ClientMessage {
+    Spawn(SpawnRequest),
+    /// Direction of the arrow keys.
+    Move(Vec2),
+}
+
+#[derive(Serialize, Deserialize)]
+enum ServerMessage {
+    State(Vec<ShipState>),
+}
+
+#[derive(Serialize, Deserialize)]
+struct ShipState {
+    /// Entity of the ship on the host.
+    id: u64,
+    faction: Faction,
+    class: ShipClass,
+    translation: Vec2,
+    rotation: f32,
+}
+
+/// Last input of each connected client, whose faction is picked in the lobby.
+#[derive(Resource, Default)]
+struct RemotePlayers(HashMap<ClientId, Vec2>);
+
+/// Ship displayed by a client, moving from the previous state of the host
+/// to the latest one.
+#[derive(Component)]
+struct Replica {
+    id: u64,
+    from: Isometry2d,
+    to: Isometry2d,
+    progress: f32,
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn server_transport(port: u16) -> Result<NetcodeServerTransport, Box<dyn Error>> {
+    let address = SocketAddr::from(([0, 0, 0, 0], port));
+    let socket = UdpSocket::bind(address)?;
+    let config = ServerConfig {
+        current_time: now(),
+        max_clients: MAX_CLIENTS,
+        protocol_id: PROTOCOL_ID,
+        public_addresses: vec![address],
+        authentication: ServerAuthentication::Unsecure,
+    };
+    Ok(NetcodeServerTransport::new(config, socket)?)
+}
+
+pub fn client_transport(
+    server: SocketAddr,
+    spectator: bool,
+) -> Result<NetcodeClientTransport, Box<dyn Error>> {
+    let socket = UdpSocket::bind(SocketAddr::from(([0, 0, 0, 0], 0)))?;
+    let current_time = now();
+    let mut user_data = [0; NETCODE_USER_DATA_BYTES];
+    if spectator {
+        user_data[0] = SPECTATOR_FLAG;
+    }
+    let authentication = ClientAuthentication::Unsecure {
+        protocol_id: PROTOCOL_ID,
+        client_id: current_time.as_millis() as u64,
+        server_addr: server,
+        user_data: Some(user_data),
+    };
+    Ok(NetcodeClientTransport::new(
+        current_time,
+        authentication,
+        socket,
+    )?)
+}
+
+/// Whether the client connected with [`client_transport`] as a spectator.
+pub fn is_spectator_client(transport: &NetcodeServerTransport, client_id: ClientId) -> bool {
+    transport
+        .user_data(client_id)
+        .is_some_and(|user_data| user_data[0] == SPECTATOR_FLAG)
+}
+
+pub fn encode(message: &impl Serialize) -> Vec<u8> {
+    bincode::serialize(message).expect("network messages are serializable")
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Option<T> {
+    bincode::deserialize(bytes)
+        .inspect_err(|err| warn!("Ignoring invalid network message: {err}"))
+        .ok()
+}
+
+fn handle_server_events(mut events: EventReader<ServerEvent>, mut players: ResMut<RemotePlayers>) {
+    for event in events.read() {
+        match event {
+            ServerEvent::ClientConnected { client_id } => {
+                info!("Client {client_id} joined");
+                players.0.insert(*client_id, Vec2::ZERO);
+            }
+            ServerEvent::ClientDisconnected { client_id, reason } => {
+                info!("Client {client_id} left: {reason}");
+                players.0.remove(client_id);
+            }
+        }
+    }
+}
+
+fn receive_client_messages(
+    mut server: ResMut<RenetServer>,
+    mut players: ResMut<RemotePlayers>,
+    roster: Res<Roster>,
+    mut requests: EventWriter<SpawnRequest>,
+) {
+    for (client_id, direction) in players.0.iter_mut() {
+        let Some(faction) = roster.faction_of(*client_id) else {
+            continue;
+        };
+        for channel in [DefaultChannel::ReliableOrdered, DefaultChannel::Unreliable] {
+            while let Some(bytes) = server.receive_message(*client_id, channel) {
+                match decode(&bytes) {
+                    // Clients only spawn ships of their own faction
+                    Some(ClientMessage::Spawn(request)) => {
+                        requests.send(SpawnRequest { faction, ..request });
+                    }
+                    Some(ClientMessage::Move(input)) => *direction = input,
+                    None => {}
+                }
+            }
+        }
+    }
+}
+
+fn move_remote_fleets(
+    players: Res<RemotePlayers>,
+    roster: Res<Roster>,
+    mut spaceships: FleetTransforms,
+    time: Res<Time>,
+) {
+    for (client_id, direction) in players.0.iter() {
+        if let Some(faction) = roster.faction_of(*client_id) {
+            move_fleet(&mut spaceships, faction, *direction, &time);
+        }
+    }
+}
+
+fn broadcast_state(
+    mut server: ResMut<RenetServer>,
+    spaceships: Query<(Entity, &Faction, &ShipClass, &Transform), With<Spaceship>>,
+) {
+    let states = spaceships
+        .iter()
+        .map(|(entity, faction, class, transform)| ShipState {
+            id: entity.to_bits(),
+            faction: *faction,
+            class: class.clone(),
+            translation: transform.translation.truncate(),
+            rotation: transform.rotation.to_euler(EulerRot::ZYX).0,
+        })
+        .collect();
+    server.broadcast_message(
+        DefaultChannel::Unreliable,
+        encode(&ServerMessage::State(states)),
+    );
+}
+
+fn send_inputs(
+    mut client: ResMut<RenetClient>,
+    mut requests: EventReader<SpawnRequest>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    for request in requests.read() {
+        client.send_message(
+            DefaultChannel::ReliableOrdered,
+            encode(&ClientMessage::Spawn(request.clone())),
+        );
+    }
+    client.send_message(
+        DefaultChannel::Unreliable,
+        encode(&ClientMessage::Move(arrows_direction(&keyboard))),
+    );
+}
+
+fn receive_state(
+    mut commands: Commands,
+    mut client: ResMut<RenetClient>,
+    mut replicas: Query<(Entity, &mut Replica)>,
+) {
+    // Only the latest state matters
+    let mut latest = None;
+    while let Some(bytes) = client.receive_message(DefaultChannel::Unreliable) {
+        if let Some(ServerMessage::State(states)) = decode(&bytes) {
+            latest = Some(states);
+        }
+    }
+    let Some(states) = latest else {
+        return;
+    };
+
+    let mut existing: HashMap<u64, Entity> = replicas
+        .iter()
+        .map(|(entity, replica)| (replica.id, entity))
+        .collect();
+
+    for state in states {
+        let to = Isometry2d::new(state.translation, Rot2::radians(state.rotation));
+        match existing.remove(&state.id) {
+            Some(entity) => {
+                let Ok((_, mut replica)) = replicas.get_mut(entity) else {
+                    continue;
+                };
+                replica.from = replica.current();
+                replica.to = to;
+                replica.progress = 0.;
+            }
+            None => {
+                // Moved by the host only, the physics just follow the transform
+                commands
+                    .spawn(spaceship_bundle(
+                        state.faction,
+                        state.class,
+                        state.translation.x,
+                        state.translation.y,
+                    ))
+                    .insert((
+                        Replica {
+                            id: state.id,
+                            from: to,
+                            to,
+                            progress: 1.,
+                        },
+                        RigidBody::KinematicPositionBased,
+                    ))
+                    .remove::<TransformInterpolation>();
+            }
+        }
+    }
+
+    // Despawned on the host
+    for entity in existing.into_values() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+impl Replica {
+    fn current(&self) -> Isometry2d {
+        Isometry2d::new(
+            self.from
+                .translation
+                .lerp(self.to.translation, self.progress),
+            self.from.rotation.slerp(self.to.rotation, self.progress),
+        )
+    }
+}
+
+fn interpolate_replicas(mut replicas: Query<(&mut Replica, &mut Transform)>, time: Res<Time>) {
+    for (mut replica, mut transform) in replicas.iter_mut() {
+        replica.progress =
+            (replica.progress + time.delta_secs() / STATE_PERIOD.as_secs_f32()).min(1.);
+        let current = replica.current();
+        transform.translation = current.translation.extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(current.rotation.as_radians());
+    }
+}
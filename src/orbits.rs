@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{gravity::GravityWell, SimulationSet};
+
+const STATION_COLOR: Color = Color::srgb(0.7, 0.7, 0.75);
+
+/// Moons and stations of the `satellites` of the planets, circling them in
+/// stable orbits: their angular speed comes from the mass of the
+/// [`GravityWell`] of their planet when spawned.
+///
+/// Satellites are kinematic bodies moved along their orbit, they aren't
+/// attracted by the wells but block the shots and push the solid ships.
+pub struct OrbitsPlugin;
+
+impl Plugin for OrbitsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            move_satellites
+                .in_set(SimulationSet)
+                // Only depends on the time, also moved on the clients
+                .run_if(any_with_component::<Orbit>),
+        );
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SatelliteDefinition {
+    #[serde(default)]
+    pub kind: SatelliteKind,
+    /// Distance from the center of the planet.
+    pub distance: f32,
+    pub radius: f32,
+    /// Initial angle on the orbit, in degrees.
+    #[serde(default)]
+    pub angle: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SatelliteKind {
+    #[default]
+    Moon,
+    Station,
+}
+
+/// Circular orbit around `center`, counterclockwise.
+#[derive(Component, Debug)]
+pub struct Orbit {
+    pub center: Vec2,
+    pub distance: f32,
+    /// Radians per second.
+    pub angular_speed: f32,
+    pub angle: f32,
+}
+
+impl Orbit {
+    /// Stable orbit around the `well` at `center`, its attraction keeping the
+    /// satellite on the circle.
+    pub fn around(center: Vec2, well: &GravityWell, distance: f32, angle: f32) -> Self {
+        let acceleration = well.acceleration(Vec2::X * distance).length();
+        Self {
+            center,
+            distance,
+            // Centripetal acceleration of v² / r with v = ω * r
+            angular_speed: (acceleration / distance).sqrt(),
+            angle,
+        }
+    }
+
+    pub fn position(&self) -> Vec2 {
+        self.center + Vec2::from_angle(self.angle) * self.distance
+    }
+}
+
+/// Sprite of the stations, the moons are drawn like the planets.
+pub fn station_sprite(radius: f32) -> Sprite {
+    Sprite::from_color(STATION_COLOR, Vec2::splat(radius * 2.))
+}
+
+fn move_satellites(mut satellites: Query<(&mut Orbit, &mut Transform)>, time: Res<Time>) {
+    for (mut orbit, mut transform) in satellites.iter_mut() {
+        orbit.angle += orbit.angular_speed * time.delta_secs();
+        let position = orbit.position();
+        transform.translation = position.extend(transform.translation.z);
+        // The stations keep the same side toward the planet
+        transform.rotation = Quat::from_rotation_z(orbit.angle);
+    }
+}
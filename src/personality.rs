@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{config::Configuration, rng::GameRng, Spaceship};
+
+/// Largest aim error in radians of the least accurate ships.
+const MAX_AIM_SPREAD: f32 = 0.15;
+
+/// Gives each ship a random [`Personality`] as it spawns, within the
+/// `personality_spread` of the [`Configuration`], so that the ships of a
+/// fleet don't all behave the same.
+///
+/// Unlike the [`crate::factions::Personality`] of a faction, it only tunes
+/// the behavior of the ship: how far it engages and how soon it flees in
+/// [`crate::behavior`], how much it steers around the obstacles in
+/// [`crate::apply_forces`], and how well it aims in
+/// [`crate::update_targets`].
+pub struct PersonalityPlugin;
+
+impl Plugin for PersonalityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_observer(randomize_personality);
+    }
+}
+
+/// Factors around 1 of the behavior of a ship.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Personality {
+    /// Factor of the `engage_distance`.
+    pub aggression: f32,
+    /// Factor of the `flee_health_threshold` and of the avoidance of the
+    /// obstacles.
+    pub caution: f32,
+    /// From 0 (aiming off by up to [`MAX_AIM_SPREAD`]) to 1 (on target).
+    pub accuracy: f32,
+}
+
+impl Default for Personality {
+    fn default() -> Self {
+        Self {
+            aggression: 1.,
+            caution: 1.,
+            accuracy: 1.,
+        }
+    }
+}
+
+impl Personality {
+    /// Maximum angle in radians between the aim and the enemy.
+    pub fn aim_spread(&self) -> f32 {
+        (1. - self.accuracy).clamp(0., 1.) * MAX_AIM_SPREAD
+    }
+}
+
+fn randomize_personality(
+    trigger: Trigger<OnAdd, Spaceship>,
+    mut commands: Commands,
+    mut rng: ResMut<GameRng>,
+    configs: Res<Configuration>,
+) {
+    let spread = configs.personality_spread.clamp(0., 1.);
+    if spread == 0. {
+        return;
+    }
+    commands.entity(trigger.entity()).insert(Personality {
+        aggression: rng.gen_range(1. - spread..=1. + spread),
+        caution: rng.gen_range(1. - spread..=1. + spread),
+        accuracy: rng.gen_range(1. - spread..=1.),
+    });
+}
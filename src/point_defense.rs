@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+
+use crate::{
+    config::ShipStats,
+    launch,
+    missile::Missile,
+    pool::{EntityPool, Pooled},
+    sound::Impact,
+    weapons::{self, Projectile},
+    Faction, SimulationSet, Spaceship,
+};
+
+/// Ships with a `point_defense_range` in their [`ShipStats`] shoot down the
+/// closest enemy munition within it every `point_defense_cooldown` seconds,
+/// missiles first as they hurt more than the bolts.
+pub struct PointDefensePlugin;
+
+impl Plugin for PointDefensePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            point_defense
+                // Once the munitions of the step are fired, and before they
+                // hit or expire, so that they are released only once
+                .after(weapons::fire_weapons)
+                .before(weapons::handle_projectile_hits)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+#[derive(Component, Default)]
+pub struct PointDefense {
+    /// Seconds until the next interception.
+    pub cooldown_secs: f32,
+}
+
+fn point_defense(
+    mut commands: Commands,
+    mut ships: Query<(&Faction, &Transform, &ShipStats, &mut PointDefense), With<Spaceship>>,
+    missiles: Query<(Entity, &Missile, &Transform)>,
+    mut projectiles: Query<(Entity, &Projectile, &mut Pooled, &Transform)>,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    time: Res<Time>,
+    mut impacts: EventWriter<Impact>,
+) {
+    let missiles: Vec<_> = missiles
+        .iter()
+        .map(|(entity, missile, transform)| {
+            (entity, missile.faction, transform.translation.truncate())
+        })
+        .collect();
+    let bolts: Vec<_> = projectiles
+        .iter()
+        .filter(|(_, _, pooled, _)| pooled.active)
+        .map(|(entity, projectile, _, transform)| {
+            (entity, projectile.faction, transform.translation.truncate())
+        })
+        .collect();
+
+    let mut intercepted = vec![];
+    for (faction, transform, stats, mut defense) in ships.iter_mut() {
+        defense.cooldown_secs = (defense.cooldown_secs - time.delta_secs()).max(0.);
+        if stats.point_defense_range <= 0. || defense.cooldown_secs > 0. {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let closest = |munitions: &[(Entity, Faction, Vec2)]| {
+            closest_enemy(
+                munitions,
+                *faction,
+                position,
+                stats.point_defense_range,
+                &intercepted,
+            )
+        };
+        let target = closest(&missiles)
+            .map(|missile| (missile, true))
+            .or_else(|| closest(&bolts).map(|bolt| (bolt, false)));
+        let Some(((entity, munition_position), is_missile)) = target else {
+            continue;
+        };
+
+        if is_missile {
+            commands.entity(entity).despawn_recursive();
+        } else if let Ok((.., mut pooled, _)) = projectiles.get_mut(entity) {
+            pool.release(&mut commands, entity, &mut pooled);
+        }
+        intercepted.push(entity);
+        defense.cooldown_secs = stats.point_defense_cooldown;
+        impacts.send(Impact {
+            position: munition_position,
+        });
+    }
+}
+
+/// Closest munition of another faction within `range` of `position`.
+fn closest_enemy(
+    munitions: &[(Entity, Faction, Vec2)],
+    faction: Faction,
+    position: Vec2,
+    range: f32,
+    intercepted: &[Entity],
+) -> Option<(Entity, Vec2)> {
+    munitions
+        .iter()
+        .filter(|(entity, munition_faction, _)| {
+            *munition_faction != faction && !intercepted.contains(entity)
+        })
+        .map(|(entity, _, munition_position)| (*entity, *munition_position))
+        .filter(|(_, munition_position)| munition_position.distance(position) <= range)
+        .min_by(|(_, a), (_, b)| a.distance(position).total_cmp(&b.distance(position)))
+}
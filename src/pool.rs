@@ -0,0 +1,87 @@
+use std::marker::PhantomData;
+
+use bevy::{ecs::system::EntityCommands, prelude::*};
+
+/// Recycles short-lived entities (projectiles, debris) instead of spawning and
+/// despawning them every frame.
+///
+/// Pooled entities keep all their components for their whole life: releasing one
+/// only flips its [`Pooled`] flag, hides it and lets `on_release` park the rest,
+/// so the archetypes never change.
+#[derive(Resource)]
+pub struct EntityPool<T: Component> {
+    free: Vec<Entity>,
+    spawn: fn(&mut Commands) -> Entity,
+    on_acquire: fn(&mut EntityCommands),
+    on_release: fn(&mut EntityCommands),
+    _marker: PhantomData<T>,
+}
+
+/// Present on every pooled entity, systems should skip inactive ones.
+#[derive(Component, Default)]
+pub struct Pooled {
+    pub active: bool,
+}
+
+impl<T: Component> EntityPool<T> {
+    /// `spawn` must create an entity with `T`, [`Pooled`] and a [`Visibility`].
+    pub fn new(
+        spawn: fn(&mut Commands) -> Entity,
+        on_acquire: fn(&mut EntityCommands),
+        on_release: fn(&mut EntityCommands),
+    ) -> Self {
+        Self {
+            free: vec![],
+            spawn,
+            on_acquire,
+            on_release,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn prefill(&mut self, commands: &mut Commands, count: usize) {
+        for _ in 0..count {
+            let entity = (self.spawn)(commands);
+            self.park(commands, entity);
+        }
+    }
+
+    /// Reuses a free entity, or spawns a new one when the pool is exhausted.
+    /// Callers then insert the per-use components (position, velocity...).
+    pub fn acquire<'a>(&mut self, commands: &'a mut Commands) -> EntityCommands<'a> {
+        let entity = match self.free.pop() {
+            Some(entity) => entity,
+            None => (self.spawn)(commands),
+        };
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert((Pooled { active: true }, Visibility::Inherited));
+        (self.on_acquire)(&mut entity_commands);
+        entity_commands
+    }
+
+    /// Hands the entity back to the pool, doing nothing if it was already
+    /// released, e.g. by another system within the same step.
+    pub fn release(&mut self, commands: &mut Commands, entity: Entity, pooled: &mut Pooled) {
+        if !pooled.active {
+            return;
+        }
+        pooled.active = false;
+        self.park(commands, entity);
+    }
+
+    fn park(&mut self, commands: &mut Commands, entity: Entity) {
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.insert(Visibility::Hidden);
+        (self.on_release)(&mut entity_commands);
+        self.free.push(entity);
+    }
+}
+
+/// Startup system pre-spawning `count` entities in the pool of `T`.
+pub fn prefill_pool<T: Component, const COUNT: usize>(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<T>>,
+) {
+    pool.prefill(&mut commands, COUNT);
+}
@@ -0,0 +1,8 @@
+/// Opens a span until the end of the current scope, only when the
+/// `profiling` feature is enabled (tracy backend).
+macro_rules! profile_scope {
+    ($name:expr) => {
+        #[cfg(feature = "profiling")]
+        let _span = bevy::log::info_span!($name).entered();
+    };
+}
@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::{ColliderMode, Configuration},
+    damage::{self, DamageEvent},
+    launch, Faction, SimulationSet, Spaceship,
+};
+
+/// Relative speed below which collisions deal no damage, e.g. ships drifting
+/// into each other.
+const MIN_RAMMING_SPEED: f32 = 50.;
+
+/// Applies the `collider_mode` of the [`Configuration`] to the ships: solid
+/// ships bounce off each other and take `ramming_damage` per unit of relative
+/// speed from the collisions.
+pub struct RammingPlugin;
+
+impl Plugin for RammingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, apply_collider_mode).add_systems(
+            FixedUpdate,
+            ram_ships
+                .before(damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+/// Applies the mode to the spawned ships, and to every ship when it changes.
+fn apply_collider_mode(
+    mut commands: Commands,
+    configs: Res<Configuration>,
+    ships: Query<(Entity, Ref<Spaceship>)>,
+) {
+    for (entity, spaceship) in ships.iter() {
+        if !configs.is_changed() && !spaceship.is_added() {
+            continue;
+        }
+        match configs.collider_mode {
+            ColliderMode::Sensor => {
+                commands
+                    .entity(entity)
+                    .insert(Sensor)
+                    .remove::<ActiveEvents>();
+            }
+            ColliderMode::Solid => {
+                commands
+                    .entity(entity)
+                    .remove::<Sensor>()
+                    .insert(ActiveEvents::COLLISION_EVENTS);
+            }
+        }
+    }
+}
+
+fn ram_ships(
+    mut events: EventReader<CollisionEvent>,
+    ships: Query<(&Faction, &Velocity), (With<Spaceship>, Without<Sensor>)>,
+    configs: Res<Configuration>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for event in events.read() {
+        let CollisionEvent::Started(first, second, _) = event else {
+            continue;
+        };
+        let (Ok((first_faction, first_velocity)), Ok((second_faction, second_velocity))) =
+            (ships.get(*first), ships.get(*second))
+        else {
+            continue;
+        };
+        let speed = first_velocity.linvel.distance(second_velocity.linvel);
+        if speed < MIN_RAMMING_SPEED {
+            continue;
+        }
+
+        // Friendly collisions hurt too, without a killer
+        let source = |faction: &Faction, other: &Faction| (faction != other).then_some(*other);
+        let amount = speed * configs.ramming_damage;
+        damages.send(DamageEvent {
+            target: *first,
+            amount,
+            source: source(first_faction, second_faction),
+            slingshot: false,
+            direction: Some(second_velocity.linvel - first_velocity.linvel),
+            attacker: (first_faction != second_faction).then_some(*second),
+        });
+        damages.send(DamageEvent {
+            target: *second,
+            amount,
+            source: source(second_faction, first_faction),
+            slingshot: false,
+            direction: Some(first_velocity.linvel - second_velocity.linvel),
+            attacker: (first_faction != second_faction).then_some(*first),
+        });
+    }
+}
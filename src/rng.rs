@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+use rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+/// Single source of randomness of the game, so a seed reproduces a whole match.
+///
+/// Systems needing randomness take `ResMut<GameRng>` and use it through [`rand::Rng`].
+/// Its state is serializable so snapshots resume the exact same random sequence.
+#[derive(Resource, Serialize, Deserialize, Clone)]
+pub struct GameRng {
+    rng: Xoshiro256PlusPlus,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        info!("Game seed: {seed}");
+        Self {
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+        }
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
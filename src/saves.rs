@@ -0,0 +1,447 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, time::SystemTime};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    launch::{self, LaunchOptions},
+    localization::Localization,
+    settings::UserSettings,
+    share_code::ShareCode,
+    snapshot::{SnapshotWorld, WorldSnapshot},
+    GameState,
+};
+
+/// Slots listed in the load menu, one per digit key.
+const MAX_LISTED_SLOTS: usize = 9;
+const DIGITS: [KeyCode; MAX_LISTED_SLOTS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// Game menu opened with `F10` by default, pausing the game, with `Save Game`
+/// and `Load Game` entries. Games are saved as [`WorldSnapshot`]s in named
+/// slots of `~/.local/share/gravity-war/saves` (or the platform equivalent).
+/// Games without network also have a `Play Share Code` entry.
+///
+/// Only available to the authoritative simulation, outside of lockstep.
+pub struct SavesPlugin;
+
+impl Plugin for SavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_menu,
+                (navigate_menu, update_menu_text)
+                    .chain()
+                    .run_if(resource_exists::<GameMenu>),
+            )
+                .chain()
+                .run_if(
+                    in_state(GameState::InGame)
+                        .and(launch::is_authoritative)
+                        .and(not(launch::is_lockstep)),
+                ),
+        )
+        .add_systems(OnExit(GameState::InGame), close_menu);
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveGame {
+    metadata: SaveMetadata,
+    snapshot: WorldSnapshot,
+}
+
+/// Shown in the load menu.
+#[derive(Serialize, Deserialize, Clone)]
+struct SaveMetadata {
+    /// Path of the scenario, `None` in sandbox games.
+    scenario: Option<String>,
+    elapsed_secs: f32,
+    /// Number of ships of each faction.
+    fleets: BTreeMap<u32, u32>,
+}
+
+/// Exists while the menu is open.
+#[derive(Resource)]
+struct GameMenu {
+    page: MenuPage,
+}
+
+enum MenuPage {
+    Main,
+    /// Name of the slot being typed.
+    Save(String),
+    Load(Vec<SaveSlot>),
+    /// Share code being typed, and why the last one was rejected.
+    ShareCode {
+        code: String,
+        error: Option<String>,
+    },
+}
+
+struct SaveSlot {
+    name: String,
+    metadata: SaveMetadata,
+}
+
+#[derive(Component)]
+struct MenuText;
+
+/// `None` on platforms without a data directory.
+fn saves_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("gravity-war").join("saves"))
+}
+
+fn slot_path(name: &str) -> Option<PathBuf> {
+    saves_dir().map(|dir| dir.join(format!("{name}.ron")))
+}
+
+/// Slot names are file names, restricted to a safe subset.
+fn is_slot_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ' '
+}
+
+fn is_code_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "-_/:.".contains(c)
+}
+
+fn write_slot(name: &str, save: &SaveGame) -> Result<(), String> {
+    let (Some(dir), Some(path)) = (saves_dir(), slot_path(name)) else {
+        return Err("no data directory".to_string());
+    };
+    let text = ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())?;
+    fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+    fs::write(&path, text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn read_slot(name: &str) -> Result<SaveGame, String> {
+    let path = slot_path(name).ok_or("no data directory")?;
+    let text = fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+    ron::from_str(&text).map_err(|err| format!("{}: {err}", path.display()))
+}
+
+/// Valid slots, the most recent first.
+fn list_slots() -> Vec<SaveSlot> {
+    let Some(Ok(entries)) = saves_dir().map(fs::read_dir) else {
+        return vec![];
+    };
+    let mut slots: Vec<(SystemTime, SaveSlot)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "ron" {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let modified = entry.metadata().and_then(|m| m.modified()).ok()?;
+            let save = read_slot(&name)
+                .inspect_err(|err| warn!("Ignoring invalid save {err}"))
+                .ok()?;
+            Some((
+                modified,
+                SaveSlot {
+                    name,
+                    metadata: save.metadata,
+                },
+            ))
+        })
+        .collect();
+    slots.sort_by(|(a, _), (b, _)| b.cmp(a));
+    slots.into_iter().map(|(_, slot)| slot).collect()
+}
+
+fn toggle_menu(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    menu: Option<Res<GameMenu>>,
+    texts: Query<Entity, With<MenuText>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !keyboard.just_pressed(settings.input.game_menu) {
+        return;
+    }
+
+    if menu.is_some() {
+        close_menu(commands, texts, time);
+    } else {
+        commands.insert_resource(GameMenu {
+            page: MenuPage::Main,
+        });
+        commands.spawn((
+            MenuText,
+            Text::default(),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                left: Val::Px(12.),
+                ..default()
+            },
+        ));
+        time.pause();
+    }
+}
+
+fn close_menu(
+    mut commands: Commands,
+    texts: Query<Entity, With<MenuText>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    commands.remove_resource::<GameMenu>();
+    for text in texts.iter() {
+        commands.entity(text).despawn_recursive();
+    }
+    time.unpause();
+}
+
+/// Keys pressed and text typed in the menu.
+#[derive(SystemParam)]
+struct MenuInput<'w, 's> {
+    keyboard: Res<'w, ButtonInput<KeyCode>>,
+    typed: EventReader<'w, 's, KeyboardInput>,
+}
+
+/// Options of the game, relaunched by the share codes.
+#[derive(SystemParam)]
+struct Relaunch<'w> {
+    options: ResMut<'w, LaunchOptions>,
+    next_state: ResMut<'w, NextState<GameState>>,
+}
+
+fn navigate_menu(
+    mut commands: Commands,
+    mut menu: ResMut<GameMenu>,
+    input: MenuInput,
+    relaunch: Relaunch,
+    mut world: SnapshotWorld,
+    texts: Query<Entity, With<MenuText>>,
+    virtual_time: ResMut<Time<Virtual>>,
+) {
+    let MenuInput {
+        keyboard,
+        mut typed,
+    } = input;
+    let Relaunch {
+        mut options,
+        mut next_state,
+    } = relaunch;
+    let mut close = false;
+    match &mut menu.page {
+        MenuPage::Main => {
+            if keyboard.just_pressed(KeyCode::Digit1) {
+                menu.page = MenuPage::Save(String::new());
+            } else if keyboard.just_pressed(KeyCode::Digit2) {
+                menu.page = MenuPage::Load(list_slots());
+            } else if keyboard.just_pressed(KeyCode::Digit3) && options.network.is_none() {
+                menu.page = MenuPage::ShareCode {
+                    code: String::new(),
+                    error: None,
+                };
+            }
+            // The digit must not be typed in the slot name
+            typed.clear();
+        }
+        MenuPage::Save(name) => {
+            for event in typed.read().filter(|event| event.state.is_pressed()) {
+                match &event.logical_key {
+                    Key::Character(text) => name.extend(text.chars().filter(|c| is_slot_char(*c))),
+                    Key::Space => name.push(' '),
+                    Key::Backspace => {
+                        name.pop();
+                    }
+                    _ => {}
+                }
+            }
+            let name = name.trim();
+            if keyboard.just_pressed(KeyCode::Enter) && !name.is_empty() {
+                let snapshot = WorldSnapshot::capture(&world);
+                let mut fleets = BTreeMap::new();
+                for ship in &snapshot.ships {
+                    *fleets.entry(ship.faction.0).or_default() += 1;
+                }
+                let save = SaveGame {
+                    metadata: SaveMetadata {
+                        scenario: options
+                            .scenario
+                            .as_ref()
+                            .map(|path| path.to_string_lossy().into_owned()),
+                        elapsed_secs: virtual_time.elapsed_secs(),
+                        fleets,
+                    },
+                    snapshot,
+                };
+                match write_slot(name, &save) {
+                    Ok(()) => info!("Saved the game to slot {name:?}"),
+                    Err(err) => error!("Failed to save the game: {err}"),
+                }
+                close = true;
+            }
+        }
+        MenuPage::Load(slots) => {
+            if let Some(slot) = DIGITS
+                .iter()
+                .position(|digit| keyboard.just_pressed(*digit))
+                .and_then(|index| slots.get(index))
+            {
+                match read_slot(&slot.name) {
+                    Ok(save) => {
+                        let current_scenario = options
+                            .scenario
+                            .as_ref()
+                            .map(|path| path.to_string_lossy().into_owned());
+                        if save.metadata.scenario != current_scenario {
+                            warn!(
+                                "Only the ships, bolts and planets of a save from another \
+                                 scenario are restored"
+                            );
+                        }
+                        save.snapshot.restore(&mut commands, &mut world);
+                        info!("Loaded the game of slot {:?}", slot.name);
+                    }
+                    Err(err) => error!("Failed to load the game: {err}"),
+                }
+                close = true;
+            }
+        }
+        MenuPage::ShareCode { code, error } => {
+            for event in typed.read().filter(|event| event.state.is_pressed()) {
+                match &event.logical_key {
+                    Key::Character(text) => code.extend(text.chars().filter(|c| is_code_char(*c))),
+                    Key::Backspace => {
+                        code.pop();
+                    }
+                    _ => {}
+                }
+            }
+            if keyboard.just_pressed(KeyCode::Enter) && !code.is_empty() {
+                match code.parse::<ShareCode>() {
+                    Ok(share_code) => {
+                        share_code.apply(
+                            &mut commands,
+                            &mut options,
+                            &mut world.rng,
+                            &mut next_state,
+                        );
+                        close = true;
+                    }
+                    Err(err) => *error = Some(err),
+                }
+            }
+        }
+    }
+
+    if close {
+        close_menu(commands, texts, virtual_time);
+    }
+}
+
+fn update_menu_text(
+    mut texts: Query<&mut Text, With<MenuText>>,
+    menu: Res<GameMenu>,
+    settings: Res<UserSettings>,
+    options: Res<LaunchOptions>,
+    localization: Res<Localization>,
+) {
+    let Ok(mut text) = texts.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = match &menu.page {
+        MenuPage::Main => {
+            let mut lines = vec![
+                localization.text("menu-paused"),
+                localization.text("menu-save-entry"),
+                localization.text("menu-load-entry"),
+            ];
+            if options.network.is_none() {
+                lines.push(localization.text("menu-code-entry"));
+            }
+            lines
+        }
+        MenuPage::Save(name) => vec![
+            localization.text("menu-save-title"),
+            localization.format("menu-slot-name", &[("name", name.as_str().into())]),
+            localization.text("menu-save-confirm"),
+        ],
+        MenuPage::Load(slots) if slots.is_empty() => {
+            vec![
+                localization.text("menu-load-title"),
+                localization.text("menu-no-save"),
+            ]
+        }
+        MenuPage::Load(slots) => {
+            let mut lines = vec![localization.text("menu-load-prompt")];
+            lines.extend(
+                slots
+                    .iter()
+                    .take(MAX_LISTED_SLOTS)
+                    .enumerate()
+                    .map(|(index, slot)| describe(index + 1, slot, &localization)),
+            );
+            lines
+        }
+        MenuPage::ShareCode { code, error } => {
+            let mut lines = vec![
+                localization.text("menu-code-title"),
+                localization.format("menu-code", &[("code", code.as_str().into())]),
+            ];
+            if let Some(error) = error {
+                lines.push(
+                    localization.format("menu-code-invalid", &[("error", error.as_str().into())]),
+                );
+            }
+            lines.push(localization.text("menu-code-confirm"));
+            lines
+        }
+    };
+    lines.push(localization.format(
+        "menu-close",
+        &[("key", format!("{:?}", settings.input.game_menu).into())],
+    ));
+    text.0 = lines.join("\n");
+}
+
+fn describe(number: usize, slot: &SaveSlot, localization: &Localization) -> String {
+    let metadata = &slot.metadata;
+    let scenario = match &metadata.scenario {
+        Some(scenario) => scenario.clone(),
+        None => localization.text("menu-sandbox"),
+    };
+    let secs = metadata.elapsed_secs as u32;
+    let fleets = metadata
+        .fleets
+        .iter()
+        .map(|(faction, count)| {
+            localization.format(
+                "menu-fleet",
+                &[("count", (*count).into()), ("faction", (*faction).into())],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    localization.format(
+        "menu-slot",
+        &[
+            ("number", number.into()),
+            ("name", slot.name.as_str().into()),
+            ("scenario", scenario.into()),
+            ("time", format!("{}:{:02}", secs / 60, secs % 60).into()),
+            ("fleets", fleets.into()),
+        ],
+    )
+}
@@ -0,0 +1,571 @@
+use std::f32::consts::TAU;
+
+use bevy::{
+    asset::LoadState,
+    color::palettes::css,
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    asteroids::{spawn_belt, AsteroidArt},
+    behavior::PatrolRoute,
+    black_hole::BlackHoleDefinition,
+    comets::{spawn_comet, CometDefinition},
+    docking::Dock,
+    factions::{DefaultFactionsHandle, FactionDefinition, Factions},
+    game_assets::{loaded_texture, GameAssets},
+    gravity::GravityWell,
+    hazards::HazardDefinition,
+    launch::LaunchOptions,
+    localization::LocalizedText,
+    nebula::NebulaDefinition,
+    orbits::{station_sprite, Orbit, SatelliteDefinition, SatelliteKind},
+    rng::GameRng,
+    ron_asset::RonAssetPlugin,
+    spaceship_bundle,
+    star_system::StarSystem,
+    stations::StationDefinition,
+    wormholes::WormholePair,
+    Faction, GameState, ShipClass, Spaceship,
+};
+
+/// Scenarios are `.scenario.ron` assets given with the `--scenario` option.
+///
+/// The scenario is instantiated when entering [`GameState::InGame`], and its
+/// entities are despawned when leaving it. Editing the file restarts the game.
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<Scenario>::new(&["scenario.ron"]))
+            .add_event::<ScenarioEnded>()
+            .add_event::<TriggerAction>()
+            .init_resource::<Waves>()
+            .add_systems(Startup, create_planet_material)
+            // Networked games only know their scenario once launched from the lobby
+            .add_systems(OnEnter(GameState::Loading), load_scenario)
+            .add_systems(Update, start_game.run_if(in_state(GameState::Loading)))
+            .add_systems(
+                OnEnter(GameState::InGame),
+                (instantiate_scenario, spawn_planets),
+            )
+            .add_systems(OnExit(GameState::InGame), despawn_scenario)
+            .add_systems(
+                Update,
+                (
+                    run_event_script,
+                    run_triggered_actions.run_if(on_event::<TriggerAction>),
+                    check_win_condition.run_if(not(resource_exists::<ScenarioEnd>)),
+                    announce_end.run_if(on_event::<ScenarioEnded>),
+                    restart_on_change.run_if(on_event::<AssetEvent<Scenario>>),
+                )
+                    .run_if(in_state(GameState::InGame).and(resource_exists::<ScenarioHandle>)),
+            );
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct Scenario {
+    pub name: String,
+    #[serde(default)]
+    pub planets: Vec<PlanetDefinition>,
+    #[serde(default)]
+    pub black_holes: Vec<BlackHoleDefinition>,
+    #[serde(default)]
+    pub wormholes: Vec<WormholePair>,
+    #[serde(default)]
+    pub nebulae: Vec<NebulaDefinition>,
+    #[serde(default)]
+    pub stations: Vec<StationDefinition>,
+    #[serde(default)]
+    pub comets: Vec<CometDefinition>,
+    #[serde(default)]
+    pub hazards: Vec<HazardDefinition>,
+    #[serde(default)]
+    pub asteroid_belts: Vec<AsteroidBelt>,
+    #[serde(default)]
+    pub spawn_zones: Vec<SpawnZone>,
+    #[serde(default)]
+    pub fleets: Vec<Fleet>,
+    #[serde(default)]
+    pub win_condition: WinCondition,
+    /// Events triggered over time, in any order.
+    #[serde(default)]
+    pub events: Vec<ScriptedEvent>,
+    /// Added to the `factions.ron` definitions, or replacing them.
+    #[serde(default)]
+    pub factions: Vec<FactionDefinition>,
+    /// Rhai scripts run by the [`crate::scripting::ScriptingPlugin`].
+    #[serde(default)]
+    pub scripts: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PlanetDefinition {
+    pub position: Vec2,
+    pub radius: f32,
+    /// Mass of the [`GravityWell`], the square of the radius by default.
+    #[serde(default)]
+    pub mass: Option<f32>,
+    /// Moons and stations orbiting the planet.
+    #[serde(default)]
+    pub satellites: Vec<SatelliteDefinition>,
+}
+
+/// Ring of asteroids of random sizes around a center.
+#[derive(Deserialize, Debug)]
+pub struct AsteroidBelt {
+    pub center: Vec2,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub count: u32,
+    pub min_size: f32,
+    pub max_size: f32,
+}
+
+/// Disk in which the ships of a [`Fleet`] are spread.
+#[derive(Deserialize, Debug)]
+pub struct SpawnZone {
+    pub name: String,
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Fleet {
+    pub faction: Faction,
+    #[serde(default)]
+    pub class: ShipClass,
+    pub count: u32,
+    /// Name of the [`SpawnZone`].
+    pub zone: String,
+    /// Waypoints cycled through by the ships while no enemy is around.
+    #[serde(default)]
+    pub patrol: Vec<Vec2>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy)]
+pub enum WinCondition {
+    /// The game ends when a single faction has ships left.
+    #[default]
+    LastFactionStanding,
+    /// The faction wins if it still has ships after the given duration.
+    Survive { faction: Faction, secs: f32 },
+    /// Every other faction wins once this one has no ship left.
+    Eliminate(Faction),
+    /// Sandbox without end.
+    None,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScriptedEvent {
+    /// Seconds since the start of the scenario.
+    pub at_secs: f32,
+    pub action: ScenarioAction,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub enum ScenarioAction {
+    SpawnFleet(Fleet),
+    SpawnComet(CometDefinition),
+    Message(String),
+}
+
+/// Sent once when the [`WinCondition`] is met.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct ScenarioEnded {
+    /// `None` when there is no single winner.
+    pub winner: Option<Faction>,
+    /// Seconds since the start of the scenario.
+    pub elapsed_secs: f32,
+}
+
+/// Runs an action of the current scenario right away, e.g. from the control API.
+#[derive(Event, Clone, Debug)]
+pub struct TriggerAction(pub ScenarioAction);
+
+#[derive(Resource)]
+pub struct ScenarioHandle(pub Handle<Scenario>);
+
+/// Progress of the event script, reset on each instantiation.
+#[derive(Resource, Default)]
+struct ScenarioClock {
+    elapsed_secs: f32,
+    /// Number of events already triggered, in chronological order.
+    triggered: usize,
+}
+
+/// Number of fleets spawned after the start, by events and scripts.
+#[derive(Resource, Default)]
+pub struct Waves(pub u32);
+
+/// Marks the end of the scenario, so the win condition is checked only once.
+#[derive(Resource)]
+struct ScenarioEnd;
+
+/// Despawned when leaving [`GameState::InGame`].
+#[derive(Component)]
+pub struct ScenarioEntity;
+
+#[derive(Component)]
+pub struct Planet;
+
+#[derive(Resource)]
+struct PlanetMaterial(Handle<ColorMaterial>);
+
+/// Resources to draw the planets and their moons.
+#[derive(SystemParam)]
+pub struct PlanetArt<'w> {
+    meshes: ResMut<'w, Assets<Mesh>>,
+    material: Res<'w, PlanetMaterial>,
+    game_assets: Res<'w, GameAssets>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+fn create_planet_material(mut commands: Commands, mut materials: ResMut<Assets<ColorMaterial>>) {
+    commands.insert_resource(PlanetMaterial(
+        materials.add(Color::from(css::SADDLE_BROWN)),
+    ));
+}
+
+fn load_scenario(
+    mut commands: Commands,
+    options: Res<LaunchOptions>,
+    asset_server: Res<AssetServer>,
+) {
+    if let Some(path) = &options.scenario {
+        // Parsed from a string for the `mods://` source
+        let path = path.to_string_lossy().into_owned();
+        commands.insert_resource(ScenarioHandle(asset_server.load(path)));
+    }
+}
+
+/// Waits for the textures, the default factions and the scenario to be loaded,
+/// games without scenario start once the textures and factions are known.
+fn start_game(
+    handle: Option<Res<ScenarioHandle>>,
+    factions: Res<DefaultFactionsHandle>,
+    game_assets: Res<GameAssets>,
+    asset_server: Res<AssetServer>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !game_assets.is_settled(&asset_server) {
+        return;
+    }
+    match asset_server.load_state(&factions.0) {
+        LoadState::Loaded => {}
+        LoadState::Failed(err) => {
+            error!("Failed to load the factions, using fallback ones: {err}");
+        }
+        _ => return,
+    }
+    if let Some(handle) = handle {
+        if asset_server.is_loaded_with_dependencies(&handle.0) {
+            next_state.set(GameState::InGame);
+        } else if let LoadState::Failed(err) = asset_server.load_state(&handle.0) {
+            error!("Failed to load the scenario, starting without it: {err}");
+            next_state.set(GameState::InGame);
+        }
+    } else {
+        next_state.set(GameState::InGame);
+    }
+}
+
+fn instantiate_scenario(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    mut art: AsteroidArt,
+    mut rng: ResMut<GameRng>,
+) {
+    commands.remove_resource::<ScenarioEnd>();
+    let Some(scenario) = handle.and_then(|handle| scenarios.get(&handle.0)) else {
+        return;
+    };
+    info!("Starting scenario {:?}", scenario.name);
+    commands.insert_resource(ScenarioClock::default());
+    commands.insert_resource(Waves::default());
+
+    for belt in &scenario.asteroid_belts {
+        spawn_belt(&mut commands, &mut art, &mut rng, belt);
+    }
+
+    for fleet in &scenario.fleets {
+        spawn_fleet(&mut commands, scenario, fleet, &mut rng);
+    }
+}
+
+/// Spawns the planets of the scenario and their satellites, or the ones of
+/// the generated [`StarSystem`] without scenario.
+pub(crate) fn spawn_planets(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    star_system: Option<Res<StarSystem>>,
+    mut art: PlanetArt,
+) {
+    let planets = match (handle, &star_system) {
+        (Some(handle), _) => match scenarios.get(&handle.0) {
+            Some(scenario) => scenario.planets.as_slice(),
+            None => return,
+        },
+        (None, Some(star_system)) => star_system.planets.as_slice(),
+        (None, None) => return,
+    };
+
+    for planet in planets {
+        let well = GravityWell {
+            mass: planet.mass.unwrap_or(planet.radius.powi(2)),
+            radius: planet.radius,
+        };
+        spawn_planet(&mut commands, &mut art, planet.position, well);
+
+        for satellite in &planet.satellites {
+            let orbit = Orbit::around(
+                planet.position,
+                &well,
+                satellite.distance,
+                satellite.angle.to_radians(),
+            );
+            let mut entity_commands = commands.spawn((
+                ScenarioEntity,
+                Transform::from_translation(orbit.position().extend(-1.)),
+                orbit,
+                RigidBody::KinematicPositionBased,
+            ));
+            match satellite.kind {
+                SatelliteKind::Moon => {
+                    entity_commands.insert(Collider::ball(satellite.radius));
+                    insert_disc(&mut entity_commands, &mut art, satellite.radius);
+                }
+                SatelliteKind::Station => {
+                    entity_commands.insert((
+                        Collider::cuboid(satellite.radius, satellite.radius),
+                        station_sprite(satellite.radius),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Spawns a planet of the radius of its `well`, docking the ships around it.
+pub fn spawn_planet(
+    commands: &mut Commands,
+    art: &mut PlanetArt,
+    position: Vec2,
+    well: GravityWell,
+) {
+    let mut entity_commands = commands.spawn((
+        ScenarioEntity,
+        Planet,
+        Dock::planet(well.radius),
+        well,
+        Transform::from_translation(position.extend(-1.)),
+        RigidBody::Fixed,
+        Collider::ball(well.radius),
+    ));
+    insert_disc(&mut entity_commands, art, well.radius);
+}
+
+/// Sprite of the texture, or a colored circle mesh without it.
+fn insert_disc(entity_commands: &mut EntityCommands, art: &mut PlanetArt, radius: f32) {
+    match loaded_texture(&art.asset_server, &art.game_assets.planet) {
+        Some(image) => entity_commands.insert(Sprite {
+            image,
+            custom_size: Some(Vec2::splat(radius * 2.)),
+            ..default()
+        }),
+        None => entity_commands.insert((
+            Mesh2d(art.meshes.add(Circle::new(radius))),
+            MeshMaterial2d(art.material.0.clone()),
+        )),
+    };
+}
+
+fn spawn_fleet(commands: &mut Commands, scenario: &Scenario, fleet: &Fleet, rng: &mut GameRng) {
+    let Some(zone) = scenario
+        .spawn_zones
+        .iter()
+        .find(|zone| zone.name == fleet.zone)
+    else {
+        error!("Unknown spawn zone {:?} in scenario", fleet.zone);
+        return;
+    };
+
+    for _ in 0..fleet.count {
+        let angle = rng.gen_range(0.0..TAU);
+        let distance = zone.radius * rng.gen_range(0.0f32..1.).sqrt();
+        let position = zone.center + Vec2::from_angle(angle) * distance;
+        let mut ship = commands.spawn((
+            ScenarioEntity,
+            spaceship_bundle(fleet.faction, fleet.class.clone(), position.x, position.y),
+        ));
+        if !fleet.patrol.is_empty() {
+            ship.insert(PatrolRoute::new(fleet.patrol.clone()));
+        }
+    }
+}
+
+fn despawn_scenario(mut commands: Commands, entities: Query<Entity, With<ScenarioEntity>>) {
+    for entity in entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<ScenarioClock>();
+}
+
+fn run_event_script(
+    mut commands: Commands,
+    handle: Res<ScenarioHandle>,
+    scenarios: Res<Assets<Scenario>>,
+    time: Res<Time>,
+    clock: Option<ResMut<ScenarioClock>>,
+    mut rng: ResMut<GameRng>,
+    mut waves: ResMut<Waves>,
+) {
+    let (Some(scenario), Some(mut clock)) = (scenarios.get(&handle.0), clock) else {
+        return;
+    };
+    clock.elapsed_secs += time.delta_secs();
+
+    let mut events: Vec<&ScriptedEvent> = scenario.events.iter().collect();
+    events.sort_by(|a, b| a.at_secs.total_cmp(&b.at_secs));
+
+    for event in &events[clock.triggered.min(events.len())..] {
+        if event.at_secs > clock.elapsed_secs {
+            break;
+        }
+        run_action(&mut commands, scenario, &event.action, &mut rng, &mut waves);
+        clock.triggered += 1;
+    }
+}
+
+fn run_action(
+    commands: &mut Commands,
+    scenario: &Scenario,
+    action: &ScenarioAction,
+    rng: &mut GameRng,
+    waves: &mut Waves,
+) {
+    match action {
+        ScenarioAction::SpawnFleet(fleet) => {
+            spawn_fleet(commands, scenario, fleet, rng);
+            waves.0 += 1;
+        }
+        ScenarioAction::SpawnComet(comet) => spawn_comet(commands, comet),
+        ScenarioAction::Message(message) => info!("{message}"),
+    }
+}
+
+fn run_triggered_actions(
+    mut commands: Commands,
+    mut triggers: EventReader<TriggerAction>,
+    handle: Res<ScenarioHandle>,
+    scenarios: Res<Assets<Scenario>>,
+    mut rng: ResMut<GameRng>,
+    mut waves: ResMut<Waves>,
+) {
+    let Some(scenario) = scenarios.get(&handle.0) else {
+        return;
+    };
+    for TriggerAction(action) in triggers.read() {
+        run_action(&mut commands, scenario, action, &mut rng, &mut waves);
+    }
+}
+
+fn check_win_condition(
+    mut commands: Commands,
+    handle: Res<ScenarioHandle>,
+    scenarios: Res<Assets<Scenario>>,
+    clock: Option<Res<ScenarioClock>>,
+    spaceships: Query<&Faction, With<Spaceship>>,
+    mut ended: EventWriter<ScenarioEnded>,
+) {
+    let (Some(scenario), Some(clock)) = (scenarios.get(&handle.0), clock) else {
+        return;
+    };
+    // Waiting for the fleets of the event script
+    if spaceships.is_empty() {
+        return;
+    }
+
+    let mut factions: Vec<Faction> = spaceships.iter().copied().collect();
+    factions.sort_by_key(|faction| faction.0);
+    factions.dedup();
+
+    let winner = match scenario.win_condition {
+        WinCondition::LastFactionStanding => match factions.as_slice() {
+            [faction] => Some(*faction),
+            _ => return,
+        },
+        WinCondition::Survive { faction, secs } => {
+            if clock.elapsed_secs < secs {
+                return;
+            }
+            factions.contains(&faction).then_some(faction)
+        }
+        WinCondition::Eliminate(faction) => {
+            if factions.contains(&faction) {
+                return;
+            }
+            match factions.as_slice() {
+                [winner] => Some(*winner),
+                _ => None,
+            }
+        }
+        WinCondition::None => return,
+    };
+
+    ended.send(ScenarioEnded {
+        winner,
+        elapsed_secs: clock.elapsed_secs,
+    });
+    commands.insert_resource(ScenarioEnd);
+}
+
+fn announce_end(
+    mut commands: Commands,
+    mut events: EventReader<ScenarioEnded>,
+    factions: Res<Factions>,
+) {
+    for event in events.read() {
+        let text = match event.winner {
+            Some(faction) => {
+                info!("Faction {} wins", faction.0);
+                LocalizedText::new("scenario-winner").with_arg("faction", factions.label(faction))
+            }
+            None => {
+                info!("No winner");
+                LocalizedText::new("scenario-no-winner")
+            }
+        };
+        commands.spawn((
+            ScenarioEntity,
+            text,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(12.),
+                right: Val::Px(12.),
+                ..default()
+            },
+        ));
+    }
+}
+
+/// Restarts the game with the edited scenario.
+fn restart_on_change(
+    mut events: EventReader<AssetEvent<Scenario>>,
+    handle: Res<ScenarioHandle>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    for event in events.read() {
+        if event.is_modified(&handle.0) {
+            info!("Reloading the scenario");
+            next_state.set(GameState::Loading);
+        }
+    }
+}
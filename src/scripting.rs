@@ -0,0 +1,402 @@
+use std::{
+    collections::HashMap,
+    f32::consts::TAU,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    time::common_conditions::on_timer,
+};
+use bevy_rapier2d::prelude::*;
+use rand::Rng;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST, FLOAT, INT};
+
+use crate::{
+    rng::GameRng,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle, Waves},
+    spaceship_bundle,
+    targeting::Marked,
+    Faction, GameState, ShipClass, SimulationSet, Spaceship,
+};
+
+const SCRIPT_PERIOD: Duration = Duration::from_secs(1);
+/// Operations allowed per script call, so a buggy loop can't freeze the game.
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// Radius of the disk in which the ships of a wave are spread.
+const WAVE_RADIUS: f32 = 100.;
+
+/// Rhai scripts listed by the `scripts` of the scenario, for mission events
+/// and custom AI. Every [`SCRIPT_PERIOD`], their `on_tick(elapsed_secs)`
+/// function is called with these bindings:
+///
+/// - `ships()`: array of `#{id, faction, class, x, y, vx, vy}`
+/// - `set_target(id, x, y)` / `clear_target(id)`: override the target of a ship
+/// - `mark(id)` / `unmark(id)`: mark a ship for the focus fire of its enemies
+/// - `spawn_wave(faction, class, count, x, y)`
+/// - `award_points(faction, points)` / `score(faction)`
+/// - `message(text)`
+///
+/// Scripts keep their state in `this`, an object map kept between calls.
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Script>()
+            .register_asset_loader(ScriptLoader)
+            .init_resource::<Score>()
+            .insert_resource(Scripts::new())
+            .add_systems(OnEnter(GameState::InGame), load_scenario_scripts)
+            .add_systems(
+                Update,
+                reload_scripts.run_if(on_event::<AssetEvent<Script>>),
+            )
+            .add_systems(
+                FixedUpdate,
+                run_scripts
+                    .in_set(SimulationSet)
+                    .run_if(in_state(GameState::InGame).and(on_timer(SCRIPT_PERIOD))),
+            );
+    }
+}
+
+#[derive(Asset, TypePath)]
+pub struct Script {
+    source: String,
+}
+
+struct ScriptLoader;
+
+impl AssetLoader for ScriptLoader {
+    type Asset = Script;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Script, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(Script {
+            source: String::from_utf8(bytes)?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+/// Points awarded by the scripts.
+#[derive(Resource, Default)]
+pub struct Score(pub HashMap<Faction, i64>);
+
+/// Target set by a script, replacing the closest enemy.
+#[derive(Component)]
+pub struct ScriptedTarget(pub Vec2);
+
+#[derive(Resource)]
+struct Scripts {
+    engine: Engine,
+    /// Shared with the functions registered in the engine.
+    context: Arc<Mutex<ScriptContext>>,
+    loaded: Vec<LoadedScript>,
+}
+
+struct LoadedScript {
+    handle: Handle<Script>,
+    /// Compiled once the asset is loaded, `None` after an error.
+    ast: Option<AST>,
+    compiled: bool,
+    scope: Scope<'static>,
+    /// Bound to `this` in the calls.
+    state: Dynamic,
+}
+
+/// Data read by the scripts and requests made by them during a call.
+#[derive(Default)]
+struct ScriptContext {
+    ships: Array,
+    score: HashMap<Faction, i64>,
+    targets: Vec<(Entity, Option<Vec2>)>,
+    marks: Vec<(Entity, bool)>,
+    waves: Vec<Wave>,
+    points: Vec<(Faction, i64)>,
+    messages: Vec<String>,
+}
+
+struct Wave {
+    faction: Faction,
+    class: ShipClass,
+    count: u32,
+    position: Vec2,
+}
+
+impl Scripts {
+    fn new() -> Self {
+        let context = Arc::new(Mutex::new(ScriptContext::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let ctx = context.clone();
+        engine.register_fn("ships", move || -> Array {
+            ctx.lock()
+                .map(|context| context.ships.clone())
+                .unwrap_or_default()
+        });
+        let ctx = context.clone();
+        engine.register_fn("score", move |faction: INT| -> INT {
+            ctx.lock()
+                .ok()
+                .and_then(|context| context.score.get(&Faction(faction as u32)).copied())
+                .unwrap_or(0)
+        });
+        let ctx = context.clone();
+        engine.register_fn("set_target", move |id: INT, x: FLOAT, y: FLOAT| {
+            if let Ok(mut context) = ctx.lock() {
+                let target = Vec2::new(x as f32, y as f32);
+                context
+                    .targets
+                    .push((Entity::from_bits(id as u64), Some(target)));
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn("clear_target", move |id: INT| {
+            if let Ok(mut context) = ctx.lock() {
+                context.targets.push((Entity::from_bits(id as u64), None));
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn("mark", move |id: INT| {
+            if let Ok(mut context) = ctx.lock() {
+                context.marks.push((Entity::from_bits(id as u64), true));
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn("unmark", move |id: INT| {
+            if let Ok(mut context) = ctx.lock() {
+                context.marks.push((Entity::from_bits(id as u64), false));
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn(
+            "spawn_wave",
+            move |faction: INT, class: &str, count: INT, x: FLOAT, y: FLOAT| {
+                if let Ok(mut context) = ctx.lock() {
+                    context.waves.push(Wave {
+                        faction: Faction(faction as u32),
+                        class: ShipClass(class.to_string()),
+                        count: count.max(0) as u32,
+                        position: Vec2::new(x as f32, y as f32),
+                    });
+                }
+            },
+        );
+        let ctx = context.clone();
+        engine.register_fn("award_points", move |faction: INT, points: INT| {
+            if let Ok(mut context) = ctx.lock() {
+                context.points.push((Faction(faction as u32), points));
+            }
+        });
+        let ctx = context.clone();
+        engine.register_fn("message", move |text: &str| {
+            if let Ok(mut context) = ctx.lock() {
+                context.messages.push(text.to_string());
+            }
+        });
+
+        Self {
+            engine,
+            context,
+            loaded: vec![],
+        }
+    }
+}
+
+/// Compiles the script and runs its top-level statements.
+fn compile(engine: &Engine, script: &mut LoadedScript, source: &str) {
+    script.compiled = true;
+    script.scope.clear();
+    script.state = Map::new().into();
+    script.ast = match engine.compile(source) {
+        Ok(ast) => match engine.run_ast_with_scope(&mut script.scope, &ast) {
+            Ok(()) => Some(ast),
+            Err(err) => {
+                error!("Script {:?} failed: {err}", script.handle.path());
+                None
+            }
+        },
+        Err(err) => {
+            error!("Script {:?} doesn't compile: {err}", script.handle.path());
+            None
+        }
+    };
+}
+
+fn load_scenario_scripts(
+    mut scripts: ResMut<Scripts>,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    asset_server: Res<AssetServer>,
+) {
+    let paths = handle
+        .and_then(|handle| scenarios.get(&handle.0))
+        .map(|scenario| scenario.scripts.clone())
+        .unwrap_or_default();
+    scripts.loaded = paths
+        .into_iter()
+        .map(|path| LoadedScript {
+            handle: asset_server.load(path),
+            ast: None,
+            compiled: false,
+            scope: Scope::new(),
+            state: Map::new().into(),
+        })
+        .collect();
+}
+
+/// Recompiles the edited scripts on their next run.
+fn reload_scripts(mut events: EventReader<AssetEvent<Script>>, mut scripts: ResMut<Scripts>) {
+    for event in events.read() {
+        if let AssetEvent::Modified { id } = event {
+            for script in scripts.loaded.iter_mut() {
+                if script.handle.id() == *id {
+                    info!("Reloading script {:?}", script.handle.path());
+                    script.compiled = false;
+                }
+            }
+        }
+    }
+}
+
+/// Game state changed by the requests of the scripts.
+#[derive(SystemParam)]
+struct ScriptEffects<'w> {
+    score: ResMut<'w, Score>,
+    rng: ResMut<'w, GameRng>,
+    waves: ResMut<'w, Waves>,
+}
+
+fn run_scripts(
+    mut commands: Commands,
+    mut scripts: ResMut<Scripts>,
+    sources: Res<Assets<Script>>,
+    ships: Query<(Entity, &Faction, &ShipClass, &Transform, &Velocity), With<Spaceship>>,
+    effects: ScriptEffects,
+    time: Res<Time>,
+) {
+    let ScriptEffects {
+        mut score,
+        mut rng,
+        mut waves,
+    } = effects;
+    if scripts.loaded.is_empty() {
+        return;
+    }
+    profile_scope!("run_scripts");
+
+    if let Ok(mut context) = scripts.context.lock() {
+        context.ships = ships
+            .iter()
+            .map(|(entity, faction, class, transform, velocity)| {
+                let mut ship = Map::new();
+                ship.insert("id".into(), Dynamic::from_int(entity.to_bits() as INT));
+                ship.insert("faction".into(), Dynamic::from_int(faction.0 as INT));
+                ship.insert("class".into(), class.0.clone().into());
+                ship.insert(
+                    "x".into(),
+                    Dynamic::from_float(transform.translation.x as FLOAT),
+                );
+                ship.insert(
+                    "y".into(),
+                    Dynamic::from_float(transform.translation.y as FLOAT),
+                );
+                ship.insert("vx".into(), Dynamic::from_float(velocity.linvel.x as FLOAT));
+                ship.insert("vy".into(), Dynamic::from_float(velocity.linvel.y as FLOAT));
+                ship.into()
+            })
+            .collect();
+        context.score = score.0.clone();
+    }
+
+    let Scripts {
+        engine,
+        context,
+        loaded,
+    } = scripts.as_mut();
+    let elapsed_secs = time.elapsed_secs() as FLOAT;
+    for script in loaded.iter_mut() {
+        if !script.compiled {
+            if let Some(source) = sources.get(&script.handle) {
+                compile(engine, script, &source.source);
+            }
+        }
+        let Some(ast) = &script.ast else {
+            continue;
+        };
+        if !ast
+            .iter_functions()
+            .any(|function| function.name == "on_tick")
+        {
+            continue;
+        }
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut script.state);
+        if let Err(err) = engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut script.scope,
+            ast,
+            "on_tick",
+            (elapsed_secs,),
+        ) {
+            error!("Script {:?} failed: {err}", script.handle.path());
+        }
+    }
+
+    let Ok(mut context) = context.lock() else {
+        return;
+    };
+    for (entity, target) in context.targets.drain(..) {
+        // Ignores the ids of despawned ships
+        if ships.contains(entity) {
+            match target {
+                Some(target) => commands.entity(entity).insert(ScriptedTarget(target)),
+                None => commands.entity(entity).remove::<ScriptedTarget>(),
+            };
+        }
+    }
+    for (entity, marked) in context.marks.drain(..) {
+        if ships.contains(entity) {
+            if marked {
+                commands.entity(entity).insert(Marked);
+            } else {
+                commands.entity(entity).remove::<Marked>();
+            }
+        }
+    }
+    for wave in context.waves.drain(..) {
+        waves.0 += 1;
+        for _ in 0..wave.count {
+            let angle = rng.gen_range(0.0..TAU);
+            let distance = WAVE_RADIUS * rng.gen_range(0.0f32..1.).sqrt();
+            let position = wave.position + Vec2::from_angle(angle) * distance;
+            commands.spawn((
+                ScenarioEntity,
+                spaceship_bundle(wave.faction, wave.class.clone(), position.x, position.y),
+            ));
+        }
+    }
+    for (faction, points) in context.points.drain(..) {
+        *score.0.entry(faction).or_default() += points;
+    }
+    for message in context.messages.drain(..) {
+        info!("{message}");
+    }
+}
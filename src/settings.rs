@@ -0,0 +1,185 @@
+use std::{fs, path::PathBuf, time::Duration};
+
+use bevy::{prelude::*, time::common_conditions::on_real_timer, window::WindowResized};
+use serde::{Deserialize, Serialize};
+
+use crate::{accessibility::Palette, config::Configuration};
+
+const SAVE_PERIOD: Duration = Duration::from_secs(2);
+
+/// Persists the [`UserSettings`] to `~/.config/gravity-war/config.toml`
+/// (or the platform equivalent), written back when they change and on exit.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                track_window_size,
+                track_configuration.run_if(resource_changed::<Configuration>),
+                save_settings
+                    .run_if(on_real_timer(SAVE_PERIOD).and(resource_changed::<UserSettings>)),
+            )
+                .chain(),
+        )
+        .add_systems(Last, save_settings.run_if(on_event::<AppExit>));
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct UserSettings {
+    pub window: WindowSettings,
+    pub audio: AudioSettings,
+    pub input: InputSettings,
+    pub accessibility: AccessibilitySettings,
+    /// One of the [`crate::localization::LANGUAGES`], `None` for the default one.
+    pub language: Option<String>,
+    /// Last state of the gameplay [`Configuration`], edited in the inspector.
+    pub gameplay: Option<Configuration>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+    pub vsync: bool,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+            vsync: true,
+        }
+    }
+}
+
+/// Volumes of the [`crate::mixer`] buses, between 0 and 1.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub effects_volume: f32,
+    pub master_muted: bool,
+    pub music_muted: bool,
+    pub effects_muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master_volume: 1.,
+            music_volume: 1.,
+            effects_volume: 1.,
+            master_muted: false,
+            music_muted: false,
+            effects_muted: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Colors of the factions.
+    pub palette: Palette,
+    /// Hull patterns, badges and heading line styles telling the factions
+    /// apart without their color.
+    pub faction_cues: bool,
+    /// Bright outlines, black background and larger HUD text.
+    pub high_contrast: bool,
+    /// No shaking, tumbling nor spreading effect, see [`crate::accessibility`].
+    pub reduced_motion: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct InputSettings {
+    pub select_class: KeyCode,
+    pub save_snapshot: KeyCode,
+    pub load_snapshot: KeyCode,
+    pub game_menu: KeyCode,
+    pub export_stats: KeyCode,
+    pub leaderboard: KeyCode,
+    pub switch_language: KeyCode,
+    pub mixer: KeyCode,
+    pub cycle_palette: KeyCode,
+    pub toggle_faction_cues: KeyCode,
+    pub trophies: KeyCode,
+    pub launch_missile: KeyCode,
+    pub drop_mine: KeyCode,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            select_class: KeyCode::Tab,
+            save_snapshot: KeyCode::F5,
+            load_snapshot: KeyCode::F9,
+            game_menu: KeyCode::F10,
+            export_stats: KeyCode::F7,
+            leaderboard: KeyCode::F8,
+            switch_language: KeyCode::F6,
+            mixer: KeyCode::F4,
+            cycle_palette: KeyCode::F11,
+            toggle_faction_cues: KeyCode::F12,
+            trophies: KeyCode::KeyT,
+            launch_missile: KeyCode::KeyQ,
+            drop_mine: KeyCode::KeyE,
+        }
+    }
+}
+
+impl UserSettings {
+    /// `None` on platforms without a config directory (e.g. the web).
+    fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("gravity-war").join("config.toml"))
+    }
+
+    /// Default settings when the file is missing or invalid.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        toml::from_str(&text).unwrap_or_else(|err| {
+            eprintln!("Ignoring invalid settings {}: {err}", path.display());
+            Self::default()
+        })
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        let text = toml::to_string_pretty(self).map_err(|err| err.to_string())?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        fs::write(&path, text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+}
+
+fn track_window_size(mut events: EventReader<WindowResized>, mut settings: ResMut<UserSettings>) {
+    if let Some(event) = events.read().last() {
+        settings.window.width = event.width;
+        settings.window.height = event.height;
+    }
+}
+
+fn track_configuration(configs: Res<Configuration>, mut settings: ResMut<UserSettings>) {
+    settings.gameplay = Some(configs.clone());
+}
+
+fn save_settings(settings: Res<UserSettings>) {
+    if let Err(err) = settings.save() {
+        error!("Failed to save settings: {err}");
+    }
+}
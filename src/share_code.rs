@@ -0,0 +1,133 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use bevy::prelude::*;
+
+use crate::{
+    launch::LaunchOptions,
+    localization::LocalizedText,
+    rng::GameRng,
+    scenario::{ScenarioEntity, ScenarioHandle},
+    GameState,
+};
+
+const SCENARIOS_FOLDER: &str = "scenarios/";
+const SCENARIO_EXTENSION: &str = ".scenario.ron";
+/// Base of the seed in the codes, with digits and case-insensitive letters.
+const SEED_RADIX: u32 = 36;
+
+/// Shows the [`ShareCode`] of the game, to play the same battlefield with the
+/// `--code` option or from the game menu.
+pub struct ShareCodePlugin;
+
+impl Plugin for ShareCodePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), show_share_code);
+    }
+}
+
+/// Scenario and seed of a game, e.g. `belt_defense-9IX` for the
+/// `scenarios/belt_defense.scenario.ron` scenario and the seed `12345`, or
+/// only the seed without scenario.
+///
+/// Scenarios outside of the `scenarios` folder keep the rest of their path,
+/// e.g. `mods://my_mod/scenarios/siege-9IX`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ShareCode {
+    pub scenario: Option<PathBuf>,
+    pub seed: u64,
+}
+
+impl ShareCode {
+    pub fn of(options: &LaunchOptions) -> Self {
+        Self {
+            scenario: options.scenario.clone(),
+            seed: options.seed,
+        }
+    }
+
+    /// Plays the code from the start, resetting the random generator.
+    pub fn apply(
+        self,
+        commands: &mut Commands,
+        options: &mut LaunchOptions,
+        rng: &mut GameRng,
+        next_state: &mut NextState<GameState>,
+    ) {
+        info!("Playing the share code {self}");
+        options.seed = self.seed;
+        options.scenario = self.scenario;
+        *rng = GameRng::new(options.seed);
+        // Loaded again when entering `Loading`
+        commands.remove_resource::<ScenarioHandle>();
+        next_state.set(GameState::Loading);
+    }
+}
+
+impl fmt::Display for ShareCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(scenario) = &self.scenario {
+            let path = scenario.to_string_lossy();
+            let path = path.strip_suffix(SCENARIO_EXTENSION).unwrap_or(&path);
+            let id = match path.strip_prefix(SCENARIOS_FOLDER) {
+                Some(id) if !id.contains('/') => id,
+                _ => path,
+            };
+            write!(f, "{id}-")?;
+        }
+        write!(f, "{}", to_radix(self.seed, SEED_RADIX))
+    }
+}
+
+impl FromStr for ShareCode {
+    type Err = String;
+
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let code = code.trim();
+        let (id, seed) = match code.rsplit_once('-') {
+            Some((id, seed)) => (Some(id), seed),
+            None => (None, code),
+        };
+        let seed = u64::from_str_radix(seed, SEED_RADIX)
+            .map_err(|err| format!("invalid seed {seed:?}: {err}"))?;
+        let scenario = match id {
+            Some("") => return Err("empty scenario".to_string()),
+            // Paths keep their folder
+            Some(id) if id.contains('/') => {
+                Some(PathBuf::from(format!("{id}{SCENARIO_EXTENSION}")))
+            }
+            Some(id) => Some(PathBuf::from(format!(
+                "{SCENARIOS_FOLDER}{id}{SCENARIO_EXTENSION}"
+            ))),
+            None => None,
+        };
+        Ok(Self { scenario, seed })
+    }
+}
+
+/// Upper case digits of `value` in the given base.
+fn to_radix(mut value: u64, radix: u32) -> String {
+    let mut digits = vec![];
+    loop {
+        let digit = char::from_digit((value % radix as u64) as u32, radix)
+            .expect("the digit is lower than the radix");
+        digits.push(digit.to_ascii_uppercase());
+        value /= radix as u64;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().collect()
+}
+
+fn show_share_code(mut commands: Commands, options: Res<LaunchOptions>) {
+    commands.spawn((
+        ScenarioEntity,
+        LocalizedText::new("share-code").with_arg("code", ShareCode::of(&options).to_string()),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(12.),
+            right: Val::Px(12.),
+            ..default()
+        },
+    ));
+}
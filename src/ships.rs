@@ -0,0 +1,206 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    damage::Health,
+    factions::Factions,
+    game_assets::{loaded_texture, GameAssets},
+    mods::LoadOrder,
+    ron_asset::RonAssetPlugin,
+    Faction, ShipClass, Spaceship,
+};
+
+/// Folder of the `.ship.ron` files, each one defining a ship class.
+#[cfg(not(target_arch = "wasm32"))]
+const SHIPS_FOLDER: &str = "ships";
+/// Folders can't be listed on the web, so only the bundled classes are loaded there.
+#[cfg(target_arch = "wasm32")]
+const BUNDLED_SHIPS: &[&str] = &["ships/fighter.ship.ron", "ships/cruiser.ship.ron"];
+
+/// Data-driven ship classes: adding a `.ship.ron` file to `assets/ships` adds
+/// a class, hot reloaded with the `dev-tools` feature.
+pub struct ShipsPlugin;
+
+impl Plugin for ShipsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(RonAssetPlugin::<ShipDefinition>::new(&["ship.ron"]))
+            .add_systems(Startup, load_ship_definitions)
+            .add_systems(
+                Update,
+                // Also recolors the ships
+                refresh_ship_bodies.run_if(
+                    on_event::<AssetEvent<ShipDefinition>>.or(resource_changed::<Factions>),
+                ),
+            )
+            .add_observer(apply_ship_definition);
+    }
+}
+
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct ShipDefinition {
+    /// Name of the [`ShipClass`].
+    pub name: String,
+    pub collider_half_size: Vec2,
+    pub mass: f32,
+    pub linear_damping: f32,
+    pub angular_damping: f32,
+    pub thrust: f32,
+    pub turn_rate: f32,
+    pub max_turn: f32,
+    /// Names of the weapons mounted on the ship.
+    #[serde(default)]
+    pub weapons: Vec<String>,
+    /// Positions of the turrets relative to the ship center, see [`crate::turrets`].
+    #[serde(default)]
+    pub turrets: Vec<Vec2>,
+    pub health: f32,
+    pub cost: u32,
+    /// Image in the assets folder, else the [`GameAssets`] ship tinted with the
+    /// faction color.
+    #[serde(default)]
+    pub sprite: Option<String>,
+}
+
+/// Strong handles keeping the ship definitions loaded, never read.
+#[derive(Resource)]
+#[allow(dead_code)]
+struct ShipDefinitionHandles(Vec<UntypedHandle>);
+
+/// Lookup of the loaded [`ShipDefinition`]s by class.
+#[derive(SystemParam)]
+pub struct ShipDefinitions<'w> {
+    definitions: Res<'w, Assets<ShipDefinition>>,
+    asset_server: Res<'w, AssetServer>,
+    load_order: Res<'w, LoadOrder>,
+}
+
+impl ShipDefinitions<'_> {
+    /// Definition of the last mod defining the class, if any.
+    pub fn get(&self, class: &ShipClass) -> Option<&ShipDefinition> {
+        self.definitions
+            .iter()
+            .filter(|(_, definition)| definition.name == class.0)
+            .max_by_key(|(id, _)| {
+                self.asset_server
+                    .get_path(*id)
+                    .map_or(0, |path| self.load_order.priority(&path))
+            })
+            .map(|(_, definition)| definition)
+    }
+
+    /// Class names, sorted.
+    pub fn classes(&self) -> Vec<ShipClass> {
+        let mut classes: Vec<ShipClass> = self
+            .definitions
+            .iter()
+            .map(|(_, definition)| ShipClass(definition.name.clone()))
+            .collect();
+        classes.sort_by(|a, b| a.0.cmp(&b.0));
+        // Classes overridden by mods
+        classes.dedup();
+        classes
+    }
+}
+
+fn load_ship_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    #[cfg(not(target_arch = "wasm32"))]
+    let handles = vec![asset_server.load_folder(SHIPS_FOLDER).untyped()];
+    #[cfg(target_arch = "wasm32")]
+    let handles = BUNDLED_SHIPS
+        .iter()
+        .map(|path| asset_server.load::<ShipDefinition>(*path).untyped())
+        .collect();
+    commands.insert_resource(ShipDefinitionHandles(handles));
+}
+
+/// Resources to draw the ships.
+#[derive(SystemParam)]
+struct ShipArt<'w> {
+    asset_server: Res<'w, AssetServer>,
+    game_assets: Option<Res<'w, GameAssets>>,
+    factions: Res<'w, Factions>,
+}
+
+/// Inserts the physical components and sprite of a ship of the given definition.
+fn insert_ship_body(
+    entity_commands: &mut EntityCommands,
+    definition: &ShipDefinition,
+    faction: Faction,
+    art: &ShipArt,
+) {
+    let size = definition.collider_half_size;
+    entity_commands.insert((
+        Collider::cuboid(size.x, size.y),
+        ColliderMassProperties::Mass(definition.mass),
+        Damping {
+            linear_damping: definition.linear_damping,
+            angular_damping: definition.angular_damping,
+        },
+    ));
+    let sprite = match &definition.sprite {
+        Some(path) => Some(Sprite {
+            image: art.asset_server.load(path),
+            custom_size: Some(size * 2.),
+            ..default()
+        }),
+        None => art
+            .game_assets
+            .as_ref()
+            .and_then(|assets| loaded_texture(&art.asset_server, &assets.ship))
+            .map(|image| Sprite {
+                image,
+                color: art.factions.color(faction),
+                custom_size: Some(size * 2.),
+                ..default()
+            }),
+    };
+    if let Some(sprite) = sprite {
+        entity_commands.insert(sprite);
+    }
+}
+
+fn apply_ship_definition(
+    trigger: Trigger<OnAdd, Spaceship>,
+    mut spaceships: Query<(&ShipClass, &Faction, &mut Health)>,
+    definitions: ShipDefinitions,
+    art: ShipArt,
+    mut commands: Commands,
+) {
+    let Ok((class, faction, mut health)) = spaceships.get_mut(trigger.entity()) else {
+        return;
+    };
+    match definitions.get(class) {
+        Some(definition) => {
+            // Not inserted, snapshots insert the saved health after the spawn
+            health.set_max(definition.health);
+            insert_ship_body(
+                &mut commands.entity(trigger.entity()),
+                definition,
+                *faction,
+                &art,
+            );
+        }
+        // Applied by `refresh_ship_bodies` once loaded
+        None => debug!("No definition for ship class {:?} yet", class.0),
+    }
+}
+
+/// Applies edited definitions to the living ships, and fixes ships spawned
+/// before their definition was loaded. Ships keep their fraction of health.
+fn refresh_ship_bodies(
+    mut spaceships: Query<(Entity, &ShipClass, &Faction, &mut Health), With<Spaceship>>,
+    definitions: ShipDefinitions,
+    art: ShipArt,
+    mut commands: Commands,
+) {
+    for (entity, class, faction, mut health) in spaceships.iter_mut() {
+        if let Some(definition) = definitions.get(class) {
+            health.set_max(definition.health);
+            insert_ship_body(&mut commands.entity(entity), definition, *faction, &art);
+        }
+    }
+}
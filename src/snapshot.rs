@@ -0,0 +1,297 @@
+use std::{collections::BTreeMap, fs};
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    damage::Health,
+    factions::Factions,
+    gravity::GravityWell,
+    pool::{EntityPool, Pooled},
+    rng::GameRng,
+    scenario::{spawn_planet, Planet, PlanetArt, ScenarioEntity},
+    scripting::Score,
+    settings::UserSettings,
+    spaceship_bundle,
+    weapons::{spawn_bolt, Projectile, Range},
+    Faction, GameState, ShipClass, Spaceship,
+};
+
+const SNAPSHOT_PATH: &str = "snapshot.ron";
+
+/// Saves the gameplay world with `F5` and restores it with `F9` by default.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (save_snapshot, load_snapshot).run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+/// Everything needed to rebuild a match: entities that aren't derived from
+/// others, with their physics state, the scores and the random generator.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct WorldSnapshot {
+    pub rng: Option<GameRng>,
+    pub ships: Vec<ShipSnapshot>,
+    pub projectiles: Vec<ProjectileSnapshot>,
+    /// The current planets are kept when missing.
+    pub planets: Option<Vec<PlanetSnapshot>>,
+    /// The current scores are kept when missing.
+    pub score: Option<BTreeMap<Faction, i64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ShipSnapshot {
+    pub faction: Faction,
+    pub class: ShipClass,
+    pub translation: Vec2,
+    pub rotation: f32,
+    pub linvel: Vec2,
+    pub angvel: f32,
+    /// Full health when missing.
+    #[serde(default)]
+    pub health: Option<Health>,
+}
+
+/// Flying bolt, its shooter is forgotten.
+#[derive(Serialize, Deserialize)]
+pub struct ProjectileSnapshot {
+    pub faction: Faction,
+    pub damage: f32,
+    pub origin: Vec2,
+    pub range: Range,
+    pub secs_left: f32,
+    pub translation: Vec2,
+    pub rotation: f32,
+    pub linvel: Vec2,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PlanetSnapshot {
+    pub translation: Vec2,
+    pub radius: f32,
+    pub mass: f32,
+}
+
+/// Gameplay world captured into and rebuilt from a [`WorldSnapshot`].
+#[derive(SystemParam)]
+pub struct SnapshotWorld<'w, 's> {
+    ships: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Faction,
+            &'static ShipClass,
+            &'static Transform,
+            &'static Velocity,
+            &'static Health,
+        ),
+        With<Spaceship>,
+    >,
+    projectiles: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static Projectile,
+            &'static mut Pooled,
+            &'static Transform,
+            &'static Velocity,
+        ),
+    >,
+    planets: Query<'w, 's, (Entity, &'static GravityWell, &'static Transform), With<Planet>>,
+    pub rng: ResMut<'w, GameRng>,
+    score: ResMut<'w, Score>,
+    pool: ResMut<'w, EntityPool<Projectile>>,
+    factions: Res<'w, Factions>,
+    art: PlanetArt<'w>,
+}
+
+impl WorldSnapshot {
+    pub fn capture(world: &SnapshotWorld) -> Self {
+        Self {
+            rng: Some(world.rng.clone()),
+            ships: world
+                .ships
+                .iter()
+                .map(
+                    |(_, faction, class, transform, velocity, health)| ShipSnapshot {
+                        faction: *faction,
+                        class: class.clone(),
+                        translation: transform.translation.truncate(),
+                        rotation: transform.rotation.to_euler(EulerRot::ZYX).0,
+                        linvel: velocity.linvel,
+                        angvel: velocity.angvel,
+                        health: Some(*health),
+                    },
+                )
+                .collect(),
+            projectiles: world
+                .projectiles
+                .iter()
+                .filter(|(_, _, pooled, ..)| pooled.active)
+                .map(
+                    |(_, projectile, _, transform, velocity)| ProjectileSnapshot {
+                        faction: projectile.faction,
+                        damage: projectile.damage,
+                        origin: projectile.origin,
+                        range: projectile.range,
+                        secs_left: projectile.secs_left,
+                        translation: transform.translation.truncate(),
+                        rotation: transform.rotation.to_euler(EulerRot::ZYX).0,
+                        linvel: velocity.linvel,
+                    },
+                )
+                .collect(),
+            planets: Some(
+                world
+                    .planets
+                    .iter()
+                    .map(|(_, well, transform)| PlanetSnapshot {
+                        translation: transform.translation.truncate(),
+                        radius: well.radius,
+                        mass: well.mass,
+                    })
+                    .collect(),
+            ),
+            score: Some(
+                world
+                    .score
+                    .0
+                    .iter()
+                    .map(|(faction, points)| (*faction, *points))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Replaces the current gameplay entities by the ones of the snapshot,
+    /// despawned with the rest of the scenario.
+    pub fn restore(&self, commands: &mut Commands, world: &mut SnapshotWorld) {
+        for (entity, ..) in world.ships.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        for (entity, _, mut pooled, ..) in world.projectiles.iter_mut() {
+            world.pool.release(commands, entity, &mut pooled);
+        }
+
+        if let Some(snapshot_rng) = &self.rng {
+            *world.rng = snapshot_rng.clone();
+        }
+        if let Some(score) = &self.score {
+            world.score.0 = score
+                .iter()
+                .map(|(faction, points)| (*faction, *points))
+                .collect();
+        }
+
+        if let Some(planets) = &self.planets {
+            for (entity, ..) in world.planets.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+            for planet in planets {
+                let well = GravityWell {
+                    mass: planet.mass,
+                    radius: planet.radius,
+                };
+                spawn_planet(commands, &mut world.art, planet.translation, well);
+            }
+        }
+
+        for ship in &self.ships {
+            let mut entity_commands = commands.spawn(spaceship_bundle(
+                ship.faction,
+                ship.class.clone(),
+                ship.translation.x,
+                ship.translation.y,
+            ));
+            entity_commands.insert((
+                ScenarioEntity,
+                Transform::from_translation(ship.translation.extend(0.))
+                    .with_rotation(Quat::from_rotation_z(ship.rotation)),
+                Velocity {
+                    linvel: ship.linvel,
+                    angvel: ship.angvel,
+                },
+            ));
+            if let Some(health) = ship.health {
+                entity_commands.insert(health);
+            }
+        }
+
+        for projectile in &self.projectiles {
+            spawn_bolt(
+                commands,
+                &mut world.pool,
+                &world.factions,
+                Projectile {
+                    faction: projectile.faction,
+                    shooter: None,
+                    damage: projectile.damage,
+                    origin: projectile.origin,
+                    range: projectile.range,
+                    secs_left: projectile.secs_left,
+                },
+                Transform::from_translation(projectile.translation.extend(0.))
+                    .with_rotation(Quat::from_rotation_z(projectile.rotation)),
+                projectile.linvel,
+            );
+        }
+    }
+
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(text)
+    }
+}
+
+fn save_snapshot(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    world: SnapshotWorld,
+) {
+    if !keyboard.just_pressed(settings.input.save_snapshot) {
+        return;
+    }
+
+    let result = WorldSnapshot::capture(&world)
+        .to_ron()
+        .map_err(|err| err.to_string())
+        .and_then(|text| fs::write(SNAPSHOT_PATH, text).map_err(|err| err.to_string()));
+    match result {
+        Ok(()) => info!("Saved snapshot to {SNAPSHOT_PATH}"),
+        Err(err) => error!("Failed to save snapshot to {SNAPSHOT_PATH}: {err}"),
+    }
+}
+
+fn load_snapshot(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+    mut world: SnapshotWorld,
+) {
+    if !keyboard.just_pressed(settings.input.load_snapshot) {
+        return;
+    }
+
+    let result = fs::read_to_string(SNAPSHOT_PATH)
+        .map_err(|err| err.to_string())
+        .and_then(|text| WorldSnapshot::from_ron(&text).map_err(|err| err.to_string()));
+    match result {
+        Ok(snapshot) => {
+            snapshot.restore(&mut commands, &mut world);
+            info!("Restored snapshot from {SNAPSHOT_PATH}");
+        }
+        Err(err) => error!("Failed to load snapshot from {SNAPSHOT_PATH}: {err}"),
+    }
+}
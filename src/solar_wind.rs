@@ -0,0 +1,94 @@
+use bevy::{color::palettes::css, prelude::*};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::Configuration,
+    launch,
+    scenario::{ScenarioEntity, ScenarioHandle},
+    star_system::{self, StarSystem},
+    GameState, SimulationSet, Spaceship,
+};
+
+const STAR_RADIUS: f32 = 40.;
+/// Distance at which the wind pushes with the `solar_wind` force, more when
+/// closer and less when farther.
+const REFERENCE_DISTANCE: f32 = 500.;
+
+/// Solar wind blowing from the [`Star`]s, pushing the ships outward with the
+/// `solar_wind` force of the [`Configuration`], decreasing with the distance.
+///
+/// It is a force and not an acceleration: the heavy ships are less pushed
+/// than the light ones. The games without scenario get a star at the center
+/// of their generated [`StarSystem`].
+pub struct SolarWindPlugin;
+
+impl Plugin for SolarWindPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::InGame),
+            spawn_central_star
+                .after(star_system::generate_star_system)
+                .run_if(not(resource_exists::<ScenarioHandle>)),
+        )
+        .add_systems(
+            FixedUpdate,
+            blow_solar_wind
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(
+                    any_with_component::<Star>
+                        .and(wind_enabled)
+                        .and(launch::is_authoritative),
+                ),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Star;
+
+fn wind_enabled(configs: Res<Configuration>) -> bool {
+    configs.solar_wind > 0.
+}
+
+/// Spawns a star blowing the solar wind at `position`.
+pub fn spawn_star(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    position: Vec2,
+) {
+    commands.spawn((
+        ScenarioEntity,
+        Star,
+        Mesh2d(meshes.add(Circle::new(STAR_RADIUS))),
+        MeshMaterial2d(materials.add(Color::from(css::GOLD))),
+        Transform::from_translation(position.extend(-1.)),
+    ));
+}
+
+fn spawn_central_star(
+    mut commands: Commands,
+    star_system: Res<StarSystem>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    spawn_star(&mut commands, &mut meshes, &mut materials, star_system.star);
+}
+
+fn blow_solar_wind(
+    stars: Query<&Transform, With<Star>>,
+    mut ships: Query<(&Transform, &mut ExternalImpulse), (With<Spaceship>, Without<Star>)>,
+    configs: Res<Configuration>,
+    time: Res<Time>,
+) {
+    for star in stars.iter() {
+        let center = star.translation.truncate();
+        for (transform, mut impulse) in ships.iter_mut() {
+            let offset = transform.translation.truncate() - center;
+            let distance = offset.length().max(STAR_RADIUS);
+            let force = configs.solar_wind * REFERENCE_DISTANCE / distance;
+            impulse.impulse += offset.normalize_or_zero() * force * time.delta_secs();
+        }
+    }
+}
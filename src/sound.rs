@@ -0,0 +1,295 @@
+use bevy::{
+    audio::{DefaultSpatialScale, SpatialScale, Volume},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::ShipStats,
+    mixer::{Bus, Mixed},
+    settings::UserSettings,
+    GameState, Spaceship,
+};
+
+/// Audio units per pixel, the attenuation grows with the distance in units.
+const SPATIAL_SCALE: f32 = 1. / 200.;
+/// Distance between the ears of the listener, in pixels.
+const EAR_GAP: f32 = 400.;
+/// Effects farther from the listener aren't played.
+const MAX_HEARING_DISTANCE: f32 = 3000.;
+/// Ships with an engine sound, the closest to the listener.
+const MAX_ENGINE_EMITTERS: usize = 8;
+const MUSIC_VOLUME: f32 = 0.5;
+const ENGINE_VOLUME: f32 = 0.3;
+const EFFECT_VOLUME: f32 = 0.6;
+/// Time for a music track to fade in while the other fades out.
+const CROSSFADE_SECS: f32 = 2.;
+/// Sounds of each kind started per frame, the closest to the listener, so
+/// that large battles don't saturate.
+const MAX_EFFECTS_PER_FRAME: usize = 4;
+
+/// Sounds of `assets/sounds`: a looping engine hum following the thrust of the
+/// ships, weapon fire, impact and explosion effects played from events, and
+/// the menu and battle music crossfaded with the game state.
+///
+/// Engines and effects are positioned relative to the camera, which carries the
+/// [`SpatialListener`]: their volume decreases with the distance and they are
+/// panned to their side of the screen. Their volume is set by the
+/// [`crate::mixer`] from their [`Mixed`] level.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DefaultSpatialScale(SpatialScale::new_2d(SPATIAL_SCALE)))
+            .add_event::<WeaponFired>()
+            .add_event::<Impact>()
+            .add_event::<Explosion>()
+            .add_systems(Startup, (load_sounds, start_music).chain())
+            .add_observer(add_listener)
+            .add_systems(
+                Update,
+                (
+                    crossfade_music,
+                    (assign_engine_emitters, update_engine_emitters).chain(),
+                    play_weapon_fire.run_if(on_event::<WeaponFired>),
+                    play_impacts.run_if(on_event::<Impact>),
+                    play_explosions.run_if(on_event::<Explosion>),
+                ),
+            );
+    }
+}
+
+/// A weapon fired at `position`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WeaponFired {
+    pub position: Vec2,
+}
+
+/// A projectile hit something at `position`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Impact {
+    pub position: Vec2,
+}
+
+/// A ship or asteroid was destroyed at `position`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Explosion {
+    pub position: Vec2,
+}
+
+#[derive(Resource)]
+struct Sounds {
+    engine: Handle<AudioSource>,
+    fire: Handle<AudioSource>,
+    impact: Handle<AudioSource>,
+    explosion: Handle<AudioSource>,
+    menu_music: Handle<AudioSource>,
+    battle_music: Handle<AudioSource>,
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum Music {
+    Menu,
+    Battle,
+}
+
+/// Engine hum of a ship, following it.
+#[derive(Component)]
+struct EngineEmitter {
+    ship: Entity,
+}
+
+fn load_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Sounds {
+        engine: asset_server.load("sounds/engine.wav"),
+        fire: asset_server.load("sounds/fire.wav"),
+        impact: asset_server.load("sounds/impact.wav"),
+        explosion: asset_server.load("sounds/explosion.wav"),
+        menu_music: asset_server.load("sounds/menu.wav"),
+        battle_music: asset_server.load("sounds/battle.wav"),
+    });
+}
+
+/// Both music tracks always play, the silent one is faded in when needed.
+fn start_music(mut commands: Commands, sounds: Res<Sounds>) {
+    let silent_loop = PlaybackSettings::LOOP.with_volume(Volume::new(0.));
+    commands.spawn((
+        Music::Menu,
+        Mixed::new(Bus::Music, 0.),
+        AudioPlayer::new(sounds.menu_music.clone()),
+        silent_loop,
+    ));
+    commands.spawn((
+        Music::Battle,
+        Mixed::new(Bus::Music, 0.),
+        AudioPlayer::new(sounds.battle_music.clone()),
+        silent_loop,
+    ));
+}
+
+fn add_listener(trigger: Trigger<OnAdd, Camera2d>, mut commands: Commands) {
+    commands
+        .entity(trigger.entity())
+        .insert(SpatialListener::new(EAR_GAP));
+}
+
+fn listener_position(listeners: &Query<&GlobalTransform, With<SpatialListener>>) -> Vec2 {
+    listeners
+        .get_single()
+        .map_or(Vec2::ZERO, |transform| transform.translation().truncate())
+}
+
+fn crossfade_music(
+    state: Res<State<GameState>>,
+    mut tracks: Query<(&Music, &mut Mixed)>,
+    time: Res<Time<Real>>,
+) {
+    let current = if *state.get() == GameState::InGame {
+        Music::Battle
+    } else {
+        Music::Menu
+    };
+    let step = MUSIC_VOLUME * time.delta_secs() / CROSSFADE_SECS;
+    for (music, mut mixed) in tracks.iter_mut() {
+        let target = if *music == current { MUSIC_VOLUME } else { 0. };
+        if mixed.level < target {
+            mixed.level = (mixed.level + step).min(target);
+        } else if mixed.level > target {
+            mixed.level = (mixed.level - step).max(target);
+        }
+    }
+}
+
+/// Moves the engine emitters to the ships closest to the listener.
+fn assign_engine_emitters(
+    mut commands: Commands,
+    sounds: Res<Sounds>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+    ships: Query<(Entity, &Transform), With<Spaceship>>,
+    emitters: Query<(Entity, &EngineEmitter)>,
+) {
+    let listener = listener_position(&listeners);
+    let mut closest: Vec<(Entity, f32)> = ships
+        .iter()
+        .map(|(ship, transform)| {
+            let distance = transform.translation.truncate().distance_squared(listener);
+            (ship, distance)
+        })
+        .collect();
+    closest.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    closest.truncate(MAX_ENGINE_EMITTERS);
+
+    for (emitter, engine) in emitters.iter() {
+        if !closest.iter().any(|(ship, _)| *ship == engine.ship) {
+            commands.entity(emitter).despawn();
+        }
+    }
+    for (ship, _) in closest {
+        if !emitters.iter().any(|(_, engine)| engine.ship == ship) {
+            commands.spawn((
+                EngineEmitter { ship },
+                Mixed::new(Bus::Effects, 0.),
+                AudioPlayer::new(sounds.engine.clone()),
+                PlaybackSettings::LOOP
+                    .with_volume(Volume::new(0.))
+                    .with_spatial(true),
+                Transform::default(),
+            ));
+        }
+    }
+}
+
+/// Louder as the ship uses more of its propulsion.
+fn update_engine_emitters(
+    mut emitters: Query<(&EngineEmitter, &mut Transform, &mut Mixed)>,
+    ships: Query<(&Transform, &ExternalForce, &ShipStats), Without<EngineEmitter>>,
+) {
+    for (engine, mut transform, mut mixed) in emitters.iter_mut() {
+        let Ok((ship_transform, force, stats)) = ships.get(engine.ship) else {
+            continue;
+        };
+        transform.translation = ship_transform.translation;
+        let throttle = if stats.propulsion_force > 0. {
+            (force.force.length() / stats.propulsion_force).min(1.)
+        } else {
+            0.
+        };
+        mixed.level = ENGINE_VOLUME * throttle;
+    }
+}
+
+/// Plays the sound at the closest positions, within hearing distance.
+fn play_effect(
+    commands: &mut Commands,
+    sound: &Handle<AudioSource>,
+    positions: impl Iterator<Item = Vec2>,
+    listener: Vec2,
+    settings: &UserSettings,
+) {
+    let volume = EFFECT_VOLUME * settings.audio.gain(Bus::Effects);
+    let mut positions: Vec<Vec2> = positions
+        .filter(|position| position.distance(listener) <= MAX_HEARING_DISTANCE)
+        .collect();
+    positions.sort_by(|a, b| a.distance(listener).total_cmp(&b.distance(listener)));
+    for position in positions.into_iter().take(MAX_EFFECTS_PER_FRAME) {
+        commands.spawn((
+            Mixed::new(Bus::Effects, EFFECT_VOLUME),
+            AudioPlayer::new(sound.clone()),
+            PlaybackSettings::DESPAWN
+                .with_volume(Volume::new(volume))
+                .with_spatial(true),
+            Transform::from_translation(position.extend(0.)),
+        ));
+    }
+}
+
+fn play_weapon_fire(
+    mut commands: Commands,
+    mut events: EventReader<WeaponFired>,
+    sounds: Res<Sounds>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+    settings: Res<UserSettings>,
+) {
+    let positions = events.read().map(|event| event.position);
+    play_effect(
+        &mut commands,
+        &sounds.fire,
+        positions,
+        listener_position(&listeners),
+        &settings,
+    );
+}
+
+fn play_impacts(
+    mut commands: Commands,
+    mut events: EventReader<Impact>,
+    sounds: Res<Sounds>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+    settings: Res<UserSettings>,
+) {
+    let positions = events.read().map(|event| event.position);
+    play_effect(
+        &mut commands,
+        &sounds.impact,
+        positions,
+        listener_position(&listeners),
+        &settings,
+    );
+}
+
+fn play_explosions(
+    mut commands: Commands,
+    mut events: EventReader<Explosion>,
+    sounds: Res<Sounds>,
+    listeners: Query<&GlobalTransform, With<SpatialListener>>,
+    settings: Res<UserSettings>,
+) {
+    let positions = events.read().map(|event| event.position);
+    play_effect(
+        &mut commands,
+        &sounds.explosion,
+        positions,
+        listener_position(&listeners),
+        &settings,
+    );
+}
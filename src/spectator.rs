@@ -0,0 +1,108 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{arrows_direction, factions::Factions, Faction, GameState, Spaceship};
+
+/// Camera speed in screen pixels per second, whatever the zoom.
+const PAN_SPEED: f32 = 800.;
+const ZOOM_STEP: f32 = 1.1;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.;
+const FOLLOW_KEYS: [KeyCode; 9] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// Camera of the spectators: free, moved with the arrows and zoomed with the
+/// mouse wheel, or following the faction of the pressed digit (`0` frees it).
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpectatorCamera>().add_systems(
+            Update,
+            (pick_followed_faction, move_camera, zoom_camera)
+                .chain()
+                .run_if(in_state(GameState::InGame)),
+        );
+    }
+}
+
+#[derive(Resource, Default)]
+struct SpectatorCamera {
+    /// `None` for the free camera.
+    followed: Option<Faction>,
+}
+
+fn pick_followed_faction(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut spectator_camera: ResMut<SpectatorCamera>,
+    factions: Res<Factions>,
+) {
+    if keyboard.just_pressed(KeyCode::Digit0) {
+        info!("Free camera");
+        spectator_camera.followed = None;
+    }
+    if let Some(index) = FOLLOW_KEYS
+        .iter()
+        .position(|key| keyboard.just_pressed(*key))
+    {
+        let faction = Faction(index as u32 + 1);
+        info!("Following {}", factions.name(faction));
+        spectator_camera.followed = Some(faction);
+    }
+}
+
+fn move_camera(
+    spectator_camera: Res<SpectatorCamera>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut cameras: Query<(&mut Transform, &OrthographicProjection), With<Camera>>,
+    spaceships: Query<(&Faction, &Transform), (With<Spaceship>, Without<Camera>)>,
+    time: Res<Time<Real>>,
+) {
+    let Ok((mut camera_transform, projection)) = cameras.get_single_mut() else {
+        return;
+    };
+
+    match spectator_camera.followed {
+        Some(followed) => {
+            let translations: Vec<Vec2> = spaceships
+                .iter()
+                .filter(|(faction, _)| **faction == followed)
+                .map(|(_, transform)| transform.translation.truncate())
+                .collect();
+            // Stays in place once the faction is eliminated
+            if !translations.is_empty() {
+                let center = translations.iter().sum::<Vec2>() / translations.len() as f32;
+                camera_transform.translation.x = center.x;
+                camera_transform.translation.y = center.y;
+            }
+        }
+        None => {
+            let direction = arrows_direction(&keyboard).normalize_or_zero();
+            let offset = direction * PAN_SPEED * projection.scale * time.delta_secs();
+            camera_transform.translation += offset.extend(0.);
+        }
+    }
+}
+
+fn zoom_camera(
+    mut wheel: EventReader<MouseWheel>,
+    mut projections: Query<&mut OrthographicProjection, With<Camera>>,
+) {
+    let scroll: f32 = wheel.read().map(|event| event.y).sum();
+    if scroll == 0. {
+        return;
+    }
+    let Ok(mut projection) = projections.get_single_mut() else {
+        return;
+    };
+    projection.scale =
+        (projection.scale * ZOOM_STEP.powf(-scroll.signum())).clamp(MIN_ZOOM, MAX_ZOOM);
+}
@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+use crate::{config::Configuration, launch, Faction, SimulationSet, Spaceship};
+
+/// Groups the ships of each faction into squads of up to `squad_size` of
+/// the [`Configuration`], as they spawn: the first ship leads the [`Squad`],
+/// the next ones join it as [`SquadMember`]s.
+///
+/// In [`crate::update_targets`], the followers attack the enemy of their
+/// leader, and fly back to it when they see no enemy. When the leader is
+/// destroyed, its followers are grouped again.
+pub struct SquadsPlugin;
+
+impl Plugin for SquadsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            form_squads
+                .before(crate::update_targets)
+                .in_set(SimulationSet)
+                .run_if(|configs: Res<Configuration>| configs.squad_size > 1)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+/// Leader of a squad.
+#[derive(Component, Default, Debug)]
+pub struct Squad {
+    /// Followers, without the leader.
+    pub members: Vec<Entity>,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SquadMember {
+    pub leader: Entity,
+}
+
+fn form_squads(
+    mut commands: Commands,
+    mut squads: Query<(Entity, &Faction, &mut Squad)>,
+    members: Query<(Entity, &Faction, &SquadMember)>,
+    unassigned: Query<(Entity, &Faction), (With<Spaceship>, Without<Squad>, Without<SquadMember>)>,
+    ships: Query<(), With<Spaceship>>,
+    configs: Res<Configuration>,
+) {
+    // Orphaned by the destruction of their leader
+    let orphans = members
+        .iter()
+        .filter(|(_, _, member)| !squads.contains(member.leader))
+        .map(|(entity, faction, _)| {
+            commands.entity(entity).remove::<SquadMember>();
+            (entity, *faction)
+        });
+    let lone_ships: Vec<(Entity, Faction)> = unassigned
+        .iter()
+        .map(|(entity, faction)| (entity, *faction))
+        .chain(orphans)
+        .collect();
+
+    let mut open_squads: Vec<(Entity, Faction, Vec<Entity>)> = squads
+        .iter_mut()
+        .map(|(leader, faction, mut squad)| {
+            squad.members.retain(|member| ships.contains(*member));
+            (leader, *faction, squad.members.clone())
+        })
+        .collect();
+    let mut new_leaders = vec![];
+    for (ship, faction) in lone_ships {
+        let open = open_squads
+            .iter_mut()
+            .find(|(_, squad_faction, followers)| {
+                *squad_faction == faction && followers.len() + 1 < configs.squad_size
+            });
+        match open {
+            Some((leader, _, followers)) => {
+                followers.push(ship);
+                commands
+                    .entity(ship)
+                    .insert(SquadMember { leader: *leader });
+            }
+            None => {
+                open_squads.push((ship, faction, vec![]));
+                new_leaders.push(ship);
+            }
+        }
+    }
+
+    for (leader, _, followers) in open_squads {
+        if new_leaders.contains(&leader) {
+            commands.entity(leader).insert(Squad { members: followers });
+        } else if let Ok((.., mut squad)) = squads.get_mut(leader) {
+            squad.members = followers;
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::{f32::consts::TAU, ops::RangeInclusive};
+
+use bevy::prelude::*;
+use rand::{seq::SliceRandom, Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{
+    comets::CometDefinition,
+    hazards::HazardDefinition,
+    launch::LaunchOptions,
+    nebula::NebulaDefinition,
+    orbits::{SatelliteDefinition, SatelliteKind},
+    scenario::{self, AsteroidBelt, PlanetDefinition},
+    GameState,
+};
+
+/// Distance from the central star to the first orbit.
+const STAR_CLEARANCE: f32 = 150.;
+/// Empty space between two consecutive orbits.
+const ORBIT_GAP: RangeInclusive<f32> = 80.0..=180.;
+const PLANET_RADIUS: RangeInclusive<f32> = 40.0..=100.;
+const SATELLITE_CHANCE: f64 = 0.5;
+/// Distance of the satellites from the surface of their planet.
+const SATELLITE_ALTITUDE: RangeInclusive<f32> = 40.0..=80.;
+const SATELLITE_RADIUS: RangeInclusive<f32> = 10.0..=20.;
+const BELT_WIDTH: RangeInclusive<f32> = 60.0..=120.;
+/// Pixels of circumference per asteroid of a belt.
+const BELT_SPACING: f32 = 40.;
+const NEBULA_RADIUS: RangeInclusive<f32> = 120.0..=250.;
+const COMET_PERIAPSIS: RangeInclusive<f32> = 100.0..=300.;
+const COMET_APOAPSIS: RangeInclusive<f32> = 900.0..=1400.;
+const COMET_PERIOD_SECS: RangeInclusive<f32> = 60.0..=120.;
+const COMET_RADIUS: RangeInclusive<f32> = 8.0..=15.;
+/// Radiation zone around the central star, within its clearance.
+const STAR_RADIATION_RADIUS: f32 = 120.;
+const ANOMALY_RADIUS: RangeInclusive<f32> = 80.0..=150.;
+
+/// Generates a [`StarSystem`] from the seed of the [`MapConfig`] when
+/// entering a game: a central star, planets and asteroid belts on orbits
+/// around it, nebulae and radiation anomalies scattered across the map, and
+/// comets passing by.
+///
+/// The games without scenario are played in the generated system, the
+/// background stars are streamed by the [`crate::starfield`].
+pub struct StarSystemPlugin;
+
+impl Plugin for StarSystemPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MapConfig>().add_systems(
+            OnEnter(GameState::InGame),
+            generate_star_system.before(scenario::spawn_planets),
+        );
+    }
+}
+
+/// Settings of the generated star systems.
+#[derive(Resource, Clone, Debug)]
+pub struct MapConfig {
+    /// Seed of the generated system, the seed of the game by default.
+    pub seed: Option<u64>,
+    /// Distance from the central star to the edge of the system.
+    pub radius: f32,
+    pub planets: RangeInclusive<usize>,
+    pub asteroid_belts: RangeInclusive<usize>,
+    pub nebulae: RangeInclusive<usize>,
+    pub comets: RangeInclusive<usize>,
+    /// Radiation zones besides the one around the star.
+    pub anomalies: RangeInclusive<usize>,
+}
+
+impl Default for MapConfig {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            radius: 1000.,
+            planets: 2..=4,
+            asteroid_belts: 1..=2,
+            nebulae: 1..=3,
+            comets: 0..=2,
+            anomalies: 0..=2,
+        }
+    }
+}
+
+/// Bodies of the generated system.
+#[derive(Resource, Debug, Default)]
+pub struct StarSystem {
+    pub star: Vec2,
+    pub planets: Vec<PlanetDefinition>,
+    pub asteroid_belts: Vec<AsteroidBelt>,
+    pub nebulae: Vec<NebulaDefinition>,
+    pub comets: Vec<CometDefinition>,
+    pub hazards: Vec<HazardDefinition>,
+}
+
+enum OrbitKind {
+    Planet,
+    AsteroidBelt,
+}
+
+impl StarSystem {
+    pub fn generate(config: &MapConfig, seed: u64) -> Self {
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+        let mut system = Self::default();
+
+        let mut orbits: Vec<OrbitKind> = std::iter::repeat_with(|| OrbitKind::Planet)
+            .take(rng.gen_range(config.planets.clone()))
+            .chain(
+                std::iter::repeat_with(|| OrbitKind::AsteroidBelt)
+                    .take(rng.gen_range(config.asteroid_belts.clone())),
+            )
+            .collect();
+        orbits.shuffle(&mut rng);
+
+        // Inner edge of the next orbit
+        let mut distance = STAR_CLEARANCE;
+        for orbit in orbits {
+            match orbit {
+                OrbitKind::Planet => {
+                    let radius = rng.gen_range(PLANET_RADIUS);
+                    let satellites = if rng.gen_bool(SATELLITE_CHANCE) {
+                        vec![SatelliteDefinition {
+                            kind: if rng.gen() {
+                                SatelliteKind::Moon
+                            } else {
+                                SatelliteKind::Station
+                            },
+                            distance: radius + rng.gen_range(SATELLITE_ALTITUDE),
+                            radius: rng.gen_range(SATELLITE_RADIUS),
+                            angle: rng.gen_range(0.0..360.),
+                        }]
+                    } else {
+                        vec![]
+                    };
+                    let extent = satellites
+                        .iter()
+                        .map(|satellite| satellite.distance + satellite.radius)
+                        .fold(radius, f32::max);
+                    if distance + 2. * extent > config.radius {
+                        continue;
+                    }
+                    let position = system.star
+                        + Vec2::from_angle(rng.gen_range(0.0..TAU)) * (distance + extent);
+                    system.planets.push(PlanetDefinition {
+                        position,
+                        radius,
+                        mass: None,
+                        satellites,
+                    });
+                    distance += 2. * extent;
+                }
+                OrbitKind::AsteroidBelt => {
+                    let width = rng.gen_range(BELT_WIDTH);
+                    if distance + width > config.radius {
+                        continue;
+                    }
+                    let circumference = (distance + width / 2.) * TAU;
+                    system.asteroid_belts.push(AsteroidBelt {
+                        center: system.star,
+                        inner_radius: distance,
+                        outer_radius: distance + width,
+                        count: (circumference / BELT_SPACING) as u32,
+                        min_size: 5.,
+                        max_size: 20.,
+                    });
+                    distance += width;
+                }
+            }
+            distance += rng.gen_range(ORBIT_GAP);
+        }
+
+        for _ in 0..rng.gen_range(config.nebulae.clone()) {
+            system.nebulae.push(NebulaDefinition {
+                position: system.star + random_in_disk(&mut rng, config.radius),
+                radius: rng.gen_range(NEBULA_RADIUS),
+                drag: rng.gen_range(1.0..=3.),
+                sensor_range: rng.gen_range(150.0..=250.),
+            });
+        }
+
+        for _ in 0..rng.gen_range(config.comets.clone()) {
+            system.comets.push(CometDefinition {
+                focus: system.star,
+                periapsis: rng.gen_range(COMET_PERIAPSIS),
+                apoapsis: rng.gen_range(COMET_APOAPSIS),
+                angle: rng.gen_range(0.0..360.),
+                period_secs: rng.gen_range(COMET_PERIOD_SECS),
+                radius: rng.gen_range(COMET_RADIUS),
+                dps: 60.,
+                phase: rng.gen(),
+            });
+        }
+
+        system.hazards.push(HazardDefinition {
+            position: system.star,
+            radius: STAR_RADIATION_RADIUS,
+            dps: 15.,
+        });
+        for _ in 0..rng.gen_range(config.anomalies.clone()) {
+            system.hazards.push(HazardDefinition {
+                position: system.star + random_in_disk(&mut rng, config.radius),
+                radius: rng.gen_range(ANOMALY_RADIUS),
+                dps: rng.gen_range(5.0..=15.),
+            });
+        }
+        system
+    }
+}
+
+/// Uniformly distributed in the disk.
+fn random_in_disk(rng: &mut impl Rng, radius: f32) -> Vec2 {
+    Vec2::from_angle(rng.gen_range(0.0..TAU)) * radius * rng.gen_range(0.0f32..1.).sqrt()
+}
+
+pub(crate) fn generate_star_system(
+    mut commands: Commands,
+    config: Res<MapConfig>,
+    options: Res<LaunchOptions>,
+) {
+    let seed = config.seed.unwrap_or(options.seed);
+    commands.insert_resource(StarSystem::generate(&config, seed));
+}
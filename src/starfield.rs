@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+
+use crate::{launch::LaunchOptions, star_system::MapConfig};
+
+/// Side of the square chunks of stars.
+const CHUNK_SIZE: f32 = 1000.;
+/// Distance from the edge of the camera view within which the chunks are
+/// spawned, and farther beyond which they are despawned.
+const SPAWN_MARGIN: f32 = 200.;
+const DESPAWN_MARGIN: f32 = 1200.;
+
+/// Layers of stars, from the farthest to the closest.
+const LAYERS: [LayerDefinition; 3] = [
+    LayerDefinition {
+        scroll: 0.1,
+        stars_per_chunk: 40,
+        star_radius: 0.6,
+        brightness: 0.4,
+    },
+    LayerDefinition {
+        scroll: 0.3,
+        stars_per_chunk: 20,
+        star_radius: 1.,
+        brightness: 0.7,
+    },
+    LayerDefinition {
+        scroll: 0.6,
+        stars_per_chunk: 8,
+        star_radius: 1.5,
+        brightness: 1.,
+    },
+];
+
+/// Background stars on parallax layers behind the arena, scrolling slower
+/// than the camera the farther they are.
+///
+/// The stars of each layer are streamed by square chunks around the camera
+/// view, random from a seed combining the seed of the map, the layer and the
+/// chunk coordinates, so a chunk seen again is the same.
+pub struct StarfieldPlugin;
+
+impl Plugin for StarfieldPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_layers).add_systems(
+            Update,
+            (parallax, stream_star_chunks)
+                .chain()
+                .after(crate::camera_follow_spaceships),
+        );
+    }
+}
+
+struct LayerDefinition {
+    /// Fraction of the moves of the camera by which the layer scrolls.
+    scroll: f32,
+    stars_per_chunk: usize,
+    star_radius: f32,
+    brightness: f32,
+}
+
+#[derive(Component)]
+struct ParallaxLayer {
+    index: usize,
+    scroll: f32,
+    stars_per_chunk: usize,
+    mesh: Handle<Mesh>,
+    material: Handle<ColorMaterial>,
+    /// Spawned chunks, children of the layer.
+    chunks: HashMap<IVec2, Entity>,
+}
+
+fn spawn_layers(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (index, layer) in LAYERS.iter().enumerate() {
+        commands.spawn((
+            ParallaxLayer {
+                index,
+                scroll: layer.scroll,
+                stars_per_chunk: layer.stars_per_chunk,
+                mesh: meshes.add(Circle::new(layer.star_radius)),
+                material: materials.add(Color::srgb(
+                    layer.brightness,
+                    layer.brightness,
+                    layer.brightness,
+                )),
+                chunks: HashMap::new(),
+            },
+            // Behind everything
+            Transform::from_xyz(0., 0., -10. + index as f32 * 0.1),
+            Visibility::default(),
+        ));
+    }
+}
+
+/// Keeps the layers behind the camera, moved by a fraction of its moves.
+fn parallax(
+    camera: Query<&Transform, (With<Camera>, Without<ParallaxLayer>)>,
+    mut layers: Query<(&ParallaxLayer, &mut Transform)>,
+) {
+    let Ok(camera_transform) = camera.get_single() else {
+        return;
+    };
+    let camera_position = camera_transform.translation.truncate();
+    for (layer, mut transform) in layers.iter_mut() {
+        let position = camera_position * (1. - layer.scroll);
+        transform.translation = position.extend(transform.translation.z);
+    }
+}
+
+fn chunk_of(position: Vec2) -> IVec2 {
+    (position / CHUNK_SIZE).floor().as_ivec2()
+}
+
+/// Chunks overlapping the area.
+fn chunks_in(area: Rect) -> impl Iterator<Item = IVec2> {
+    let min = chunk_of(area.min);
+    let max = chunk_of(area.max);
+    (min.x..=max.x).flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+}
+
+fn chunk_seed(seed: u64, layer: usize, chunk: IVec2) -> u64 {
+    let coordinates = (chunk.x as u32 as u64) << 32 | chunk.y as u32 as u64;
+    seed.wrapping_add(layer as u64) ^ coordinates
+}
+
+fn stream_star_chunks(
+    mut commands: Commands,
+    camera: Query<(&Transform, &OrthographicProjection), With<Camera>>,
+    mut layers: Query<(Entity, &mut ParallaxLayer, &Transform), Without<Camera>>,
+    mut current_seed: Local<Option<u64>>,
+    map_config: Res<MapConfig>,
+    options: Res<LaunchOptions>,
+) {
+    let Ok((camera_transform, projection)) = camera.get_single() else {
+        return;
+    };
+    // Another map, e.g. once launched from the lobby
+    let seed = map_config.seed.unwrap_or(options.seed);
+    let reseeded = *current_seed != Some(seed);
+    *current_seed = Some(seed);
+
+    for (layer_entity, mut layer, layer_transform) in layers.iter_mut() {
+        if reseeded {
+            for (_, chunk) in layer.chunks.drain() {
+                commands.entity(chunk).despawn_recursive();
+            }
+        }
+
+        // Camera view in the coordinates of the layer
+        let center = (camera_transform.translation - layer_transform.translation).truncate()
+            + projection.area.center();
+        let view = Rect::from_center_size(center, projection.area.size());
+
+        let kept: HashSet<IVec2> = chunks_in(view.inflate(DESPAWN_MARGIN)).collect();
+        layer.chunks.retain(|coordinates, chunk| {
+            let keep = kept.contains(coordinates);
+            if !keep {
+                commands.entity(*chunk).despawn_recursive();
+            }
+            keep
+        });
+
+        for coordinates in chunks_in(view.inflate(SPAWN_MARGIN)) {
+            if layer.chunks.contains_key(&coordinates) {
+                continue;
+            }
+            let mut rng =
+                Xoshiro256PlusPlus::seed_from_u64(chunk_seed(seed, layer.index, coordinates));
+            let origin = coordinates.as_vec2() * CHUNK_SIZE;
+            let chunk = commands
+                .spawn((
+                    Transform::from_translation(origin.extend(0.)),
+                    Visibility::default(),
+                ))
+                .with_children(|chunk| {
+                    for _ in 0..layer.stars_per_chunk {
+                        let position = Vec2::new(
+                            rng.gen_range(0.0..CHUNK_SIZE),
+                            rng.gen_range(0.0..CHUNK_SIZE),
+                        );
+                        chunk.spawn((
+                            Mesh2d(layer.mesh.clone()),
+                            MeshMaterial2d(layer.material.clone()),
+                            Transform::from_translation(position.extend(0.)),
+                        ));
+                    }
+                })
+                .set_parent(layer_entity)
+                .id();
+            layer.chunks.insert(coordinates, chunk);
+        }
+    }
+}
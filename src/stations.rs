@@ -0,0 +1,147 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    damage::{DamageEvent, Health},
+    docking::Dock,
+    factions::Factions,
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    sound::Impact,
+    spaceship_bundle, Faction, GameState, ShipClass, SimulationSet,
+};
+
+const STATION_SIZE: f32 = 40.;
+/// Distance from the center of the station where the reinforcements appear.
+const LAUNCH_DISTANCE: f32 = 60.;
+
+/// Stations of the `stations` of the scenario, launching a ship of their
+/// faction every `spawn_secs`.
+///
+/// Stations are fixed bodies blocking the shots and targeted by the enemy
+/// ships like them. A station whose health runs out is captured by the
+/// faction which damaged it last, producing its ships from then on.
+pub struct StationsPlugin;
+
+impl Plugin for StationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_stations)
+            .add_systems(
+                FixedUpdate,
+                (capture_stations, launch_reinforcements)
+                    .in_set(SimulationSet)
+                    .run_if(any_with_component::<Station>.and(launch::is_authoritative)),
+            )
+            .add_systems(Update, recolor_captured_stations);
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct StationDefinition {
+    pub faction: Faction,
+    pub position: Vec2,
+    /// Class of the launched ships.
+    #[serde(default)]
+    pub class: ShipClass,
+    #[serde(default = "default_spawn_secs")]
+    pub spawn_secs: f32,
+    #[serde(default = "default_health")]
+    pub health: f32,
+}
+
+fn default_spawn_secs() -> f32 {
+    20.
+}
+
+fn default_health() -> f32 {
+    500.
+}
+
+#[derive(Component)]
+pub struct Station {
+    pub class: ShipClass,
+    pub spawn_timer: Timer,
+}
+
+fn spawn_stations(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+    factions: Res<Factions>,
+) {
+    let Some(scenario) = handle.and_then(|handle| scenarios.get(&handle.0)) else {
+        return;
+    };
+    for station in &scenario.stations {
+        commands.spawn((
+            ScenarioEntity,
+            Station {
+                class: station.class.clone(),
+                spawn_timer: Timer::from_seconds(station.spawn_secs, TimerMode::Repeating),
+            },
+            station.faction,
+            Health::new(station.health),
+            Dock::station(STATION_SIZE),
+            RigidBody::Fixed,
+            Collider::cuboid(STATION_SIZE / 2., STATION_SIZE / 2.),
+            Sprite::from_color(factions.color(station.faction), Vec2::splat(STATION_SIZE)),
+            Transform::from_translation(station.position.extend(-0.5)),
+        ));
+    }
+}
+
+/// Damages the stations, handing the ones out of health over to their
+/// attacker with a full health.
+fn capture_stations(
+    mut events: EventReader<DamageEvent>,
+    mut stations: Query<(&mut Faction, &Transform, &mut Health, &mut Station)>,
+    mut impacts: EventWriter<Impact>,
+) {
+    for event in events.read() {
+        let Ok((mut faction, transform, mut health, mut station)) = stations.get_mut(event.target)
+        else {
+            continue;
+        };
+        health.current = (health.current - event.amount).max(0.);
+        let Some(attacker) = event.source.filter(|attacker| *attacker != *faction) else {
+            continue;
+        };
+        if health.current > 0. {
+            continue;
+        }
+        info!("Station captured by faction {}", attacker.0);
+        *faction = attacker;
+        health.current = health.max;
+        station.spawn_timer.reset();
+        impacts.send(Impact {
+            position: transform.translation.truncate(),
+        });
+    }
+}
+
+fn launch_reinforcements(
+    mut commands: Commands,
+    mut stations: Query<(&Faction, &Transform, &mut Station)>,
+    time: Res<Time>,
+) {
+    for (faction, transform, mut station) in stations.iter_mut() {
+        if !station.spawn_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let position = transform.translation.truncate() + Vec2::Y * LAUNCH_DISTANCE;
+        commands.spawn((
+            ScenarioEntity,
+            spaceship_bundle(*faction, station.class.clone(), position.x, position.y),
+        ));
+    }
+}
+
+fn recolor_captured_stations(
+    mut stations: Query<(&Faction, &mut Sprite), (With<Station>, Changed<Faction>)>,
+    factions: Res<Factions>,
+) {
+    for (faction, mut sprite) in stations.iter_mut() {
+        sprite.color = factions.color(*faction);
+    }
+}
@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    behavior::{AiState, PatrolRoute},
+    commander::{Order, Orders},
+    config::Configuration,
+    gravity::{self, GravityWell},
+    hazards::{self, Hazard},
+    launch, Faction, SimulationSet, Spaceship, Target,
+};
+
+const PLANNING_PERIOD: Duration = Duration::from_millis(500);
+/// Distance of the wells and hazards the ships consider using.
+const PLANNING_RADIUS: f32 = 1500.;
+/// Distance to the objective beyond which a slingshot is worth it.
+const SLINGSHOT_MIN_DISTANCE: f32 = 800.;
+/// Distance of the pass from the center of a well, in radii of the well.
+const PASS_RADII: f32 = 2.5;
+/// Speed assumed for the ships slower than that.
+const CRUISE_SPEED: f32 = 150.;
+const HORIZON_SECS: f32 = 3.;
+const SAMPLES: usize = 30;
+/// Fraction of the distance left by the direct way that a slingshot must save.
+const MIN_GAIN: f32 = 0.05;
+/// Distance beyond the edge of a hazard of the point the fleeing ships head for.
+const HAZARD_MARGIN: f32 = 80.;
+
+/// Maneuvers of the ships making use of the gravity wells and hazards, with
+/// the `gravity_tactics` of the [`Configuration`], planned every half a
+/// second.
+///
+/// The patrolling ships far from their objective sample their trajectory
+/// bent by the wells around, when heading straight for the objective or
+/// passing by each side of a well, and take the pass leaving them the
+/// closest to their objective: a slingshot.
+///
+/// The fleeing ships head for the far side of a hazard away from their
+/// pursuers, so that the pursuers chasing them cut through it.
+pub struct TacticsPlugin;
+
+impl Plugin for TacticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            plan_maneuvers
+                .after(crate::update_targets)
+                .before(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(on_timer(PLANNING_PERIOD).and(launch::is_authoritative)),
+        );
+    }
+}
+
+/// Point the ship heads for instead of its goal, if any.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Maneuver {
+    pub aim: Option<Vec2>,
+}
+
+/// Positions of a body leaving `position` at `velocity` and only accelerated
+/// by the `wells`, every `HORIZON_SECS / SAMPLES` seconds.
+fn sample_trajectory(
+    mut position: Vec2,
+    mut velocity: Vec2,
+    wells: &[(Vec2, GravityWell)],
+) -> impl Iterator<Item = Vec2> + '_ {
+    let dt = HORIZON_SECS / SAMPLES as f32;
+    (0..SAMPLES).map(move |_| {
+        velocity += gravity::acceleration_at(position, wells) * dt;
+        position += velocity * dt;
+        position
+    })
+}
+
+/// Pass by a well bringing the ship closer to the `objective` than the
+/// direct way, at its cruising `speed`.
+fn slingshot(
+    position: Vec2,
+    speed: f32,
+    objective: Vec2,
+    wells: &[(Vec2, GravityWell)],
+) -> Option<Vec2> {
+    let remaining = |aim: Vec2| {
+        let velocity = (aim - position).normalize_or_zero() * speed;
+        sample_trajectory(position, velocity, wells)
+            .map(|sample| sample.distance(objective))
+            .fold(f32::INFINITY, f32::min)
+    };
+    let direct = remaining(objective);
+    wells
+        .iter()
+        .filter(|(center, _)| center.distance(position) <= PLANNING_RADIUS)
+        .flat_map(|(center, well)| {
+            let side = (*center - position).perp().normalize_or_zero() * well.radius * PASS_RADII;
+            [*center + side, *center - side]
+        })
+        .map(|aim| (aim, remaining(aim)))
+        .filter(|(_, distance)| *distance < direct * (1. - MIN_GAIN))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(aim, _)| aim)
+}
+
+/// Far side of the closest hazard away from the pursuers at `from`.
+fn hazard_lure(position: Vec2, from: Vec2, zones: &[(Vec2, f32)]) -> Option<Vec2> {
+    let away = (position - from).normalize_or_zero();
+    zones
+        .iter()
+        .filter(|(center, radius)| {
+            center.distance(position) <= PLANNING_RADIUS
+                && center.distance(position) > *radius
+                && (*center - position).dot(away) > 0.
+        })
+        .min_by(|(a, _), (b, _)| a.distance(position).total_cmp(&b.distance(position)))
+        .map(|(center, radius)| {
+            *center + (*center - from).normalize_or_zero() * (radius + HAZARD_MARGIN)
+        })
+}
+
+fn plan_maneuvers(
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &Velocity,
+            &Target,
+            &AiState,
+            Option<&PatrolRoute>,
+            &mut Maneuver,
+        ),
+        With<Spaceship>,
+    >,
+    wells: Query<(&Transform, &GravityWell), Without<Spaceship>>,
+    hazards: Query<(&Hazard, &Transform)>,
+    orders: Res<Orders>,
+    configs: Res<Configuration>,
+) {
+    // Back to the plain goals once disabled
+    if !configs.gravity_tactics {
+        for (.., mut maneuver) in ships.iter_mut() {
+            maneuver.aim = None;
+        }
+        return;
+    }
+    let wells = gravity::wells(wells.iter());
+    let zones = hazards::zones(&hazards);
+    for (faction, transform, velocity, target, state, route, mut maneuver) in ships.iter_mut() {
+        let position = transform.translation.truncate();
+        maneuver.aim = match state {
+            AiState::Patrol => {
+                let objective = route
+                    .and_then(PatrolRoute::waypoint)
+                    .or_else(|| orders.get(*faction).map(Order::objective))
+                    .unwrap_or(target.translation.truncate());
+                (objective.distance(position) > SLINGSHOT_MIN_DISTANCE)
+                    .then(|| {
+                        let speed = velocity.linvel.length().max(CRUISE_SPEED);
+                        slingshot(position, speed, objective, &wells)
+                    })
+                    .flatten()
+            }
+            AiState::Flee { from: Some(from) } => hazard_lure(position, *from, &zones),
+            _ => None,
+        };
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    config::ShipStats, damage::DamageEvent, factions::Factions, launch, nebula, Faction,
+    SimulationSet, Target,
+};
+
+/// Seconds for the threat of an attacker to halve.
+const THREAT_HALF_LIFE_SECS: f32 = 3.;
+/// Threat below which an attacker is forgotten.
+const MIN_THREAT: f32 = 1.;
+
+/// How the ships pick their enemy in [`crate::update_targets`]: the
+/// [`TargetingPolicy`] of the ship if any, else the default of its faction
+/// in the [`TargetingPolicies`], from the `targeting` of its definition.
+///
+/// Policies other than `Nearest` choose among the enemies within the
+/// `engage_distance`, and fall back to the nearest enemy without any.
+///
+/// With `aim_lead`, the ships target the point where their bolts would
+/// intercept the enemy given its velocity, rather than its position.
+///
+/// Whatever the policy, the ships fight back the attacker within the
+/// `engage_distance` which dealt them the most damage lately, from their
+/// [`ThreatTable`].
+pub struct TargetingPlugin;
+
+impl Plugin for TargetingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetingPolicies>()
+            .add_systems(
+                Update,
+                resolve_targeting_policies.run_if(resource_changed::<Factions>),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_threats
+                    .before(crate::update_targets)
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            );
+    }
+}
+
+#[derive(Component, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum TargetingPolicy {
+    #[default]
+    Nearest,
+    /// Least health left.
+    Weakest,
+    /// Most damage per second dealt.
+    HighestThreat,
+    /// The [`Marked`] enemies first.
+    FocusFire,
+}
+
+/// Default [`TargetingPolicy`] of the factions.
+#[derive(Resource, Default, Debug)]
+pub struct TargetingPolicies(pub HashMap<Faction, TargetingPolicy>);
+
+impl TargetingPolicies {
+    pub fn get(&self, faction: Faction) -> TargetingPolicy {
+        self.0.get(&faction).copied().unwrap_or_default()
+    }
+}
+
+/// Enemy marked for the ships with the [`TargetingPolicy::FocusFire`], e.g.
+/// by the `mark(id)` of the scripts.
+#[derive(Component, Clone, Copy, Default, Debug)]
+pub struct Marked;
+
+/// Damage recently dealt to the ship by each attacker, decaying over time.
+#[derive(Component, Default, Debug)]
+pub struct ThreatTable {
+    threats: Vec<(Entity, f32)>,
+}
+
+impl ThreatTable {
+    fn add(&mut self, attacker: Entity, amount: f32) {
+        match self
+            .threats
+            .iter_mut()
+            .find(|(other, _)| *other == attacker)
+        {
+            Some((_, threat)) => *threat += amount,
+            None => self.threats.push((attacker, amount)),
+        }
+    }
+
+    fn decay(&mut self, secs: f32) {
+        let factor = 0.5f32.powf(secs / THREAT_HALF_LIFE_SECS);
+        self.threats.retain_mut(|(_, threat)| {
+            *threat *= factor;
+            *threat >= MIN_THREAT
+        });
+    }
+
+    /// Attacker which dealt the most damage lately.
+    pub fn highest(&self) -> Option<Entity> {
+        self.threats
+            .iter()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(attacker, _)| *attacker)
+    }
+}
+
+/// Ship or station which can be targeted.
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    /// Picked first among the candidates on a par.
+    pub entity: Entity,
+    pub translation: Vec3,
+    pub velocity: Vec2,
+    /// Distance within which it can be seen, see [`nebula::visibility`].
+    pub visibility: f32,
+    pub health: f32,
+    /// Damage per second it deals.
+    pub threat: f32,
+    pub marked: bool,
+}
+
+impl Candidate {
+    pub fn new(
+        entity: Entity,
+        transform: &Transform,
+        concealed: Option<&nebula::Concealed>,
+    ) -> Self {
+        Self {
+            entity,
+            translation: transform.translation,
+            velocity: Vec2::ZERO,
+            visibility: nebula::visibility(concealed),
+            health: 0.,
+            threat: 0.,
+            marked: false,
+        }
+    }
+
+    pub fn with_velocity(mut self, velocity: Vec2) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_health(mut self, health: f32) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub fn with_threat(mut self, stats: &ShipStats) -> Self {
+        self.threat = stats.projectile_damage / stats.fire_cooldown.max(f32::EPSILON);
+        self
+    }
+
+    pub fn with_mark(mut self, marked: bool) -> Self {
+        self.marked = marked;
+        self
+    }
+}
+
+/// Sorted by faction, so that the lockstep peers walk them in the same order.
+pub type CandidatesByFaction = BTreeMap<Faction, Vec<Candidate>>;
+
+/// Motion of a ship aiming ahead of its enemy.
+#[derive(Clone, Copy, Debug)]
+pub struct Lead {
+    /// Inherited by the projectiles.
+    pub velocity: Vec2,
+    pub projectile_speed: f32,
+}
+
+impl Lead {
+    /// Point where a projectile fired from `origin` meets the `enemy`, or its
+    /// position if too fast to be caught.
+    fn aim(&self, origin: Vec3, enemy: &Candidate) -> Vec3 {
+        let offset = (enemy.translation - origin).truncate();
+        let relative_velocity = enemy.velocity - self.velocity;
+        match intercept_secs(offset, relative_velocity, self.projectile_speed) {
+            Some(secs) => enemy.translation + (relative_velocity * secs).extend(0.),
+            None => enemy.translation,
+        }
+    }
+}
+
+/// Smallest positive time when a projectile of `speed` meets a target at
+/// `offset` moving with `velocity`, solving `|offset + velocity t| = speed t`.
+fn intercept_secs(offset: Vec2, velocity: Vec2, speed: f32) -> Option<f32> {
+    let a = velocity.length_squared() - speed.powi(2);
+    let b = 2. * offset.dot(velocity);
+    let c = offset.length_squared();
+    if a.abs() < f32::EPSILON {
+        return (b < 0.).then(|| -c / b);
+    }
+    let discriminant = b.powi(2) - 4. * a * c;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [(-b - root) / (2. * a), (-b + root) / (2. * a)]
+        .into_iter()
+        .filter(|secs| *secs > 0.)
+        .min_by(f32::total_cmp)
+}
+
+impl TargetingPolicy {
+    /// Enemy of `faction` picked from `translation`, among the visible ones,
+    /// aimed ahead with a `lead`.
+    pub fn select(
+        self,
+        candidates: &CandidatesByFaction,
+        faction: Faction,
+        translation: Vec3,
+        engage_distance: f32,
+        lead: Option<Lead>,
+    ) -> Option<Target> {
+        let visible: Vec<(&Candidate, f32)> = candidates
+            .iter()
+            .filter(|(candidate_faction, _)| **candidate_faction != faction)
+            .flat_map(|(_, candidates)| candidates)
+            .map(|candidate| (candidate, candidate.translation.distance(translation)))
+            .filter(|(candidate, distance)| *distance <= candidate.visibility)
+            .collect();
+        let in_range = || {
+            visible
+                .iter()
+                .filter(|(_, distance)| *distance <= engage_distance)
+        };
+        let picked = match self {
+            Self::Nearest => None,
+            Self::Weakest => in_range().min_by(|(a, _), (b, _)| {
+                a.health.total_cmp(&b.health).then(a.entity.cmp(&b.entity))
+            }),
+            Self::HighestThreat => in_range().min_by(|(a, _), (b, _)| {
+                b.threat.total_cmp(&a.threat).then(a.entity.cmp(&b.entity))
+            }),
+            Self::FocusFire => visible
+                .iter()
+                .filter(|(candidate, _)| candidate.marked)
+                .min_by(by_distance),
+        };
+        picked
+            .or_else(|| visible.iter().min_by(by_distance))
+            .map(|(candidate, _)| aim_at(candidate, translation, lead))
+    }
+}
+
+/// Nearest first, ties broken by entity.
+fn by_distance(
+    (a, a_distance): &&(&Candidate, f32),
+    (b, b_distance): &&(&Candidate, f32),
+) -> Ordering {
+    a_distance
+        .total_cmp(b_distance)
+        .then(a.entity.cmp(&b.entity))
+}
+
+/// Hostile target of a ship at `translation` on the `enemy`, aimed ahead
+/// with a `lead`.
+pub fn aim_at(enemy: &Candidate, translation: Vec3, lead: Option<Lead>) -> Target {
+    let aimed = lead.map_or(enemy.translation, |lead| lead.aim(translation, enemy));
+    Target {
+        translation: aimed,
+        distance: aimed.distance(translation),
+        hostile: true,
+    }
+}
+
+fn record_threats(
+    mut damages: EventReader<DamageEvent>,
+    mut tables: Query<&mut ThreatTable>,
+    time: Res<Time>,
+) {
+    for mut table in tables.iter_mut() {
+        table.decay(time.delta_secs());
+    }
+    for damage in damages.read() {
+        let Some(attacker) = damage.attacker else {
+            continue;
+        };
+        if let Ok(mut table) = tables.get_mut(damage.target) {
+            table.add(attacker, damage.amount);
+        }
+    }
+}
+
+fn resolve_targeting_policies(mut policies: ResMut<TargetingPolicies>, factions: Res<Factions>) {
+    policies.0 = factions
+        .definitions()
+        .map(|definition| (definition.id, definition.targeting))
+        .collect();
+}
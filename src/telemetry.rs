@@ -0,0 +1,343 @@
+use std::{collections::VecDeque, fmt::Write, fs, time::Duration};
+
+use bevy::{
+    ecs::entity::Entities, prelude::*, time::common_conditions::on_real_timer, utils::Instant,
+};
+use itertools::Itertools;
+use serde::Serialize;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::{scenario::ScenarioEnded, settings::UserSettings};
+use crate::{Faction, ShipClass, Spaceship};
+
+const SAMPLE_PERIOD: Duration = Duration::from_secs(1);
+/// Ten minutes of samples.
+const SAMPLE_CAPACITY: usize = 600;
+const EXPORT_PATH: &str = "telemetry.csv";
+/// Folder of the timestamped match statistics.
+#[cfg(not(target_arch = "wasm32"))]
+const STATS_FOLDER: &str = "stats";
+
+/// Samples match metrics every second and records the life of each ship.
+///
+/// The samples are exported as CSV when the game exits. At the end of a
+/// scenario, or with `F7` by default, the samples and the ship records are
+/// written to `stats/match-<unix time>.json` and to two CSV files next to it.
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Telemetry>()
+            .add_systems(FixedFirst, start_fixed_update_timer)
+            .add_systems(FixedLast, stop_fixed_update_timer)
+            .add_systems(
+                Update,
+                (
+                    count_frame,
+                    take_sample.run_if(on_real_timer(SAMPLE_PERIOD)),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, track_distances)
+            .add_systems(Last, export_on_exit.run_if(on_event::<AppExit>))
+            .add_observer(count_spawn)
+            .add_observer(count_death);
+
+        // There is no file system nor system clock in a browser tab
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, export_match_stats);
+    }
+}
+
+#[derive(Resource)]
+pub struct Telemetry {
+    pub samples: VecDeque<TelemetrySample>,
+    /// Every ship spawned since the start.
+    pub ships: Vec<ShipRecord>,
+    current: SampleAccumulator,
+    fixed_update_start: Option<Instant>,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(SAMPLE_CAPACITY),
+            ships: vec![],
+            current: default(),
+            fixed_update_start: None,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TelemetrySample {
+    pub elapsed_secs: f32,
+    pub entities: u32,
+    pub spaceships: u32,
+    pub frame_time_ms: f32,
+    /// Time spent in the `FixedUpdate` schedule per second of real time.
+    pub fixed_update_ms: f32,
+    pub spawns: u32,
+    pub deaths: u32,
+    /// Number of spaceships of each faction, sorted by faction.
+    pub faction_strength: Vec<(Faction, u32)>,
+}
+
+/// Life of a ship, in seconds of game time.
+#[derive(Serialize, Clone, Debug)]
+pub struct ShipRecord {
+    /// Entity of the ship, unique while it lives.
+    pub id: u64,
+    pub faction: Faction,
+    pub class: ShipClass,
+    pub spawned_secs: f32,
+    pub died_secs: Option<f32>,
+    pub distance: f32,
+}
+
+/// Index of the [`ShipRecord`] of a ship, and its last position.
+#[derive(Component)]
+struct Tracked {
+    record: usize,
+    last_position: Vec2,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Serialize)]
+struct MatchStats<'a> {
+    winner: Option<Faction>,
+    samples: &'a VecDeque<TelemetrySample>,
+    ships: &'a [ShipRecord],
+}
+
+#[derive(Default)]
+struct SampleAccumulator {
+    frames: u32,
+    frame_time: Duration,
+    fixed_update_time: Duration,
+    spawns: u32,
+    deaths: u32,
+}
+
+impl Telemetry {
+    pub fn latest(&self) -> Option<&TelemetrySample> {
+        self.samples.back()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let factions: Vec<Faction> = self
+            .samples
+            .iter()
+            .flat_map(|sample| sample.faction_strength.iter().map(|(faction, _)| *faction))
+            .unique()
+            .sorted_by_key(|faction| faction.0)
+            .collect();
+
+        let mut csv = String::from(
+            "elapsed_secs,entities,spaceships,frame_time_ms,fixed_update_ms,spawns,deaths",
+        );
+        for faction in &factions {
+            let _ = write!(csv, ",faction_{}", faction.0);
+        }
+        csv.push('\n');
+
+        for sample in &self.samples {
+            let _ = write!(
+                csv,
+                "{},{},{},{},{},{},{}",
+                sample.elapsed_secs,
+                sample.entities,
+                sample.spaceships,
+                sample.frame_time_ms,
+                sample.fixed_update_ms,
+                sample.spawns,
+                sample.deaths
+            );
+            for faction in &factions {
+                let strength = sample
+                    .faction_strength
+                    .iter()
+                    .find(|(sample_faction, _)| sample_faction == faction)
+                    .map_or(0, |(_, strength)| *strength);
+                let _ = write!(csv, ",{strength}");
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+
+    pub fn ships_to_csv(&self) -> String {
+        let mut csv = String::from("id,faction,class,spawned_secs,died_secs,distance\n");
+        for ship in &self.ships {
+            let died_secs = ship.died_secs.map(|secs| secs.to_string());
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{}",
+                ship.id,
+                ship.faction.0,
+                ship.class.0,
+                ship.spawned_secs,
+                died_secs.unwrap_or_default(),
+                ship.distance
+            );
+        }
+        csv
+    }
+}
+
+fn start_fixed_update_timer(mut telemetry: ResMut<Telemetry>) {
+    telemetry.fixed_update_start = Some(Instant::now());
+}
+
+fn stop_fixed_update_timer(mut telemetry: ResMut<Telemetry>) {
+    if let Some(start) = telemetry.fixed_update_start.take() {
+        telemetry.current.fixed_update_time += start.elapsed();
+    }
+}
+
+fn count_frame(mut telemetry: ResMut<Telemetry>, time: Res<Time<Real>>) {
+    telemetry.current.frames += 1;
+    telemetry.current.frame_time += time.delta();
+}
+
+fn count_spawn(
+    trigger: Trigger<OnAdd, Spaceship>,
+    mut commands: Commands,
+    mut telemetry: ResMut<Telemetry>,
+    spaceships: Query<(&Faction, &ShipClass, &Transform)>,
+    time: Res<Time<Virtual>>,
+) {
+    telemetry.current.spawns += 1;
+
+    let Ok((faction, class, transform)) = spaceships.get(trigger.entity()) else {
+        return;
+    };
+    commands.entity(trigger.entity()).insert(Tracked {
+        record: telemetry.ships.len(),
+        last_position: transform.translation.truncate(),
+    });
+    telemetry.ships.push(ShipRecord {
+        id: trigger.entity().to_bits(),
+        faction: *faction,
+        class: class.clone(),
+        spawned_secs: time.elapsed_secs(),
+        died_secs: None,
+        distance: 0.,
+    });
+}
+
+fn count_death(
+    trigger: Trigger<OnRemove, Spaceship>,
+    mut telemetry: ResMut<Telemetry>,
+    tracked: Query<&Tracked>,
+    time: Res<Time<Virtual>>,
+) {
+    telemetry.current.deaths += 1;
+
+    if let Ok(tracked) = tracked.get(trigger.entity()) {
+        if let Some(record) = telemetry.ships.get_mut(tracked.record) {
+            record.died_secs = Some(time.elapsed_secs());
+        }
+    }
+}
+
+fn track_distances(
+    mut telemetry: ResMut<Telemetry>,
+    mut spaceships: Query<(&mut Tracked, &Transform), With<Spaceship>>,
+) {
+    for (mut tracked, transform) in spaceships.iter_mut() {
+        let position = transform.translation.truncate();
+        let distance = position.distance(tracked.last_position);
+        tracked.last_position = position;
+        if let Some(record) = telemetry.ships.get_mut(tracked.record) {
+            record.distance += distance;
+        }
+    }
+}
+
+fn take_sample(
+    mut telemetry: ResMut<Telemetry>,
+    entities: &Entities,
+    spaceships: Query<&Faction, With<Spaceship>>,
+    time: Res<Time<Real>>,
+) {
+    let current = std::mem::take(&mut telemetry.current);
+
+    let faction_strength = spaceships
+        .iter()
+        .counts()
+        .into_iter()
+        .map(|(faction, count)| (*faction, count as u32))
+        .sorted_by_key(|(faction, _)| faction.0)
+        .collect();
+
+    let sample = TelemetrySample {
+        elapsed_secs: time.elapsed_secs(),
+        entities: entities.len(),
+        spaceships: spaceships.iter().len() as u32,
+        frame_time_ms: current.frame_time.as_secs_f32() * 1000. / current.frames.max(1) as f32,
+        fixed_update_ms: current.fixed_update_time.as_secs_f32() * 1000.,
+        spawns: current.spawns,
+        deaths: current.deaths,
+        faction_strength,
+    };
+
+    if telemetry.samples.len() == SAMPLE_CAPACITY {
+        telemetry.samples.pop_front();
+    }
+    telemetry.samples.push_back(sample);
+}
+
+fn export_on_exit(telemetry: Res<Telemetry>) {
+    if telemetry.samples.is_empty() {
+        return;
+    }
+    match fs::write(EXPORT_PATH, telemetry.to_csv()) {
+        Ok(()) => info!("Exported telemetry to {EXPORT_PATH}"),
+        Err(err) => error!("Failed to export telemetry to {EXPORT_PATH}: {err}"),
+    }
+}
+
+/// Writes the statistics at the end of the scenario or when asked.
+#[cfg(not(target_arch = "wasm32"))]
+fn export_match_stats(
+    telemetry: Res<Telemetry>,
+    mut ended: EventReader<ScenarioEnded>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    settings: Res<UserSettings>,
+) {
+    let winner = match ended.read().last() {
+        Some(event) => event.winner,
+        None if keyboard.just_pressed(settings.input.export_stats) => None,
+        None => return,
+    };
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let base = format!("{STATS_FOLDER}/match-{timestamp}");
+    let stats = MatchStats {
+        winner,
+        samples: &telemetry.samples,
+        ships: &telemetry.ships,
+    };
+    let result = serde_json::to_string_pretty(&stats)
+        .map_err(|err| err.to_string())
+        .and_then(|json| {
+            fs::create_dir_all(STATS_FOLDER).map_err(|err| err.to_string())?;
+            [
+                (format!("{base}.json"), json),
+                (format!("{base}-samples.csv"), telemetry.to_csv()),
+                (format!("{base}-ships.csv"), telemetry.ships_to_csv()),
+            ]
+            .into_iter()
+            .try_for_each(|(path, text)| {
+                fs::write(&path, text).map_err(|err| format!("{path}: {err}"))
+            })
+        });
+    match result {
+        Ok(()) => info!("Exported match statistics to {base}.json"),
+        Err(err) => error!("Failed to export match statistics: {err}"),
+    }
+}
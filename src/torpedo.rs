@@ -0,0 +1,245 @@
+use std::f32::consts::FRAC_PI_4;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    config::{Configuration, ShipStats},
+    damage::{self, DamageEvent},
+    emp::Disabled,
+    factions::Factions,
+    gravity::{self, GravityWell},
+    launch,
+    scenario::ScenarioEntity,
+    sound::{Impact, WeaponFired},
+    weapons::{self, Heat, Weapon, WeaponKind},
+    Faction, SimulationSet, Spaceship, Target,
+};
+
+const TORPEDO_SIZE: Vec2 = Vec2::new(6., 16.);
+const LIFETIME_SECS: f32 = 6.;
+/// Distance of the enemy ships hit by a torpedo.
+const HIT_RADIUS: f32 = 30.;
+/// Multiplier of the attraction of the gravity wells, heavier than for the
+/// ships for tighter slingshots.
+const GRAVITY_SCALE: f32 = 6.;
+/// Bend of the trajectory making a hit a slingshot, in radians.
+const SLINGSHOT_ANGLE: f32 = FRAC_PI_4;
+/// Distance of the launcher ahead of the ship center.
+const MUZZLE_OFFSET: f32 = 35.;
+/// Timestep of the simulated trajectories when aiming.
+const AIM_TIMESTEP: f32 = 1. / 30.;
+/// Corrections of the launch direction when aiming.
+const AIM_ITERATIONS: usize = 3;
+
+/// Ships with a [`WeaponKind::Torpedoes`] launch a heavy torpedo every
+/// `fire_cooldown` seconds at their hostile target within `weapon_range`.
+///
+/// Torpedoes are attracted by the [`GravityWell`]s more than the ships, so
+/// their trajectory bends around the planets. Unless `torpedo_aim_lead` is disabled, the ships aim so that the bent
+/// trajectory reaches the current position of the target. Hits after a bend of
+/// more than 45° are slingshots.
+pub struct TorpedoPlugin;
+
+impl Plugin for TorpedoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                // After the weapon cooldowns
+                launch_torpedoes.after(weapons::fire_weapons),
+                (move_torpedoes, hit_torpedoes)
+                    .chain()
+                    .run_if(any_with_component::<Torpedo>),
+            )
+                .chain()
+                .before(damage::resolve_damage)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+#[derive(Component)]
+pub struct Torpedo {
+    pub faction: Faction,
+    pub velocity: Vec2,
+    pub damage: f32,
+    /// Total angle the trajectory turned, in radians.
+    pub bend: f32,
+    pub secs_left: f32,
+}
+
+/// Acceleration of the wells at `position`.
+fn gravity_at(position: Vec2, wells: &[(Vec2, GravityWell)]) -> Vec2 {
+    gravity::acceleration_at(position, wells) * GRAVITY_SCALE
+}
+
+/// Closest approach to `target` of a torpedo launched from `origin` with
+/// `velocity`, relative to the target.
+fn miss_offset(
+    origin: Vec2,
+    mut velocity: Vec2,
+    target: Vec2,
+    wells: &[(Vec2, GravityWell)],
+) -> Vec2 {
+    let mut position = origin;
+    let mut closest = target - position;
+    for _ in 0..(LIFETIME_SECS / AIM_TIMESTEP) as usize {
+        velocity += gravity_at(position, wells) * AIM_TIMESTEP;
+        position += velocity * AIM_TIMESTEP;
+        if (target - position).length() < closest.length() {
+            closest = target - position;
+        }
+    }
+    -closest
+}
+
+/// Launch direction reaching `target` once bent by the planets, corrected from
+/// the straight direction by the angle of the simulated misses.
+fn lead_aim(
+    origin: Vec2,
+    target: Vec2,
+    speed: f32,
+    ship_velocity: Vec2,
+    wells: &[(Vec2, GravityWell)],
+) -> Vec2 {
+    let aimed = target - origin;
+    let mut direction = aimed.normalize_or(Vec2::Y);
+    for _ in 0..AIM_ITERATIONS {
+        let miss = miss_offset(origin, direction * speed + ship_velocity, target, wells);
+        let correction = (aimed + miss).angle_to(aimed);
+        direction = Vec2::from_angle(correction).rotate(direction);
+    }
+    direction
+}
+
+fn launch_torpedoes(
+    mut commands: Commands,
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &Velocity,
+            &ShipStats,
+            &Target,
+            &mut Weapon,
+            &mut Heat,
+            &mut Ammo,
+        ),
+        (With<Spaceship>, Without<Disabled>),
+    >,
+    wells: Query<(&Transform, &GravityWell)>,
+    configs: Res<Configuration>,
+    factions: Res<Factions>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    let wells = gravity::wells(wells.iter());
+    for (faction, transform, velocity, stats, target, mut weapon, mut heat, mut ammo) in
+        ships.iter_mut()
+    {
+        if stats.weapon != WeaponKind::Torpedoes
+            || weapon.cooldown_secs > 0.
+            || heat.overheated_secs > 0.
+            || ammo.is_empty()
+            || !target.hostile
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+
+        weapon.cooldown_secs = stats.fire_cooldown;
+        heat.current += stats.heat_per_shot;
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        ammo.spend();
+        let heading = transform.up().truncate();
+        let muzzle = transform.translation.truncate() + heading * MUZZLE_OFFSET;
+        let target_position = target.translation.truncate();
+        let direction = if configs.torpedo_aim_lead {
+            lead_aim(
+                muzzle,
+                target_position,
+                stats.torpedo_speed,
+                velocity.linvel,
+                &wells,
+            )
+        } else {
+            (target_position - muzzle).normalize_or(heading)
+        };
+        commands.spawn((
+            ScenarioEntity,
+            Torpedo {
+                faction: *faction,
+                velocity: direction * stats.torpedo_speed + velocity.linvel,
+                damage: stats.torpedo_damage,
+                bend: 0.,
+                secs_left: LIFETIME_SECS,
+            },
+            Sprite::from_color(factions.color(*faction), TORPEDO_SIZE),
+            Transform::from_translation(muzzle.extend(0.))
+                .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, direction)),
+        ));
+        fired.send(WeaponFired { position: muzzle });
+    }
+}
+
+/// Moves the torpedoes along their trajectory bent by the planets.
+fn move_torpedoes(
+    mut torpedoes: Query<(&mut Torpedo, &mut Transform)>,
+    wells: Query<(&Transform, &GravityWell), Without<Torpedo>>,
+    time: Res<Time>,
+) {
+    let wells = gravity::wells(wells.iter());
+    let delta_secs = time.delta_secs();
+    for (mut torpedo, mut transform) in torpedoes.iter_mut() {
+        let position = transform.translation.truncate();
+        let velocity = torpedo.velocity + gravity_at(position, &wells) * delta_secs;
+        torpedo.bend += torpedo.velocity.angle_to(velocity).abs();
+        torpedo.velocity = velocity;
+        torpedo.secs_left -= delta_secs;
+        transform.translation += (velocity * delta_secs).extend(0.);
+        transform.rotation = Quat::from_rotation_arc_2d(Vec2::Y, velocity.normalize_or(Vec2::Y));
+    }
+}
+
+/// Damages the first enemy ship close to each torpedo, and removes the
+/// torpedoes crashed on a planet or out of fuel.
+fn hit_torpedoes(
+    mut commands: Commands,
+    torpedoes: Query<(Entity, &Torpedo, &Transform)>,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    wells: Query<(&Transform, &GravityWell)>,
+    mut damages: EventWriter<DamageEvent>,
+    mut impacts: EventWriter<Impact>,
+) {
+    let wells = gravity::wells(wells.iter());
+    for (entity, torpedo, transform) in torpedoes.iter() {
+        let position = transform.translation.truncate();
+        let crashed = wells
+            .iter()
+            .any(|(center, well)| center.distance(position) <= well.radius);
+        let hit = ships.iter().find(|(_, faction, ship_transform)| {
+            **faction != torpedo.faction
+                && ship_transform.translation.truncate().distance(position) <= HIT_RADIUS
+        });
+        if let Some((ship, ..)) = hit {
+            damages.send(DamageEvent {
+                target: ship,
+                amount: torpedo.damage,
+                source: Some(torpedo.faction),
+                slingshot: torpedo.bend >= SLINGSHOT_ANGLE,
+                direction: Some(torpedo.velocity),
+                attacker: None,
+            });
+        }
+        if hit.is_some() || crashed {
+            impacts.send(Impact { position });
+        }
+        if hit.is_some() || crashed || torpedo.secs_left <= 0. {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
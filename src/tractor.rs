@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    config::ShipStats, emp::Disabled, factions::Factions, launch, Faction, SimulationSet, Spaceship,
+};
+
+/// Seconds to recharge an empty tractor beam.
+const RECHARGE_SECS: f32 = 8.;
+
+/// Ships with a `tractor_range` in their [`ShipStats`] lock the closest enemy
+/// within it and pull it toward them, e.g. into a mine or a planet.
+///
+/// The pull lasts `tractor_duration_secs` with a full energy, and the beam
+/// locks again once recharged. It is applied as an [`ExternalImpulse`] every
+/// step, the [`ExternalForce`] of the ships being overwritten by their steering.
+pub struct TractorPlugin;
+
+impl Plugin for TractorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (lock_tractor_beams, pull_tractored_ships)
+                .chain()
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, draw_tractor_beams);
+    }
+}
+
+#[derive(Component)]
+pub struct TractorBeam {
+    /// Locked ship.
+    pub target: Option<Entity>,
+    /// Between 0 and 1.
+    pub energy: f32,
+}
+
+impl Default for TractorBeam {
+    fn default() -> Self {
+        Self {
+            target: None,
+            energy: 1.,
+        }
+    }
+}
+
+/// Locks the closest enemies of the charged beams, and recharges the others.
+fn lock_tractor_beams(
+    mut beams: Query<
+        (&Faction, &Transform, &ShipStats, &mut TractorBeam),
+        (With<Spaceship>, Without<Disabled>),
+    >,
+    ships: Query<(Entity, &Faction, &Transform), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    for (faction, transform, stats, mut beam) in beams.iter_mut() {
+        if stats.tractor_range <= 0. || beam.target.is_some() {
+            continue;
+        }
+        if beam.energy < 1. {
+            beam.energy = (beam.energy + time.delta_secs() / RECHARGE_SECS).min(1.);
+            continue;
+        }
+        let position = transform.translation.truncate();
+        beam.target = ships
+            .iter()
+            .filter(|(_, ship_faction, _)| *ship_faction != faction)
+            .map(|(ship, _, ship_transform)| {
+                (
+                    ship,
+                    ship_transform.translation.truncate().distance(position),
+                )
+            })
+            .filter(|(_, distance)| *distance <= stats.tractor_range)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(ship, _)| ship);
+    }
+}
+
+/// Pulls the locked ships while the beams have energy, releasing the ships
+/// destroyed or out of range.
+fn pull_tractored_ships(
+    mut beams: Query<(&Transform, &ShipStats, &mut TractorBeam, Has<Disabled>)>,
+    mut ships: Query<(&Transform, &mut ExternalImpulse), With<Spaceship>>,
+    time: Res<Time>,
+) {
+    for (transform, stats, mut beam, disabled) in beams.iter_mut() {
+        let Some(target) = beam.target else {
+            continue;
+        };
+        let Ok((target_transform, mut impulse)) = ships.get_mut(target) else {
+            beam.target = None;
+            continue;
+        };
+        let offset = (transform.translation - target_transform.translation).truncate();
+        if disabled || beam.energy <= 0. || offset.length() > stats.tractor_range {
+            beam.target = None;
+            continue;
+        }
+
+        beam.energy = (beam.energy - time.delta_secs() / stats.tractor_duration_secs).max(0.);
+        impulse.impulse += offset.normalize_or_zero() * stats.tractor_force * time.delta_secs();
+    }
+}
+
+fn draw_tractor_beams(
+    mut gizmos: Gizmos,
+    beams: Query<(&Faction, &Transform, &TractorBeam)>,
+    ships: Query<&Transform, With<Spaceship>>,
+    factions: Res<Factions>,
+) {
+    for (faction, transform, beam) in beams.iter() {
+        let Some(target) = beam.target.and_then(|target| ships.get(target).ok()) else {
+            continue;
+        };
+        gizmos.line_2d(
+            transform.translation.truncate(),
+            target.translation.truncate(),
+            factions.color(*faction).with_alpha(0.2 + 0.6 * beam.energy),
+        );
+    }
+}
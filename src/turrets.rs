@@ -0,0 +1,150 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_rapier2d::prelude::*;
+
+use crate::{
+    ammo::Ammo,
+    config::ShipStats,
+    factions::Factions,
+    launch,
+    pool::EntityPool,
+    ships::ShipDefinitions,
+    sound::WeaponFired,
+    weapons::{fire_bolt, Projectile, Shot},
+    Faction, ShipClass, SimulationSet, Spaceship, Target,
+};
+
+const BARREL_SIZE: Vec2 = Vec2::new(4., 16.);
+const BARREL_COLOR: Color = Color::srgb(0.8, 0.8, 0.8);
+/// Turn rate of the turrets, in radians per second.
+const TURN_RATE: f32 = 3.;
+/// Largest angle between a turret and its target to fire, in radians.
+const FIRING_CONE: f32 = 0.1;
+
+/// Turrets mounted at the `turrets` positions of the
+/// [`crate::ships::ShipDefinition`], as children of the ships. They turn
+/// toward the [`Target`] of their ship whatever the heading of the hull, and
+/// fire bolts every `fire_cooldown` seconds once aligned with it, spending
+/// the [`crate::ammo::Ammo`] of their ship.
+///
+/// Turrets are mounted once the definition is loaded, edits of the positions
+/// only apply to the ships spawned afterwards.
+pub struct TurretsPlugin;
+
+impl Plugin for TurretsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, mount_turrets).add_systems(
+            FixedUpdate,
+            aim_turrets
+                // After the targets are updated
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(any_with_component::<Turret>.and(launch::is_authoritative)),
+        );
+    }
+}
+
+#[derive(Component, Default)]
+pub struct Turret {
+    /// Seconds until the next shot.
+    pub cooldown_secs: f32,
+}
+
+/// The turrets of the ship definition were mounted.
+#[derive(Component)]
+struct TurretsMounted;
+
+fn mount_turrets(
+    mut commands: Commands,
+    ships: Query<(Entity, &ShipClass), (With<Spaceship>, Without<TurretsMounted>)>,
+    definitions: ShipDefinitions,
+) {
+    for (entity, class) in ships.iter() {
+        // Mounted once loaded
+        let Some(definition) = definitions.get(class) else {
+            continue;
+        };
+        commands
+            .entity(entity)
+            .insert(TurretsMounted)
+            .with_children(|ship| {
+                for position in &definition.turrets {
+                    ship.spawn((
+                        Turret::default(),
+                        Sprite {
+                            color: BARREL_COLOR,
+                            custom_size: Some(BARREL_SIZE),
+                            anchor: Anchor::BottomCenter,
+                            ..default()
+                        },
+                        // Above the hull
+                        Transform::from_translation(position.extend(0.1)),
+                    ));
+                }
+            });
+    }
+}
+
+fn aim_turrets(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut turrets: Query<(&Parent, &mut Transform, &mut Turret), Without<Spaceship>>,
+    mut ships: Query<
+        (
+            &Faction,
+            &Transform,
+            &Velocity,
+            &ShipStats,
+            &Target,
+            &mut Ammo,
+        ),
+        With<Spaceship>,
+    >,
+    factions: Res<Factions>,
+    time: Res<Time>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    for (parent, mut transform, mut turret) in turrets.iter_mut() {
+        let Ok((faction, ship_transform, velocity, stats, target, mut ammo)) =
+            ships.get_mut(parent.get())
+        else {
+            continue;
+        };
+        turret.cooldown_secs = (turret.cooldown_secs - time.delta_secs()).max(0.);
+        if !target.hostile {
+            continue;
+        }
+
+        // The ships have no parent, their transform is global
+        let world = ship_transform.mul_transform(*transform);
+        let heading = world.up().truncate();
+        let to_target = (target.translation - world.translation).truncate();
+        let angle = heading.angle_to(to_target);
+        let max_turn = TURN_RATE * time.delta_secs();
+        transform.rotate_z(angle.clamp(-max_turn, max_turn));
+
+        if turret.cooldown_secs > 0.
+            || angle.abs() > FIRING_CONE
+            || ammo.is_empty()
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+        turret.cooldown_secs = stats.fire_cooldown;
+        ammo.spend();
+        let muzzle = world.translation.truncate() + heading * BARREL_SIZE.y;
+        fire_bolt(
+            &mut commands,
+            &mut pool,
+            &factions,
+            stats,
+            Shot {
+                faction: *faction,
+                shooter: parent.get(),
+                muzzle,
+                direction: heading,
+                ship_velocity: velocity.linvel,
+            },
+        );
+        fired.send(WeaponFired { position: muzzle });
+    }
+}
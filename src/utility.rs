@@ -0,0 +1,295 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ammo::Ammo,
+    behavior::AiState,
+    brain::{Brain, BrainScheduler},
+    config::{AiBackend, Configuration},
+    damage::Health,
+    docking::{is_friendly, occupied_slots, Dock, Docking},
+    factions::Factions,
+    influence::InfluenceMap,
+    launch,
+    personality::Personality,
+    Faction, SimulationSet, Spaceship, Target,
+};
+
+/// Score of guarding the objective, the action of the ships with nothing
+/// better to do.
+const GUARD_SCORE: f32 = 0.4;
+/// Factor of the score of orbiting, a fallback when chasing is unwise.
+const ORBIT_FACTOR: f32 = 0.8;
+/// Bonus to the score of the current action, so that the ships don't switch
+/// back and forth between two close actions.
+const COMMITMENT: f32 = 1.1;
+/// Radius of the orbits as a fraction of the `engage_distance`.
+const ORBIT_RANGE: f32 = 0.5;
+/// Angle in radians ahead on the orbit of the point the ships head for.
+const ORBIT_LEAD: f32 = 0.5;
+
+/// Utility-based alternative to the state machine of [`crate::behavior`],
+/// deciding for the ships of the factions whose `ai_backend` is
+/// [`AiBackend::Utility`].
+///
+/// When the [`BrainScheduler`] lets a ship think, each [`UtilityAction`] is
+/// scored from considerations from 0 to 1, averaged with the
+/// [`UtilityWeights`] of the [`Configuration`]: the health, the ammo, the
+/// distance to the enemy and the support of the allies around on the
+/// [`InfluenceMap`]. The best action sets the [`AiState`] steering the ship,
+/// so that both backends can be compared in the same battle.
+pub struct UtilityPlugin;
+
+impl Plugin for UtilityPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            decide_utility
+                .after(crate::update_targets)
+                .before(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        );
+    }
+}
+
+/// Weights of the considerations, ignored at 0.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct UtilityWeights {
+    pub health: f32,
+    pub ammo: f32,
+    /// Closeness of the hostile target.
+    pub distance: f32,
+    /// Share of the allies in the strength around.
+    pub support: f32,
+}
+
+impl Default for UtilityWeights {
+    fn default() -> Self {
+        Self {
+            health: 1.,
+            ammo: 1.,
+            distance: 1.,
+            support: 1.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UtilityAction {
+    /// At the enemy, when healthy, armed and supported.
+    Chase,
+    /// Around the enemy at range, when healthy but outnumbered.
+    Orbit,
+    /// Away from the enemy, when damaged and outnumbered.
+    Flee,
+    /// To a friendly dock, when damaged or out of ammo.
+    Dock,
+    /// Toward the objective, when no enemy is close.
+    Guard,
+}
+
+impl UtilityAction {
+    const ALL: [Self; 5] = [
+        Self::Chase,
+        Self::Orbit,
+        Self::Flee,
+        Self::Dock,
+        Self::Guard,
+    ];
+
+    fn of(state: AiState) -> Option<Self> {
+        match state {
+            AiState::Engage => Some(Self::Chase),
+            AiState::Orbit => Some(Self::Orbit),
+            AiState::Flee { .. } => Some(Self::Flee),
+            AiState::Dock => Some(Self::Dock),
+            AiState::Patrol => Some(Self::Guard),
+            AiState::Regroup => None,
+        }
+    }
+
+    fn state(self, target: &Target) -> AiState {
+        match self {
+            Self::Chase => AiState::Engage,
+            Self::Orbit => AiState::Orbit,
+            Self::Flee => AiState::Flee {
+                from: target.hostile.then(|| target.translation.truncate()),
+            },
+            Self::Dock => AiState::Dock,
+            Self::Guard => AiState::Patrol,
+        }
+    }
+}
+
+/// Situation of a ship, each from 0 to 1.
+struct Considerations {
+    health: f32,
+    ammo: f32,
+    /// 0 beyond the engage distance, 1 on the enemy.
+    closeness: f32,
+    support: f32,
+    engaged: bool,
+    /// Already heading for a dock, or a friendly one has a free slot.
+    can_dock: bool,
+}
+
+impl Considerations {
+    fn score(
+        &self,
+        action: UtilityAction,
+        weights: &UtilityWeights,
+        personality: &Personality,
+    ) -> f32 {
+        let Self {
+            health,
+            ammo,
+            closeness,
+            support,
+            engaged,
+            can_dock,
+        } = *self;
+        match action {
+            UtilityAction::Chase if engaged => {
+                weighted_mean(&[
+                    (weights.health, health),
+                    (weights.ammo, ammo),
+                    (weights.distance, closeness),
+                    (weights.support, support),
+                ]) * personality.aggression
+            }
+            UtilityAction::Orbit if engaged => {
+                weighted_mean(&[
+                    (weights.health, health),
+                    (weights.ammo, ammo),
+                    (weights.distance, closeness),
+                    (weights.support, 1. - support),
+                ]) * ORBIT_FACTOR
+                    * personality.caution
+            }
+            UtilityAction::Flee if engaged => {
+                weighted_mean(&[
+                    (weights.health, 1. - health),
+                    (weights.distance, closeness),
+                    (weights.support, 1. - support),
+                ]) * personality.caution
+            }
+            // Either badly damaged or out of ammo is enough
+            UtilityAction::Dock if can_dock => {
+                let need = (weights.health * (1. - health)).max(weights.ammo * (1. - ammo));
+                need / weights.health.max(weights.ammo).max(f32::EPSILON) * personality.caution
+            }
+            UtilityAction::Guard => GUARD_SCORE,
+            _ => 0.,
+        }
+    }
+}
+
+fn weighted_mean(considerations: &[(f32, f32)]) -> f32 {
+    let total: f32 = considerations.iter().map(|(weight, _)| weight).sum();
+    if total <= 0. {
+        return 0.;
+    }
+    considerations
+        .iter()
+        .map(|(weight, value)| weight * value.clamp(0., 1.))
+        .sum::<f32>()
+        / total
+}
+
+/// Point ahead of `position` on the orbit around `center`.
+pub fn orbit_point(position: Vec2, center: Vec2, configs: &Configuration) -> Vec2 {
+    let radius = configs.engage_distance * ORBIT_RANGE;
+    let offset = (position - center).try_normalize().unwrap_or(Vec2::Y);
+    center + Vec2::from_angle(ORBIT_LEAD).rotate(offset) * radius
+}
+
+fn decide_utility(
+    mut ships: Query<
+        (
+            Entity,
+            &Faction,
+            &Transform,
+            &Health,
+            &Ammo,
+            &Target,
+            &Personality,
+            &Docking,
+            &Brain,
+            &mut AiState,
+        ),
+        With<Spaceship>,
+    >,
+    docks: Query<(Entity, &Dock, Option<&Faction>), Without<Spaceship>>,
+    influence: Res<InfluenceMap>,
+    factions: Res<Factions>,
+    scheduler: Res<BrainScheduler>,
+    configs: Res<Configuration>,
+) {
+    let occupied = occupied_slots(ships.iter().map(|(.., docking, _, _)| docking));
+    for (
+        entity,
+        faction,
+        transform,
+        health,
+        ammo,
+        target,
+        personality,
+        docking,
+        brain,
+        mut state,
+    ) in ships.iter_mut()
+    {
+        let backend = factions.ai_backend(*faction).unwrap_or(configs.ai_backend);
+        if backend != AiBackend::Utility || !scheduler.is_due(brain) {
+            continue;
+        }
+        let position = transform.translation.truncate();
+        let engage_distance = configs.engage_distance * personality.aggression;
+        let allies = influence.ally_strength(position, *faction);
+        let enemies = influence.enemy_strength(position, *faction);
+        let considerations = Considerations {
+            health: health.current / health.max.max(f32::EPSILON),
+            // Unlimited at 0
+            ammo: if ammo.max > 0. {
+                ammo.current / ammo.max
+            } else {
+                1.
+            },
+            closeness: 1. - target.distance / engage_distance.max(f32::EPSILON),
+            support: if allies + enemies > 0. {
+                allies / (allies + enemies)
+            } else {
+                0.5
+            },
+            engaged: target.hostile && target.distance <= engage_distance,
+            can_dock: docking.dock().is_some()
+                || docks.iter().any(|(dock_entity, dock, dock_faction)| {
+                    is_friendly(dock_faction, *faction)
+                        && occupied.get(&dock_entity).copied().unwrap_or(0) < dock.slots
+                }),
+        };
+        let current = UtilityAction::of(*state);
+        let Some((action, _)) = UtilityAction::ALL
+            .into_iter()
+            .map(|action| {
+                let score = considerations.score(action, &configs.utility_weights, personality);
+                let commitment = if Some(action) == current {
+                    COMMITMENT
+                } else {
+                    1.
+                };
+                (action, score * commitment)
+            })
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            continue;
+        };
+        let new_state = action.state(target);
+        if *state != new_state {
+            debug!("Ship {entity} switches from {:?} to {new_state:?}", *state);
+            *state = new_state;
+        }
+    }
+}
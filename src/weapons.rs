@@ -0,0 +1,524 @@
+use bevy::{
+    ecs::system::{EntityCommands, SystemParam},
+    prelude::*,
+};
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ammo::Ammo,
+    config::ShipStats,
+    damage::{DamageEvent, Health},
+    factions::Factions,
+    launch,
+    nebula::Nebula,
+    pool::{prefill_pool, EntityPool, Pooled},
+    sound::{Impact, WeaponFired},
+    Faction, GameState, SimulationSet, Spaceship, Target,
+};
+
+/// Projectiles spawned ahead of time, more are spawned when they run out.
+const PREFILLED_PROJECTILES: usize = 256;
+const PROJECTILE_SIZE: Vec2 = Vec2::new(3., 12.);
+/// Distance of the muzzle ahead of the ship center.
+const MUZZLE_OFFSET: f32 = 35.;
+/// Largest angle between the heading of a ship and its target to fire, in radians.
+const FIRING_CONE: f32 = 0.3;
+const BEAM_WIDTH: f32 = 2.;
+
+/// Laser bolts fired by the ships at their hostile [`Target`], every
+/// `fire_cooldown` seconds of their [`ShipStats`] while it is within their
+/// `weapon_range` and ahead of them, unless their weapon overheated or ran out
+/// of [`Ammo`].
+///
+/// Bolts are pooled kinematic bodies, released when they hit a ship of
+/// another faction or an asteroid, dealing their `projectile_damage`, or
+/// another body, or after their lifetime.
+///
+/// Ships with a [`WeaponKind::Beam`] rather cast a ray toward their target,
+/// dealing `beam_dps` to the first enemy ship or asteroid on its way, unless
+/// another body blocks it.
+///
+/// Damage decreases beyond the `aim_distance` of the ship, see [`Range`].
+pub struct WeaponsPlugin;
+
+impl Plugin for WeaponsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(EntityPool::<Projectile>::new(
+            spawn_projectile,
+            activate_collisions,
+            park_projectile,
+        ))
+        .add_systems(Startup, prefill_pool::<Projectile, PREFILLED_PROJECTILES>)
+        .add_systems(
+            FixedUpdate,
+            (
+                heat_dissipation,
+                fire_weapons,
+                fire_beams,
+                handle_projectile_hits,
+                expire_projectiles,
+            )
+                .chain()
+                // After the targets are updated
+                .after(crate::apply_forces)
+                .in_set(SimulationSet)
+                .run_if(launch::is_authoritative),
+        )
+        .add_systems(Update, draw_beams)
+        .add_systems(OnExit(GameState::InGame), release_projectiles);
+    }
+}
+
+/// Weapon archetype of a ship, from the `weapons` of its
+/// [`crate::ships::ShipDefinition`] or the [`ShipStats`] overrides.
+#[derive(Reflect, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum WeaponKind {
+    /// Laser bolts.
+    #[default]
+    Bolts,
+    /// Hitscan beam.
+    Beam,
+    /// Homing missiles, see [`crate::missile`].
+    Missiles,
+    /// Pulse disabling the enemies around, see [`crate::emp`].
+    Emp,
+    /// Heavy torpedoes bent by the planets, see [`crate::torpedo`].
+    Torpedoes,
+    /// Shells bursting at the range of the target, see [`crate::flak`].
+    Flak,
+}
+
+impl WeaponKind {
+    /// Kind of a weapon name of the ship definitions, e.g. `beam`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bolts" => Some(Self::Bolts),
+            "beam" => Some(Self::Beam),
+            "missiles" => Some(Self::Missiles),
+            "emp" => Some(Self::Emp),
+            "torpedoes" => Some(Self::Torpedoes),
+            "flak" => Some(Self::Flak),
+            _ => None,
+        }
+    }
+}
+
+/// Cooldown of the weapon of a spaceship.
+#[derive(Component, Default)]
+#[require(Heat, Ammo, Beam)]
+pub struct Weapon {
+    /// Seconds until the next shot.
+    pub cooldown_secs: f32,
+}
+
+/// Heat gauge of a weapon, built by each shot and vented over time. The
+/// weapon can't fire for `overheat_secs` once it reaches 1.
+#[derive(Component, Default)]
+pub struct Heat {
+    pub current: f32,
+    /// Seconds until the overheated weapon can fire again.
+    pub overheated_secs: f32,
+}
+
+/// End of the beam of a ship while it fires one.
+#[derive(Component, Default)]
+pub struct Beam {
+    pub end: Option<Vec2>,
+}
+
+#[derive(Component)]
+pub struct Projectile {
+    pub faction: Faction,
+    /// Ship which fired it.
+    pub shooter: Option<Entity>,
+    /// Damage within the optimal range.
+    pub damage: f32,
+    pub origin: Vec2,
+    pub range: Range,
+    pub secs_left: f32,
+}
+
+impl Default for Projectile {
+    fn default() -> Self {
+        Self {
+            faction: Faction(0),
+            shooter: None,
+            damage: 0.,
+            origin: Vec2::ZERO,
+            range: Range {
+                optimal: 0.,
+                max: 0.,
+            },
+            secs_left: 0.,
+        }
+    }
+}
+
+/// Weapons deal their full damage up to the `aim_distance` of the ship, then
+/// less and less up to its `weapon_range`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Range {
+    pub optimal: f32,
+    pub max: f32,
+}
+
+impl Range {
+    pub fn of(stats: &ShipStats) -> Self {
+        Self {
+            optimal: stats.aim_distance,
+            max: stats.weapon_range,
+        }
+    }
+
+    /// Factor of the damage at `distance`, decreasing linearly beyond the
+    /// optimal range.
+    pub fn falloff(&self, distance: f32) -> f32 {
+        if distance <= self.optimal {
+            1.
+        } else if distance >= self.max {
+            0.
+        } else {
+            1. - (distance - self.optimal) / (self.max - self.optimal)
+        }
+    }
+}
+
+fn spawn_projectile(commands: &mut Commands) -> Entity {
+    commands
+        .spawn((
+            Projectile::default(),
+            Pooled::default(),
+            Sprite::from_color(Color::WHITE, PROJECTILE_SIZE),
+            RigidBody::KinematicVelocityBased,
+            // Not a sensor, sensors don't detect the sensor ships
+            Collider::ball(PROJECTILE_SIZE.x),
+            ActiveEvents::COLLISION_EVENTS,
+            // Blocked by the planets and their satellites
+            ActiveCollisionTypes::default()
+                | ActiveCollisionTypes::KINEMATIC_FIXED
+                | ActiveCollisionTypes::KINEMATIC_KINEMATIC,
+            CollisionGroups::new(Group::NONE, Group::NONE),
+            Velocity::zero(),
+            Transform::default(),
+            Visibility::Hidden,
+        ))
+        .id()
+}
+
+fn activate_collisions(entity_commands: &mut EntityCommands) {
+    entity_commands.insert(CollisionGroups::default());
+}
+
+fn park_projectile(entity_commands: &mut EntityCommands) {
+    entity_commands.insert((
+        CollisionGroups::new(Group::NONE, Group::NONE),
+        Velocity::zero(),
+    ));
+}
+
+pub(crate) fn fire_weapons(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut ships: Query<
+        (
+            Entity,
+            &Faction,
+            &Transform,
+            &Velocity,
+            &ShipStats,
+            &Target,
+            &mut Weapon,
+            &mut Heat,
+            &mut Ammo,
+        ),
+        With<Spaceship>,
+    >,
+    factions: Res<Factions>,
+    time: Res<Time>,
+    mut fired: EventWriter<WeaponFired>,
+) {
+    for (entity, faction, transform, velocity, stats, target, mut weapon, mut heat, mut ammo) in
+        ships.iter_mut()
+    {
+        weapon.cooldown_secs = (weapon.cooldown_secs - time.delta_secs()).max(0.);
+        if stats.weapon != WeaponKind::Bolts
+            || weapon.cooldown_secs > 0.
+            || heat.overheated_secs > 0.
+            || ammo.is_empty()
+            || !target.hostile
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+        let heading = transform.up().truncate();
+        let to_target = (target.translation - transform.translation).truncate();
+        if heading.angle_to(to_target).abs() > FIRING_CONE {
+            continue;
+        }
+
+        weapon.cooldown_secs = stats.fire_cooldown;
+        heat.current += stats.heat_per_shot;
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        ammo.spend();
+        let muzzle = transform.translation.truncate() + heading * MUZZLE_OFFSET;
+        fire_bolt(
+            &mut commands,
+            &mut pool,
+            &factions,
+            stats,
+            Shot {
+                faction: *faction,
+                shooter: entity,
+                muzzle,
+                direction: to_target.normalize_or(heading),
+                ship_velocity: velocity.linvel,
+            },
+        );
+        fired.send(WeaponFired { position: muzzle });
+    }
+}
+
+/// Bolt fired from `muzzle`, inheriting the velocity of the ship.
+pub struct Shot {
+    pub faction: Faction,
+    pub shooter: Entity,
+    pub muzzle: Vec2,
+    pub direction: Vec2,
+    pub ship_velocity: Vec2,
+}
+
+/// Fires a bolt with the `projectile_*` stats of the ship.
+pub fn fire_bolt(
+    commands: &mut Commands,
+    pool: &mut EntityPool<Projectile>,
+    factions: &Factions,
+    stats: &ShipStats,
+    shot: Shot,
+) {
+    spawn_bolt(
+        commands,
+        pool,
+        factions,
+        Projectile {
+            faction: shot.faction,
+            shooter: Some(shot.shooter),
+            damage: stats.projectile_damage,
+            origin: shot.muzzle,
+            range: Range::of(stats),
+            // Out of range afterwards
+            secs_left: stats.weapon_range / stats.projectile_speed,
+        },
+        Transform::from_translation(shot.muzzle.extend(0.))
+            .with_rotation(Quat::from_rotation_arc_2d(Vec2::Y, shot.direction)),
+        shot.direction * stats.projectile_speed + shot.ship_velocity,
+    );
+}
+
+/// Takes a bolt from the pool, e.g. fired or restored from a snapshot.
+pub fn spawn_bolt(
+    commands: &mut Commands,
+    pool: &mut EntityPool<Projectile>,
+    factions: &Factions,
+    projectile: Projectile,
+    transform: Transform,
+    linvel: Vec2,
+) {
+    pool.acquire(commands).insert((
+        Sprite::from_color(factions.color(projectile.faction), PROJECTILE_SIZE),
+        projectile,
+        transform,
+        Velocity::linear(linvel),
+    ));
+}
+
+/// Casts the beams toward the targets, through the sensors and the ships of
+/// the same faction. Beams build as much heat per second as the bolts.
+fn fire_beams(
+    mut ships: Query<
+        (
+            Entity,
+            &Faction,
+            &Transform,
+            &ShipStats,
+            &Target,
+            &mut Beam,
+            &mut Heat,
+        ),
+        With<Spaceship>,
+    >,
+    factions: Query<&Faction, With<Spaceship>>,
+    see_through: Query<(), Or<(With<Sensor>, With<Projectile>)>>,
+    damageable: Query<(), With<Health>>,
+    rapier_context: ReadDefaultRapierContext,
+    time: Res<Time>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    for (entity, faction, transform, stats, target, mut beam, mut heat) in ships.iter_mut() {
+        beam.end = None;
+        if stats.weapon != WeaponKind::Beam
+            || heat.overheated_secs > 0.
+            || !target.hostile
+            || target.distance > stats.weapon_range
+        {
+            continue;
+        }
+        let origin = transform.translation.truncate();
+        let direction = (target.translation.truncate() - origin).normalize_or_zero();
+        if direction == Vec2::ZERO {
+            continue;
+        }
+
+        let blocks = |hit: Entity| match factions.get(hit) {
+            Ok(hit_faction) => hit_faction != faction,
+            Err(_) => !see_through.contains(hit),
+        };
+        let filter = QueryFilter::new()
+            .exclude_collider(entity)
+            .predicate(&blocks);
+        let hit = rapier_context.cast_ray(origin, direction, stats.weapon_range, true, filter);
+        let length = hit.map_or(stats.weapon_range, |(_, toi)| toi);
+        beam.end = Some(origin + direction * length);
+
+        heat.current += stats.heat_per_shot / stats.fire_cooldown * time.delta_secs();
+        if heat.current >= 1. {
+            heat.overheated_secs = stats.overheat_secs;
+        }
+        if let Some((hit, _)) = hit.filter(|(hit, _)| damageable.contains(*hit)) {
+            damages.send(DamageEvent {
+                target: hit,
+                amount: stats.beam_dps * time.delta_secs() * Range::of(stats).falloff(length),
+                source: Some(*faction),
+                slingshot: false,
+                direction: Some(direction),
+                attacker: Some(entity),
+            });
+        }
+    }
+}
+
+fn draw_beams(
+    mut gizmos: Gizmos,
+    beams: Query<(&Faction, &Transform, &Beam)>,
+    factions: Res<Factions>,
+) {
+    for (faction, transform, beam) in beams.iter() {
+        if let Some(end) = beam.end {
+            let color = factions.color(*faction);
+            let start = transform.translation.truncate();
+            gizmos.line_2d(start, end, color);
+            // Thicker than the default gizmo lines
+            let normal = (end - start).perp().normalize_or_zero() * BEAM_WIDTH / 2.;
+            gizmos.line_2d(start + normal, end + normal, color);
+            gizmos.line_2d(start - normal, end - normal, color);
+        }
+    }
+}
+
+/// Vents the heat of the weapons, faster while the ship isn't thrusting.
+fn heat_dissipation(mut heats: Query<(&mut Heat, &ShipStats, &ExternalForce)>, time: Res<Time>) {
+    for (mut heat, stats, force) in heats.iter_mut() {
+        let factor = if force.force == Vec2::ZERO {
+            stats.idle_venting_factor
+        } else {
+            1.
+        };
+        heat.current = (heat.current - stats.heat_dissipation * factor * time.delta_secs()).max(0.);
+        heat.overheated_secs = (heat.overheated_secs - time.delta_secs()).max(0.);
+    }
+}
+
+/// Bodies the projectiles collide with.
+#[derive(SystemParam)]
+pub(crate) struct HitBodies<'w, 's> {
+    ships: Query<'w, 's, &'static Faction, With<Spaceship>>,
+    damageable: Query<'w, 's, (), With<Health>>,
+    /// Crossed without a hit.
+    nebulae: Query<'w, 's, (), With<Nebula>>,
+}
+
+/// Releases the projectiles hitting anything but their own faction, damaging
+/// the ships and asteroids they hit.
+pub(crate) fn handle_projectile_hits(
+    mut commands: Commands,
+    mut events: EventReader<CollisionEvent>,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut projectiles: Query<(&Projectile, &mut Pooled, &Transform, &Velocity)>,
+    bodies: HitBodies,
+    mut impacts: EventWriter<Impact>,
+    mut damages: EventWriter<DamageEvent>,
+) {
+    let HitBodies {
+        ships,
+        damageable,
+        nebulae,
+    } = bodies;
+    for event in events.read() {
+        let CollisionEvent::Started(first, second, _) = event else {
+            continue;
+        };
+        for (entity, other) in [(*first, *second), (*second, *first)] {
+            if projectiles.contains(other) || nebulae.contains(other) {
+                continue;
+            }
+            let Ok((projectile, mut pooled, transform, velocity)) = projectiles.get_mut(entity)
+            else {
+                continue;
+            };
+            if !pooled.active {
+                continue;
+            }
+            if ships
+                .get(other)
+                .is_ok_and(|faction| *faction == projectile.faction)
+            {
+                continue;
+            }
+            // Enemy ships and asteroids
+            if damageable.contains(other) {
+                let distance = transform.translation.truncate().distance(projectile.origin);
+                damages.send(DamageEvent {
+                    target: other,
+                    amount: projectile.damage * projectile.range.falloff(distance),
+                    source: Some(projectile.faction),
+                    slingshot: false,
+                    direction: Some(velocity.linvel),
+                    attacker: projectile.shooter,
+                });
+            }
+            pool.release(&mut commands, entity, &mut pooled);
+            impacts.send(Impact {
+                position: transform.translation.truncate(),
+            });
+        }
+    }
+}
+
+fn expire_projectiles(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut projectiles: Query<(Entity, &mut Projectile, &mut Pooled)>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, mut pooled) in projectiles.iter_mut() {
+        if !pooled.active {
+            continue;
+        }
+        projectile.secs_left -= time.delta_secs();
+        if projectile.secs_left <= 0. {
+            pool.release(&mut commands, entity, &mut pooled);
+        }
+    }
+}
+
+fn release_projectiles(
+    mut commands: Commands,
+    mut pool: ResMut<EntityPool<Projectile>>,
+    mut projectiles: Query<(Entity, &mut Pooled), With<Projectile>>,
+) {
+    for (entity, mut pooled) in projectiles.iter_mut() {
+        pool.release(&mut commands, entity, &mut pooled);
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{
+    config::Configuration, launch, localization::LocalizedText, rng::GameRng, solar_wind::Star,
+    GameState, SimulationSet, Spaceship,
+};
+
+/// Seconds between the warning and the start of an event.
+const WARNING_SECS: f32 = 5.;
+const FLARE_SECS: f32 = 4.;
+/// Force pushing the ships away from the star during a flare.
+const FLARE_FORCE: f32 = 150.;
+
+/// Map-wide [`WorldEvent`]s happening every `world_event_secs` of the
+/// [`Configuration`] on average, announced by an on-screen warning a few
+/// seconds before they start.
+///
+/// A solar flare pushes all the ships away from the closest star and blinds
+/// their targeting: they keep chasing the positions known before the flare.
+pub struct WorldEventsPlugin;
+
+impl Plugin for WorldEventsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldEvents>()
+            .add_systems(OnEnter(GameState::InGame), reset_world_events)
+            .add_systems(
+                FixedUpdate,
+                (
+                    schedule_world_events
+                        .run_if(|configs: Res<Configuration>| configs.world_event_secs > 0.),
+                    blow_solar_flare.run_if(event_is_active(WorldEvent::SolarFlare)),
+                )
+                    .chain()
+                    .before(crate::update_targets)
+                    .in_set(SimulationSet)
+                    .run_if(launch::is_authoritative),
+            )
+            .add_systems(Update, show_warning);
+    }
+}
+
+/// Event affecting the whole map for a while.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldEvent {
+    SolarFlare,
+}
+
+impl WorldEvent {
+    const ALL: [Self; 1] = [Self::SolarFlare];
+
+    fn duration_secs(self) -> f32 {
+        match self {
+            Self::SolarFlare => FLARE_SECS,
+        }
+    }
+
+    /// The ships keep their targets while the event lasts.
+    fn blinds_targeting(self) -> bool {
+        match self {
+            Self::SolarFlare => true,
+        }
+    }
+
+    /// Localized text announcing the event, then shown while it lasts.
+    fn warning_id(self, active: bool) -> &'static str {
+        match (self, active) {
+            (Self::SolarFlare, false) => "world-event-solar-flare-warning",
+            (Self::SolarFlare, true) => "world-event-solar-flare",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct WorldEvents {
+    /// Until the next event is announced.
+    countdown: Timer,
+    current: Option<CurrentEvent>,
+}
+
+struct CurrentEvent {
+    event: WorldEvent,
+    warning: Timer,
+    duration: Timer,
+}
+
+impl WorldEvents {
+    /// The event going on, once its warning is over.
+    pub fn active(&self) -> Option<WorldEvent> {
+        self.current
+            .as_ref()
+            .filter(|current| current.warning.finished())
+            .map(|current| current.event)
+    }
+}
+
+fn event_is_active(event: WorldEvent) -> impl Fn(Res<WorldEvents>) -> bool {
+    move |events: Res<WorldEvents>| events.active() == Some(event)
+}
+
+/// Whether [`crate::update_targets`] is skipped, the ships keeping stale targets.
+pub fn targeting_blinded(events: Res<WorldEvents>) -> bool {
+    events.active().is_some_and(WorldEvent::blinds_targeting)
+}
+
+/// Seconds until the next event, around the configured mean.
+fn random_interval(rng: &mut GameRng, configs: &Configuration) -> Timer {
+    let secs = configs.world_event_secs * rng.gen_range(0.5..1.5);
+    Timer::from_seconds(secs, TimerMode::Once)
+}
+
+fn reset_world_events(
+    mut events: ResMut<WorldEvents>,
+    mut rng: ResMut<GameRng>,
+    configs: Res<Configuration>,
+) {
+    *events = WorldEvents {
+        countdown: random_interval(&mut rng, &configs),
+        current: None,
+    };
+}
+
+fn schedule_world_events(
+    mut events: ResMut<WorldEvents>,
+    mut rng: ResMut<GameRng>,
+    configs: Res<Configuration>,
+    stars: Query<(), With<Star>>,
+    time: Res<Time>,
+) {
+    let events = &mut *events;
+    if let Some(current) = &mut events.current {
+        if !current.warning.tick(time.delta()).finished() {
+            return;
+        }
+        if current.duration.tick(time.delta()).finished() {
+            events.current = None;
+        }
+        return;
+    }
+
+    if !events.countdown.tick(time.delta()).finished() {
+        return;
+    }
+    events.countdown = random_interval(&mut rng, &configs);
+    let possible: Vec<WorldEvent> = WorldEvent::ALL
+        .into_iter()
+        .filter(|event| match event {
+            WorldEvent::SolarFlare => !stars.is_empty(),
+        })
+        .collect();
+    let Some(&event) = possible.choose(&mut *rng) else {
+        return;
+    };
+    info!("World event {event:?} incoming");
+    events.current = Some(CurrentEvent {
+        event,
+        warning: Timer::from_seconds(WARNING_SECS, TimerMode::Once),
+        duration: Timer::from_seconds(event.duration_secs(), TimerMode::Once),
+    });
+}
+
+/// Pushes the ships away from their closest star.
+fn blow_solar_flare(
+    stars: Query<&Transform, With<Star>>,
+    mut ships: Query<(&Transform, &mut ExternalImpulse), (With<Spaceship>, Without<Star>)>,
+    time: Res<Time>,
+) {
+    for (transform, mut impulse) in ships.iter_mut() {
+        let position = transform.translation.truncate();
+        let Some(star) = stars
+            .iter()
+            .map(|star| star.translation.truncate())
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+        else {
+            return;
+        };
+        impulse.impulse += (position - star).normalize_or_zero() * FLARE_FORCE * time.delta_secs();
+    }
+}
+
+#[derive(Component)]
+struct WarningBanner;
+
+/// Shows the warning of the current event, replaced once it starts.
+fn show_warning(
+    mut commands: Commands,
+    events: Res<WorldEvents>,
+    banners: Query<Entity, With<WarningBanner>>,
+    mut shown: Local<Option<&'static str>>,
+) {
+    let warning = events
+        .current
+        .as_ref()
+        .map(|current| current.event.warning_id(current.warning.finished()));
+    if warning == *shown {
+        return;
+    }
+    *shown = warning;
+    for banner in banners.iter() {
+        commands.entity(banner).despawn_recursive();
+    }
+    if let Some(id) = warning {
+        commands.spawn((
+            WarningBanner,
+            LocalizedText::new(id),
+            TextColor(Color::srgb(1., 0.6, 0.1)),
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(48.),
+                left: Val::Percent(40.),
+                ..default()
+            },
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    launch,
+    scenario::{Scenario, ScenarioEntity, ScenarioHandle},
+    GameState, SimulationSet, Spaceship,
+};
+
+/// Seconds before a ship out of a wormhole can enter one again.
+const COOLDOWN_SECS: f32 = 1.5;
+const WORMHOLE_COLOR: Color = Color::srgb(0.6, 0.3, 1.);
+/// Turns per second of the drawn swirl.
+const SWIRL_SPEED: f32 = 0.5;
+const SWIRL_ARMS: usize = 3;
+
+/// Pairs of wormholes of the `wormholes` of the scenario. Ships entering one
+/// mouth exit the other, their heading and velocity turned by the angle
+/// between the orientations of the mouths, and can't enter another wormhole
+/// for a moment.
+///
+/// With `wormhole_targeting`, ships whose target is closer through a wormhole
+/// head for its mouth first, see [`shortcut`].
+pub struct WormholesPlugin;
+
+impl Plugin for WormholesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::InGame), spawn_wormholes)
+            .add_systems(
+                FixedUpdate,
+                (cool_down, traverse_wormholes)
+                    .chain()
+                    // After the velocities are set
+                    .after(crate::apply_forces)
+                    .in_set(SimulationSet)
+                    .run_if(any_with_component::<Wormhole>.and(launch::is_authoritative)),
+            )
+            .add_systems(Update, draw_wormholes);
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WormholeDefinition {
+    pub position: Vec2,
+    /// Orientation of the mouth, in degrees.
+    #[serde(default)]
+    pub angle: f32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct WormholePair {
+    pub radius: f32,
+    pub mouths: [WormholeDefinition; 2],
+}
+
+/// Mouth of a wormhole leading to the `exit` mouth.
+#[derive(Component)]
+pub struct Wormhole {
+    pub exit: Entity,
+    pub radius: f32,
+}
+
+/// The ship just went through a wormhole.
+#[derive(Component)]
+struct WormholeCooldown(Timer);
+
+fn spawn_wormholes(
+    mut commands: Commands,
+    handle: Option<Res<ScenarioHandle>>,
+    scenarios: Res<Assets<Scenario>>,
+) {
+    let Some(scenario) = handle.and_then(|handle| scenarios.get(&handle.0)) else {
+        return;
+    };
+    for pair in &scenario.wormholes {
+        let [first, second] = pair.mouths.each_ref().map(|mouth| {
+            commands
+                .spawn((
+                    ScenarioEntity,
+                    Transform::from_translation(mouth.position.extend(-1.))
+                        .with_rotation(Quat::from_rotation_z(mouth.angle.to_radians())),
+                ))
+                .id()
+        });
+        for (mouth, exit) in [(first, second), (second, first)] {
+            commands.entity(mouth).insert(Wormhole {
+                exit,
+                radius: pair.radius,
+            });
+        }
+    }
+}
+
+fn cool_down(
+    mut commands: Commands,
+    mut ships: Query<(Entity, &mut WormholeCooldown)>,
+    time: Res<Time>,
+) {
+    for (entity, mut cooldown) in ships.iter_mut() {
+        if cooldown.0.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<WormholeCooldown>();
+        }
+    }
+}
+
+fn traverse_wormholes(
+    mut commands: Commands,
+    wormholes: Query<(&Wormhole, &Transform), Without<Spaceship>>,
+    mut ships: Query<
+        (Entity, &mut Transform, &mut Velocity),
+        (With<Spaceship>, Without<WormholeCooldown>),
+    >,
+) {
+    for (entity, mut transform, mut velocity) in ships.iter_mut() {
+        let entered = wormholes.iter().find(|(wormhole, wormhole_transform)| {
+            wormhole_transform
+                .translation
+                .truncate()
+                .distance(transform.translation.truncate())
+                <= wormhole.radius
+        });
+        let Some((wormhole, entry_transform)) = entered else {
+            continue;
+        };
+        let Ok((_, exit_transform)) = wormholes.get(wormhole.exit) else {
+            continue;
+        };
+
+        // Same direction relative to the exit as relative to the entry
+        let turn = exit_transform.rotation * entry_transform.rotation.inverse();
+        transform.rotation = turn * transform.rotation;
+        velocity.linvel = (turn * velocity.linvel.extend(0.)).truncate();
+        // Out of the exit, so that slow ships don't go back through it
+        let heading = velocity
+            .linvel
+            .try_normalize()
+            .unwrap_or_else(|| (transform.rotation * Vec3::Y).truncate());
+        let exit = exit_transform.translation.truncate() + heading * wormhole.radius;
+        transform.translation = exit.extend(transform.translation.z);
+        commands
+            .entity(entity)
+            .insert(WormholeCooldown(Timer::from_seconds(
+                COOLDOWN_SECS,
+                TimerMode::Once,
+            )));
+    }
+}
+
+/// Mouth to go through to reach `to` from `from`, when shorter than the
+/// straight path.
+pub fn shortcut(from: Vec2, to: Vec2, mouths: &[(Vec2, Vec2)]) -> Option<Vec2> {
+    mouths
+        .iter()
+        .map(|(entry, exit)| (*entry, from.distance(*entry) + exit.distance(to)))
+        .filter(|(_, distance)| *distance < from.distance(to))
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entry, _)| entry)
+}
+
+/// Entry and exit positions of every mouth.
+pub fn mouths(wormholes: &Query<(&Wormhole, &Transform)>) -> Vec<(Vec2, Vec2)> {
+    wormholes
+        .iter()
+        .filter_map(|(wormhole, transform)| {
+            let (_, exit) = wormholes.get(wormhole.exit).ok()?;
+            Some((
+                transform.translation.truncate(),
+                exit.translation.truncate(),
+            ))
+        })
+        .collect()
+}
+
+/// Circle of the mouth with arms swirling from its orientation.
+fn draw_wormholes(wormholes: Query<(&Wormhole, &Transform)>, time: Res<Time>, mut gizmos: Gizmos) {
+    for (wormhole, transform) in wormholes.iter() {
+        let center = transform.translation.truncate();
+        gizmos.circle_2d(center, wormhole.radius, WORMHOLE_COLOR);
+        let (_, _, orientation) = transform.rotation.to_euler(EulerRot::XYZ);
+        let swirl = orientation + time.elapsed_secs() * SWIRL_SPEED * TAU;
+        for arm in 0..SWIRL_ARMS {
+            let angle = swirl + TAU * arm as f32 / SWIRL_ARMS as f32;
+            gizmos.line_2d(
+                center + Vec2::from_angle(angle) * wormhole.radius * 0.2,
+                center + Vec2::from_angle(angle + TAU / 8.) * wormhole.radius,
+                WORMHOLE_COLOR.with_alpha(0.5),
+            );
+        }
+    }
+}